@@ -486,7 +486,7 @@ impl ProjectDiagnosticsEditor {
                                         position: (excerpt_id.clone(), entry.range.start),
                                         height: diagnostic.message.matches('\n').count() as u8 + 1,
                                         style: BlockStyle::Fixed,
-                                        render: diagnostic_block_renderer(diagnostic, true),
+                                        render: diagnostic_block_renderer(diagnostic, true, false),
                                         disposition: BlockDisposition::Below,
                                     });
                                 }
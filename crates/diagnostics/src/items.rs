@@ -151,7 +151,11 @@ impl DiagnosticIndicator {
     fn go_to_next_diagnostic(&mut self, cx: &mut ViewContext<Self>) {
         if let Some(editor) = self.active_editor.as_ref().and_then(|e| e.upgrade()) {
             editor.update(cx, |editor, cx| {
-                editor.go_to_diagnostic_impl(editor::Direction::Next, cx);
+                editor.go_to_diagnostic_impl(
+                    editor::Direction::Next,
+                    lsp::DiagnosticSeverity::WARNING,
+                    cx,
+                );
             })
         }
     }
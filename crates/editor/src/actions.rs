@@ -5,12 +5,18 @@ use super::*;
 pub struct SelectNext {
     #[serde(default)]
     pub replace_newest: bool,
+    /// Match occurrences regardless of case. Only ASCII letters are folded.
+    #[serde(default)]
+    pub case_insensitive: bool,
 }
 
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct SelectPrevious {
     #[serde(default)]
     pub replace_newest: bool,
+    /// Match occurrences regardless of case. Only ASCII letters are folded.
+    #[serde(default)]
+    pub case_insensitive: bool,
 }
 
 #[derive(PartialEq, Clone, Deserialize, Default)]
@@ -59,6 +65,10 @@ pub struct ConfirmCodeAction {
 pub struct ToggleComments {
     #[serde(default)]
     pub advance_downwards: bool,
+    /// Whether to also comment the blank lines within the block, even when the
+    /// `comment_blank_lines` setting is off.
+    #[serde(default)]
+    pub comment_blank_lines: bool,
 }
 
 #[derive(PartialEq, Clone, Deserialize, Default)]
@@ -66,10 +76,54 @@ pub struct FoldAt {
     pub buffer_row: u32,
 }
 
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct FoldAtLevel {
+    pub level: u32,
+}
+
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct UnfoldAt {
     pub buffer_row: u32,
 }
+
+/// The least severe diagnostics to stop at when navigating between diagnostics.
+#[derive(PartialEq, Clone, Copy, Debug, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MinDiagnosticSeverity {
+    Error,
+    #[default]
+    Warning,
+    Information,
+    Hint,
+}
+
+impl From<MinDiagnosticSeverity> for DiagnosticSeverity {
+    fn from(severity: MinDiagnosticSeverity) -> Self {
+        match severity {
+            MinDiagnosticSeverity::Error => DiagnosticSeverity::ERROR,
+            MinDiagnosticSeverity::Warning => DiagnosticSeverity::WARNING,
+            MinDiagnosticSeverity::Information => DiagnosticSeverity::INFORMATION,
+            MinDiagnosticSeverity::Hint => DiagnosticSeverity::HINT,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct GoToDiagnosticWithSeverity {
+    #[serde(default)]
+    pub min_severity: MinDiagnosticSeverity,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct GoToPrevDiagnosticWithSeverity {
+    #[serde(default)]
+    pub min_severity: MinDiagnosticSeverity,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SetWrapColumn {
+    pub column: u32,
+}
 impl_actions!(
     editor,
     [
@@ -84,7 +138,11 @@ impl_actions!(
         ConfirmCodeAction,
         ToggleComments,
         FoldAt,
-        UnfoldAt
+        FoldAtLevel,
+        UnfoldAt,
+        SetWrapColumn,
+        GoToDiagnosticWithSeverity,
+        GoToPrevDiagnosticWithSeverity
     ]
 );
 
@@ -95,6 +153,10 @@ gpui::actions!(
         AddSelectionBelow,
         Backspace,
         Cancel,
+        CapitalizeWord,
+        CenterOnCursors,
+        ClearSelectionsContent,
+        CollapseToNewest,
         ConfirmRename,
         ContextMenuFirst,
         ContextMenuLast,
@@ -113,9 +175,13 @@ gpui::actions!(
         CopyRelativePath,
         Cut,
         CutToEndOfLine,
+        CycleLineNumbers,
         Delete,
         DeleteLine,
+        DeleteParagraph,
         DeleteToBeginningOfLine,
+        DeleteToEnclosingBracket,
+        DeleteToEndOfBufferLine,
         DeleteToEndOfLine,
         DeleteToNextSubwordEnd,
         DeleteToNextWordEnd,
@@ -123,9 +189,13 @@ gpui::actions!(
         DeleteToPreviousWordStart,
         DisplayCursorNames,
         DuplicateLine,
+        DuplicateLineDown,
         ExpandMacroRecursively,
+        ExpandToLineBoundaries,
         FindAllReferences,
         Fold,
+        FoldAll,
+        FoldRecursive,
         FoldSelectedRanges,
         Format,
         GoToDefinition,
@@ -152,6 +222,7 @@ gpui::actions!(
         MoveToBeginningOfLine,
         MoveToEnclosingBracket,
         MoveToEnd,
+        MoveToEndOfBufferLine,
         MoveToEndOfLine,
         MoveToEndOfParagraph,
         MoveToNextSubwordEnd,
@@ -169,25 +240,34 @@ gpui::actions!(
         PageDown,
         PageUp,
         Paste,
+        PasteAbove,
+        PasteBelow,
         Redo,
         RedoSelection,
         Rename,
         RestartLanguageServer,
         RevealInFinder,
         ReverseLines,
+        Rewrap,
         ScrollCursorBottom,
         ScrollCursorCenter,
         ScrollCursorTop,
         SelectAll,
         SelectAllMatches,
         SelectDown,
+        SelectEnclosingBracketPair,
+        SelectEnclosingBrackets,
+        SelectIndentBlock,
         SelectLargerSyntaxNode,
         SelectLeft,
         SelectLine,
+        SelectPageDown,
+        SelectPageUp,
         SelectRight,
         SelectSmallerSyntaxNode,
         SelectToBeginning,
         SelectToEnd,
+        SelectToEndOfBufferLine,
         SelectToEndOfParagraph,
         SelectToNextSubwordEnd,
         SelectToNextWordEnd,
@@ -195,12 +275,14 @@ gpui::actions!(
         SelectToPreviousWordStart,
         SelectToStartOfParagraph,
         SelectUp,
+        SelectWord,
         ShowCharacterPalette,
         ShowCompletions,
         ShuffleLines,
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
+        SwapSelectionEnds,
         Tab,
         TabPrev,
         ToggleInlayHints,
@@ -208,6 +290,8 @@ gpui::actions!(
         Transpose,
         Undo,
         UndoSelection,
+        UnfoldAll,
+        UnfoldAllAutomatic,
         UnfoldLines,
     ]
 );
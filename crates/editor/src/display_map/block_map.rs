@@ -949,6 +949,10 @@ impl DerefMut for BlockContext<'_, '_> {
 }
 
 impl Block {
+    pub fn id(&self) -> BlockId {
+        self.id
+    }
+
     pub fn render(&self, cx: &mut BlockContext) -> AnyElement {
         self.render.lock()(cx)
     }
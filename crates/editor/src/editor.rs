@@ -37,12 +37,12 @@ mod editor_tests;
 pub mod test;
 use ::git::diff::DiffHunk;
 pub(crate) use actions::*;
-use aho_corasick::AhoCorasick;
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
 use anyhow::{anyhow, Context as _, Result};
 use blink_manager::BlinkManager;
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
-use collections::{BTreeMap, Bound, HashMap, HashSet, VecDeque};
+use collections::{BTreeMap, BTreeSet, Bound, HashMap, HashSet, VecDeque};
 use convert_case::{Case, Casing};
 use copilot::Copilot;
 pub use display_map::DisplayPoint;
@@ -52,7 +52,8 @@ use element::LineWithInvisibles;
 pub use element::{Cursor, EditorElement, HighlightedRange, HighlightedRangeLine};
 use futures::FutureExt;
 use fuzzy::{StringMatch, StringMatchCandidate};
-use git::diff_hunk_to_display;
+pub use git::DiffMarker;
+use git::{diff_hunk_to_display, diff_hunk_to_markers};
 use gpui::{
     div, impl_actions, point, prelude::*, px, relative, rems, size, uniform_list, Action,
     AnyElement, AppContext, AsyncWindowContext, BackgroundExecutor, Bounds, ClipboardItem, Context,
@@ -73,6 +74,7 @@ use language::{
     markdown, point_from_lsp, AutoindentMode, BracketPair, Buffer, Capability, CodeAction,
     CodeLabel, Completion, CursorShape, Diagnostic, Documentation, IndentKind, IndentSize,
     Language, LanguageServerName, OffsetRangeExt, Point, Selection, SelectionGoal, TransactionId,
+    BYTE_ORDER_MARK,
 };
 
 use link_go_to_definition::{GoToDefinitionLink, InlayHighlight, LinkGoToDefinitionState};
@@ -125,12 +127,15 @@ const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 const MAX_LINE_LEN: usize = 1024;
 const MIN_NAVIGATION_HISTORY_ROW_DELTA: i64 = 10;
 const MAX_SELECTION_HISTORY_LEN: usize = 1024;
+const MAX_FOLD_HISTORY_LEN: usize = 1024;
 const COPILOT_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
 pub(crate) const CURSORS_VISIBLE_FOR: Duration = Duration::from_millis(2000);
 #[doc(hidden)]
 pub const CODE_ACTIONS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
 #[doc(hidden)]
 pub const DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
+#[doc(hidden)]
+pub const ACTIVE_LINE_ANNOTATION_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
 
 pub(crate) const FORMAT_TIMEOUT: Duration = Duration::from_secs(2);
 
@@ -309,6 +314,13 @@ pub enum SoftWrap {
     Column(u32),
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineNumbersMode {
+    Absolute,
+    Relative,
+    Hidden,
+}
+
 #[derive(Clone)]
 pub struct EditorStyle {
     pub background: Hsla,
@@ -347,6 +359,11 @@ type CompletionId = usize;
 type BackgroundHighlight = (fn(&ThemeColors) -> Hsla, Vec<Range<Anchor>>);
 type InlayBackgroundHighlight = (fn(&ThemeColors) -> Hsla, Vec<InlayHighlight>);
 
+/// Computes the annotation shown at the end of the newest cursor's line, such as the
+/// git blame for that line, given the line's buffer row.
+pub type ActiveLineAnnotationProvider =
+    Box<dyn Fn(&MultiBufferSnapshot, u32) -> Option<SharedString>>;
+
 pub struct Editor {
     handle: WeakView<Self>,
     focus_handle: FocusHandle,
@@ -358,13 +375,19 @@ pub struct Editor {
     add_selections_state: Option<AddSelectionsState>,
     select_next_state: Option<SelectNextState>,
     select_prev_state: Option<SelectNextState>,
+    text_object_state: Option<TextObjectState>,
     selection_history: SelectionHistory,
+    fold_history: FoldHistory,
+    manual_folds: Vec<Range<Anchor>>,
     autoclose_regions: Vec<AutocloseRegion>,
     snippet_stack: InvalidationStack<SnippetState>,
+    templates: HashMap<String, Arc<str>>,
     select_larger_syntax_node_stack: Vec<Box<[Selection<usize>]>>,
     ime_transaction: Option<TransactionId>,
     active_diagnostics: Option<ActiveDiagnosticGroup>,
     soft_wrap_mode_override: Option<language_settings::SoftWrap>,
+    soft_wrap_column_override: Option<u32>,
+    line_numbers_mode_override: Option<LineNumbersMode>,
     project: Option<Model<Project>>,
     completion_provider: Option<Box<dyn CompletionProvider>>,
     collaboration_hub: Option<Box<dyn CollaborationHub>>,
@@ -376,7 +399,7 @@ pub struct Editor {
     show_gutter: bool,
     show_wrap_guides: Option<bool>,
     placeholder_text: Option<Arc<str>>,
-    highlighted_rows: Option<Range<u32>>,
+    highlighted_rows: Vec<Range<u32>>,
     background_highlights: BTreeMap<TypeId, BackgroundHighlight>,
     inlay_background_highlights: TreeMap<Option<TypeId>, InlayBackgroundHighlight>,
     nav_history: Option<ItemNavHistory>,
@@ -387,9 +410,13 @@ pub struct Editor {
     available_code_actions: Option<(Model<Buffer>, Arc<[CodeAction]>)>,
     code_actions_task: Option<Task<()>>,
     document_highlights_task: Option<Task<()>>,
+    active_line_annotation_provider: Option<ActiveLineAnnotationProvider>,
+    active_line_annotation: Option<(Anchor, SharedString)>,
+    active_line_annotation_task: Option<Task<()>>,
     pending_rename: Option<RenameState>,
     searchable: bool,
     cursor_shape: CursorShape,
+    cursor_shape_overridden: bool,
     collapse_matches: bool,
     autoindent_mode: Option<AutoindentMode>,
     workspace: Option<(WeakView<Workspace>, i64)>,
@@ -415,10 +442,13 @@ pub struct Editor {
 
 pub struct EditorSnapshot {
     pub mode: EditorMode,
+    pub line_numbers_mode: LineNumbersMode,
     show_gutter: bool,
     pub display_snapshot: DisplaySnapshot,
     pub placeholder_text: Option<Arc<str>>,
     is_focused: bool,
+    rainbow_brackets: bool,
+    pub cursor_shape: CursorShape,
     scroll_anchor: ScrollAnchor,
     ongoing_scroll: OngoingScroll,
 }
@@ -464,6 +494,39 @@ enum SelectionHistoryMode {
     Redoing,
 }
 
+/// The folds a transaction removed and added, so that undoing or redoing it can revert or
+/// reapply them without touching any other fold.
+#[derive(Clone, Default)]
+struct FoldHistoryEntry {
+    removed: Vec<Range<Anchor>>,
+    added: Vec<Range<Anchor>>,
+}
+
+#[derive(Default)]
+struct FoldHistory {
+    transaction_depth: usize,
+    folds_at_transaction_start: Option<Arc<[Range<Anchor>]>>,
+    changes_by_transaction: HashMap<TransactionId, FoldHistoryEntry>,
+    transaction_order: VecDeque<TransactionId>,
+}
+
+/// The objects that [`Editor::select_text_object`] can select.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextObject {
+    Word,
+    Paragraph,
+    String,
+    Brackets,
+}
+
+/// The last text object selected, used to toggle between its inner and around ranges.
+struct TextObjectState {
+    object: TextObject,
+    around: bool,
+    origin: Arc<[Selection<Anchor>]>,
+    selections: Arc<[Selection<Anchor>]>,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 struct HoveredCursor {
     replica_id: u16,
@@ -552,6 +615,65 @@ impl SelectionHistory {
     }
 }
 
+impl FoldHistory {
+    fn end_transaction(
+        &mut self,
+        transaction_id: Option<TransactionId>,
+        end_folds: Arc<[Range<Anchor>]>,
+    ) {
+        let Some(start_folds) = self.folds_at_transaction_start.take() else {
+            return;
+        };
+        let Some(transaction_id) = transaction_id else {
+            return;
+        };
+
+        let start_set = start_folds.iter().cloned().collect::<HashSet<_>>();
+        let end_set = end_folds.iter().cloned().collect::<HashSet<_>>();
+        let removed = start_folds
+            .iter()
+            .filter(|fold| !end_set.contains(fold))
+            .cloned()
+            .collect::<Vec<_>>();
+        let added = end_folds
+            .iter()
+            .filter(|fold| !start_set.contains(fold))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Transactions can be grouped into an earlier one, in which case the changes
+        // are composed with the ones already recorded for it.
+        if let Some(entry) = self.changes_by_transaction.get_mut(&transaction_id) {
+            let undone_additions = entry
+                .added
+                .iter()
+                .filter(|fold| removed.contains(fold))
+                .cloned()
+                .collect::<Vec<_>>();
+            entry.added.retain(|fold| !undone_additions.contains(fold));
+            entry.removed.extend(
+                removed
+                    .into_iter()
+                    .filter(|fold| !undone_additions.contains(fold)),
+            );
+            entry.added.extend(added);
+        } else if !removed.is_empty() || !added.is_empty() {
+            self.changes_by_transaction
+                .insert(transaction_id, FoldHistoryEntry { removed, added });
+            self.transaction_order.push_back(transaction_id);
+            if self.transaction_order.len() > MAX_FOLD_HISTORY_LEN {
+                if let Some(transaction_id) = self.transaction_order.pop_front() {
+                    self.changes_by_transaction.remove(&transaction_id);
+                }
+            }
+        }
+    }
+
+    fn transaction(&self, transaction_id: TransactionId) -> Option<&FoldHistoryEntry> {
+        self.changes_by_transaction.get(&transaction_id)
+    }
+}
+
 #[derive(Clone, Debug)]
 struct AddSelectionsState {
     above: bool,
@@ -562,6 +684,7 @@ struct AddSelectionsState {
 struct SelectNextState {
     query: AhoCorasick,
     wordwise: bool,
+    case_insensitive: bool,
     done: bool,
 }
 
@@ -569,6 +692,7 @@ impl std::fmt::Debug for SelectNextState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct(std::any::type_name::<Self>())
             .field("wordwise", &self.wordwise)
+            .field("case_insensitive", &self.case_insensitive)
             .field("done", &self.done)
             .finish()
     }
@@ -1393,13 +1517,19 @@ impl Editor {
             add_selections_state: None,
             select_next_state: None,
             select_prev_state: None,
+            text_object_state: None,
             selection_history: Default::default(),
+            fold_history: Default::default(),
+            manual_folds: Vec::new(),
             autoclose_regions: Default::default(),
             snippet_stack: Default::default(),
+            templates: HashMap::default(),
             select_larger_syntax_node_stack: Vec::new(),
             ime_transaction: Default::default(),
             active_diagnostics: None,
             soft_wrap_mode_override,
+            soft_wrap_column_override: None,
+            line_numbers_mode_override: None,
             completion_provider: project.clone().map(|project| Box::new(project) as _),
             collaboration_hub: project.clone().map(|project| Box::new(project) as _),
             project,
@@ -1409,7 +1539,7 @@ impl Editor {
             show_gutter: mode == EditorMode::Full,
             show_wrap_guides: None,
             placeholder_text: None,
-            highlighted_rows: None,
+            highlighted_rows: Vec::new(),
             background_highlights: Default::default(),
             inlay_background_highlights: Default::default(),
             nav_history: None,
@@ -1421,9 +1551,13 @@ impl Editor {
             available_code_actions: Default::default(),
             code_actions_task: Default::default(),
             document_highlights_task: Default::default(),
+            active_line_annotation_provider: None,
+            active_line_annotation: None,
+            active_line_annotation_task: None,
             pending_rename: Default::default(),
             searchable: true,
-            cursor_shape: Default::default(),
+            cursor_shape: EditorSettings::get_global(cx).cursor_shape,
+            cursor_shape_overridden: false,
             autoindent_mode: Some(AutoindentMode::EachLine),
             collapse_matches: false,
             workspace: None,
@@ -1470,6 +1604,7 @@ impl Editor {
 
         this.end_selection(cx);
         this.scroll_manager.show_scrollbar(cx);
+        this.scroll_manager.refresh_vertical_scroll_margin(cx);
 
         if mode == EditorMode::Full {
             let should_auto_hide_scrollbars = cx.should_auto_hide_scrollbars();
@@ -1588,12 +1723,15 @@ impl Editor {
     pub fn snapshot(&mut self, cx: &mut WindowContext) -> EditorSnapshot {
         EditorSnapshot {
             mode: self.mode,
+            line_numbers_mode: self.line_numbers_mode(cx),
             show_gutter: self.show_gutter,
             display_snapshot: self.display_map.update(cx, |map, cx| map.snapshot(cx)),
             scroll_anchor: self.scroll_manager.anchor(),
             ongoing_scroll: self.scroll_manager.ongoing_scroll(),
             placeholder_text: self.placeholder_text.clone(),
             is_focused: self.focus_handle.is_focused(cx),
+            rainbow_brackets: EditorSettings::get_global(cx).rainbow_brackets,
+            cursor_shape: self.cursor_shape,
         }
     }
 
@@ -1626,6 +1764,18 @@ impl Editor {
         self.mode
     }
 
+    /// Returns the number of lines an auto-height editor should display, which is
+    /// the number of display rows clamped to the editor's `max_lines`.
+    pub fn line_count_for_auto_height(&self, cx: &mut AppContext) -> usize {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let row_count = display_map.max_point().row() as usize + 1;
+        match self.mode {
+            EditorMode::AutoHeight { max_lines } => row_count.min(max_lines),
+            EditorMode::SingleLine => 1,
+            EditorMode::Full => row_count,
+        }
+    }
+
     pub fn collaboration_hub(&self) -> Option<&dyn CollaborationHub> {
         self.collaboration_hub.as_deref()
     }
@@ -1654,8 +1804,17 @@ impl Editor {
         }
     }
 
+    /// Overrides the cursor shape configured in the settings, e.g. for modal editing.
     pub fn set_cursor_shape(&mut self, cursor_shape: CursorShape, cx: &mut ViewContext<Self>) {
         self.cursor_shape = cursor_shape;
+        self.cursor_shape_overridden = true;
+        cx.notify();
+    }
+
+    /// Restores the cursor shape configured in the settings.
+    pub fn reset_cursor_shape(&mut self, cx: &mut ViewContext<Self>) {
+        self.cursor_shape = EditorSettings::get_global(cx).cursor_shape;
+        self.cursor_shape_overridden = false;
         cx.notify();
     }
 
@@ -1712,6 +1871,12 @@ impl Editor {
         self.read_only = read_only;
     }
 
+    /// Sets whether selections that touch without overlapping are merged into one,
+    /// which they are by default. Takes effect the next time the selections change.
+    pub fn set_merge_adjacent_selections(&mut self, merge_adjacent: bool) {
+        self.selections.set_merge_adjacent(merge_adjacent);
+    }
+
     pub fn set_use_autoclose(&mut self, autoclose: bool) {
         self.use_autoclose = autoclose;
     }
@@ -1723,6 +1888,7 @@ impl Editor {
     fn selections_did_change(
         &mut self,
         local: bool,
+        emit_event: bool,
         old_cursor_position: &Anchor,
         cx: &mut ViewContext<Self>,
     ) {
@@ -1821,12 +1987,15 @@ impl Editor {
             }
             self.refresh_code_actions(cx);
             self.refresh_document_highlights(cx);
+            self.refresh_active_line_annotation(cx);
             refresh_matching_bracket_highlights(self, cx);
             self.discard_copilot_suggestion(cx);
         }
 
         self.blink_manager.update(cx, BlinkManager::pause_blinking);
-        cx.emit(EditorEvent::SelectionsChanged { local });
+        if emit_event {
+            cx.emit(EditorEvent::SelectionsChanged { local });
+        }
 
         if self.selections.disjoint_anchors().len() == 1 {
             cx.emit(SearchEvent::ActiveMatchChanged)
@@ -1842,6 +2011,8 @@ impl Editor {
         change: impl FnOnce(&mut MutableSelectionsCollection<'_>) -> R,
     ) -> R {
         let old_cursor_position = self.selections.newest_anchor().head();
+        let old_selections = self.selections.disjoint_anchors();
+        let old_pending = self.selections.pending_anchor();
         self.push_to_selection_history();
 
         let (changed, result) = self.selections.change_with(cx, change);
@@ -1850,7 +2021,13 @@ impl Editor {
             if let Some(autoscroll) = autoscroll {
                 self.request_autoscroll(autoscroll, cx);
             }
-            self.selections_did_change(true, &old_cursor_position, cx);
+
+            // Replacing the selections with identical ones isn't reported as a change.
+            let new_selections = self.selections.disjoint_anchors();
+            let selections_changed = !(Arc::ptr_eq(&old_selections, &new_selections)
+                || old_selections == new_selections)
+                || old_pending != self.selections.pending_anchor();
+            self.selections_did_change(true, selections_changed, &old_cursor_position, cx);
         }
 
         result
@@ -1971,6 +2148,14 @@ impl Editor {
         });
     }
 
+    /// Extends the newest selection to the given position, keeping its tail in place.
+    pub fn select_to(&mut self, position: Anchor, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut selection = self.selections.newest::<usize>(cx);
+        selection.set_head(position.to_offset(&buffer), SelectionGoal::None);
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(vec![selection]));
+    }
+
     fn begin_selection(
         &mut self,
         position: DisplayPoint,
@@ -2221,6 +2406,35 @@ impl Editor {
         self.selections.pending_anchor().is_some() || self.columnar_selection_tail.is_some()
     }
 
+    /// Returns the head of the newest selection, which is the pending one while the mouse
+    /// is being dragged.
+    pub fn newest_cursor_position(&self, cx: &AppContext) -> Point {
+        self.selections.newest::<Point>(cx).head()
+    }
+
+    /// Like [`Editor::newest_cursor_position`], but in display coordinates.
+    pub fn newest_cursor_display_position(&self, cx: &mut AppContext) -> DisplayPoint {
+        self.selections.newest_display(cx).head()
+    }
+
+    /// Collapses the selections to a cursor at the head of the newest one. Unlike
+    /// [`Self::cancel`], which keeps the oldest selection, this keeps the most recent.
+    pub fn collapse_to_newest(&mut self, _: &CollapseToNewest, cx: &mut ViewContext<Self>) {
+        let head = self.selections.newest_anchor().head();
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select_anchor_ranges([head..head])
+        });
+    }
+
+    /// Swaps the head and tail of every selection, scrolling the new heads into view.
+    pub fn swap_selection_ends(&mut self, _: &SwapSelectionEnds, cx: &mut ViewContext<Self>) {
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_with(|_, selection| {
+                selection.reversed = !selection.reversed;
+            })
+        });
+    }
+
     pub fn cancel(&mut self, _: &Cancel, cx: &mut ViewContext<Self>) {
         if self.take_rename(false, cx).is_some() {
             return;
@@ -2453,7 +2667,22 @@ impl Editor {
         });
     }
 
+    /// Replaces the text being composed by an input method with the given preedit text,
+    /// starting a new composition at each selection if none is in progress.
+    pub fn replace_composition(&mut self, preedit: &str, cx: &mut ViewContext<Self>) {
+        self.replace_and_mark_text_in_range(None, preedit, None, cx);
+    }
+
+    /// Replaces the text being composed by an input method and finishes the composition.
+    pub fn commit_composition(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        self.replace_text_in_range(None, text, cx);
+    }
+
     pub fn newline(&mut self, _: &Newline, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         self.transact(cx, |this, cx| {
             let (edits, selection_fixup_info): (Vec<_>, Vec<_>) = {
                 let selections = this.selections.all::<usize>(cx);
@@ -2592,6 +2821,10 @@ impl Editor {
     }
 
     pub fn newline_above(&mut self, _: &NewlineAbove, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let buffer = self.buffer.read(cx);
         let snapshot = buffer.snapshot(cx);
 
@@ -2651,6 +2884,10 @@ impl Editor {
     }
 
     pub fn newline_below(&mut self, _: &NewlineBelow, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let buffer = self.buffer.read(cx);
         let snapshot = buffer.snapshot(cx);
 
@@ -2730,7 +2967,12 @@ impl Editor {
             return;
         }
 
-        let text: Arc<str> = text.into();
+        // Single-line editors must never contain more than one line.
+        let text: Arc<str> = if self.mode == EditorMode::SingleLine {
+            text.replace(['\n', '\r'], "").into()
+        } else {
+            text.into()
+        };
         self.transact(cx, |this, cx| {
             let old_selections = this.selections.all_adjusted(cx);
             let selection_anchors = this.buffer.update(cx, |buffer, cx| {
@@ -2778,11 +3020,13 @@ impl Editor {
     }
 
     /// If any empty selections is touching the start of its innermost containing autoclose
-    /// region, expand it to select the brackets.
+    /// region, expand it to select the brackets. As the callers delete the selected text,
+    /// those regions are removed from the autoclose stack.
     fn select_autoclose_pair(&mut self, cx: &mut ViewContext<Self>) {
         let selections = self.selections.all::<usize>(cx);
         let buffer = self.buffer.read(cx).read(cx);
         let mut new_selections = Vec::new();
+        let mut selected_regions = Vec::new();
         for (mut selection, region) in self.selections_with_autoclose_regions(selections, &buffer) {
             if let (Some(region), true) = (region, selection.is_empty()) {
                 let mut range = region.range.to_offset(&buffer);
@@ -2794,6 +3038,7 @@ impl Editor {
                                 range.end += region.pair.end.len();
                                 selection.start = range.start;
                                 selection.end = range.end;
+                                selected_regions.push(region.range.clone());
                             }
                         }
                     }
@@ -2803,9 +3048,37 @@ impl Editor {
         }
 
         drop(buffer);
+        self.autoclose_regions
+            .retain(|region| !selected_regions.contains(&region.range));
         self.change_selections(None, cx, |selections| selections.select(new_selections));
     }
 
+    /// If every selection is a cursor right before the closing bracket of an autoclosed
+    /// pair, moves the cursors past those closing brackets.
+    fn move_past_autoclosed_brackets(&mut self, cx: &mut ViewContext<Self>) -> bool {
+        let selections = self.selections.all::<usize>(cx);
+        let buffer = self.buffer.read(cx).read(cx);
+        let mut new_selections = Vec::with_capacity(selections.len());
+        for (mut selection, region) in self.selections_with_autoclose_regions(selections, &buffer) {
+            let Some(region) = region.filter(|_| selection.is_empty()) else {
+                return false;
+            };
+            let closing_bracket_start = region.range.end.to_offset(&buffer);
+            if selection.start != closing_bracket_start
+                || !buffer.contains_str_at(closing_bracket_start, &region.pair.end)
+            {
+                return false;
+            }
+            selection.start = closing_bracket_start + region.pair.end.len();
+            selection.end = selection.start;
+            new_selections.push(selection);
+        }
+
+        drop(buffer);
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(new_selections));
+        true
+    }
+
     /// Iterate the given selections, and for each one, find the smallest surrounding
     /// autoclose region. This uses the ordering of the selections and the autoclose
     /// regions to avoid repeated comparisons.
@@ -3532,6 +3805,60 @@ impl Editor {
         None
     }
 
+    /// Annotates the end of the newest cursor's line using `provider`, following the
+    /// cursor as it moves between lines. Passing `None` removes the annotation.
+    pub fn set_active_line_annotation_provider(
+        &mut self,
+        provider: Option<ActiveLineAnnotationProvider>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.active_line_annotation_provider = provider;
+        self.active_line_annotation = None;
+        self.refresh_active_line_annotation(cx);
+        cx.notify();
+    }
+
+    /// The buffer row and text of the annotation currently shown, if any.
+    pub fn active_line_annotation(&self, cx: &AppContext) -> Option<(u32, SharedString)> {
+        let (line_start, text) = self.active_line_annotation.as_ref()?;
+        let row = line_start.to_point(&self.buffer.read(cx).snapshot(cx)).row;
+        Some((row, text.clone()))
+    }
+
+    fn refresh_active_line_annotation(&mut self, cx: &mut ViewContext<Self>) {
+        if self.active_line_annotation_provider.is_none() {
+            self.active_line_annotation_task = None;
+            return;
+        }
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let cursor_row = self.selections.newest_anchor().head().to_point(&buffer).row;
+        if let Some((line_start, _)) = self.active_line_annotation.as_ref() {
+            if line_start.to_point(&buffer).row == cursor_row {
+                return;
+            }
+            self.active_line_annotation = None;
+        }
+
+        self.active_line_annotation_task = Some(cx.spawn(|this, mut cx| async move {
+            cx.background_executor()
+                .timer(ACTIVE_LINE_ANNOTATION_DEBOUNCE_TIMEOUT)
+                .await;
+
+            this.update(&mut cx, |this, cx| {
+                let Some(provider) = this.active_line_annotation_provider.as_ref() else {
+                    return;
+                };
+                let buffer = this.buffer.read(cx).snapshot(cx);
+                let row = this.selections.newest_anchor().head().to_point(&buffer).row;
+                this.active_line_annotation = provider(&buffer, row)
+                    .map(|text| (buffer.anchor_before(Point::new(row, 0)), text));
+                cx.notify();
+            })
+            .log_err();
+        }));
+    }
+
     fn refresh_document_highlights(&mut self, cx: &mut ViewContext<Self>) -> Option<()> {
         if self.pending_rename.is_some() {
             return None;
@@ -4009,6 +4336,27 @@ impl Editor {
         context_menu
     }
 
+    /// Parses `text` as a snippet and inserts it in place of every selection. Its tab stops
+    /// can then be cycled through with [`Tab`] and [`TabPrev`].
+    pub fn insert_snippet_text(&mut self, text: &str, cx: &mut ViewContext<Self>) -> Result<()> {
+        if self.read_only(cx) {
+            return Ok(());
+        }
+
+        let snippet = Snippet::parse(text)?;
+        let insertion_ranges = self
+            .selections
+            .all::<usize>(cx)
+            .into_iter()
+            .map(|selection| selection.range())
+            .collect::<Vec<_>>();
+        let mut result = Ok(());
+        self.transact(cx, |this, cx| {
+            result = this.insert_snippet(&insertion_ranges, snippet, cx);
+        });
+        result
+    }
+
     pub fn insert_snippet(
         &mut self,
         insertion_ranges: &[Range<usize>],
@@ -4120,6 +4468,10 @@ impl Editor {
     }
 
     pub fn backspace(&mut self, _: &Backspace, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         self.transact(cx, |this, cx| {
             this.select_autoclose_pair(cx);
             let mut selections = this.selections.all::<Point>(cx);
@@ -4167,6 +4519,10 @@ impl Editor {
     }
 
     pub fn delete(&mut self, _: &Delete, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         self.transact(cx, |this, cx| {
             this.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 let line_mode = s.line_mode;
@@ -4184,6 +4540,39 @@ impl Editor {
         });
     }
 
+    /// Deletes the contents of every non-empty selection, leaving a cursor in place of each
+    /// one. Unlike [`Editor::delete`] and [`Editor::cut`], empty selections are left alone
+    /// and whole lines are never deleted, even in line mode.
+    pub fn clear_selections_content(
+        &mut self,
+        _: &ClearSelectionsContent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+
+        let ranges = self
+            .selections
+            .all::<usize>(cx)
+            .into_iter()
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| selection.range())
+            .collect::<Vec<_>>();
+        if ranges.is_empty() {
+            return;
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer.update(cx, |buffer, cx| {
+                buffer.edit(ranges.into_iter().map(|range| (range, "")), None, cx);
+            });
+            let selections = this.selections.all::<usize>(cx);
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+            this.refresh_copilot_suggestions(true, cx);
+        });
+    }
+
     pub fn tab_prev(&mut self, _: &TabPrev, cx: &mut ViewContext<Self>) {
         if self.move_to_prev_snippet_tabstop(cx) {
             return;
@@ -4192,8 +4581,65 @@ impl Editor {
         self.outdent(&Outdent, cx);
     }
 
+    /// Registers a template that is expanded when `trigger` is the word before the cursor
+    /// and [`Tab`] is pressed. The template uses snippet syntax, so it can contain tab stops.
+    pub fn register_template(&mut self, trigger: impl Into<String>, template: impl Into<Arc<str>>) {
+        self.templates.insert(trigger.into(), template.into());
+    }
+
+    /// Expands the template whose trigger is the word before every cursor, returning
+    /// whether a template was expanded.
+    fn expand_template(&mut self, cx: &mut ViewContext<Self>) -> bool {
+        if self.templates.is_empty() {
+            return false;
+        }
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut template = None;
+        let mut trigger_ranges = Vec::new();
+        for selection in self.selections.all::<usize>(cx) {
+            if !selection.is_empty() {
+                return false;
+            }
+
+            let cursor = selection.head();
+            let trigger_len: usize = buffer
+                .reversed_chars_at(cursor)
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .map(char::len_utf8)
+                .sum();
+            let trigger = buffer
+                .text_for_range(cursor - trigger_len..cursor)
+                .collect::<String>();
+            match (self.templates.get(&trigger), &template) {
+                (Some(found), None) => template = Some(found.clone()),
+                (Some(found), Some(template)) if found == template => {}
+                _ => return false,
+            }
+            trigger_ranges.push(cursor - trigger_len..cursor);
+        }
+
+        let Some(snippet) = template.and_then(|template| Snippet::parse(&template).log_err())
+        else {
+            return false;
+        };
+        self.transact(cx, |this, cx| {
+            this.insert_snippet(&trigger_ranges, snippet, cx).log_err();
+        });
+        true
+    }
+
     pub fn tab(&mut self, _: &Tab, cx: &mut ViewContext<Self>) {
-        if self.move_to_next_snippet_tabstop(cx) || self.read_only(cx) {
+        if self.read_only(cx) {
+            return;
+        }
+        if self.expand_template(cx) || self.move_to_next_snippet_tabstop(cx) {
+            return;
+        }
+
+        if EditorSettings::get_global(cx).tab_over_closing_brackets
+            && self.move_past_autoclosed_brackets(cx)
+        {
             return;
         }
 
@@ -4258,12 +4704,19 @@ impl Editor {
                 IndentSize::tab()
             } else {
                 let tab_size = settings.tab_size.get();
-                let char_column = snapshot
+                // Expand any tabs preceding the cursor to find its visual column.
+                let column = snapshot
                     .text_for_range(Point::new(cursor.row, 0)..cursor)
                     .flat_map(str::chars)
-                    .count()
-                    + row_delta as usize;
-                let chars_to_next_tab_stop = tab_size - (char_column as u32 % tab_size);
+                    .fold(0, |column, c| {
+                        if c == '\t' {
+                            column + tab_size - column % tab_size
+                        } else {
+                            column + 1
+                        }
+                    })
+                    + row_delta;
+                let chars_to_next_tab_stop = tab_size - (column % tab_size);
                 IndentSize::spaces(chars_to_next_tab_stop)
             };
             selection.start = Point::new(cursor.row, cursor.column + row_delta + tab_size.len);
@@ -4280,6 +4733,10 @@ impl Editor {
     }
 
     pub fn indent(&mut self, _: &Indent, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let mut selections = self.selections.all::<Point>(cx);
         let mut prev_edited_row = 0;
         let mut row_delta = 0;
@@ -4373,6 +4830,10 @@ impl Editor {
     }
 
     pub fn outdent(&mut self, _: &Outdent, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let selections = self.selections.all::<Point>(cx);
         let mut deletion_ranges = Vec::new();
@@ -4431,15 +4892,29 @@ impl Editor {
     }
 
     pub fn delete_line(&mut self, _: &DeleteLine, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let selections = self.selections.all::<Point>(cx);
+        let text_layout_details = &self.text_layout_details(cx);
 
         let mut new_cursors = Vec::new();
         let mut edit_ranges = Vec::new();
         let mut selections = selections.iter().peekable();
         while let Some(selection) = selections.next() {
             let mut rows = selection.spanned_rows(false, &display_map);
-            let goal_display_column = selection.head().to_display_point(&display_map).column();
+            // Preserve the goal column, like moving vertically would.
+            let goal_x = match selection.goal {
+                SelectionGoal::HorizontalPosition(x) => px(x),
+                SelectionGoal::WrappedHorizontalPosition((_, x)) => px(x),
+                SelectionGoal::HorizontalRange { end, .. } => px(end),
+                SelectionGoal::None => display_map.x_for_display_point(
+                    selection.head().to_display_point(&display_map),
+                    text_layout_details,
+                ),
+            };
 
             // Accumulate contiguous regions of rows that we want to delete.
             while let Some(next_selection) = selections.peek() {
@@ -4471,11 +4946,12 @@ impl Editor {
 
             let mut cursor = Point::new(cursor_buffer_row, 0).to_display_point(&display_map);
             *cursor.column_mut() =
-                cmp::min(goal_display_column, display_map.line_len(cursor.row()));
+                display_map.display_column_for_x(cursor.row(), goal_x, text_layout_details);
 
             new_cursors.push((
                 selection.id,
                 buffer.anchor_after(cursor.to_point(&display_map)),
+                SelectionGoal::HorizontalPosition(goal_x.into()),
             ));
             edit_ranges.push(edit_start..edit_end);
         }
@@ -4494,14 +4970,14 @@ impl Editor {
             });
             let new_selections = new_cursors
                 .into_iter()
-                .map(|(id, cursor)| {
+                .map(|(id, cursor, goal)| {
                     let cursor = cursor.to_point(&buffer);
                     Selection {
                         id,
                         start: cursor,
                         end: cursor,
                         reversed: false,
-                        goal: SelectionGoal::None,
+                        goal,
                     }
                 })
                 .collect();
@@ -4513,6 +4989,10 @@ impl Editor {
     }
 
     pub fn join_lines(&mut self, _: &JoinLines, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let mut row_ranges = Vec::<Range<u32>>::new();
         for selection in self.selections.all::<Point>(cx) {
             let start = selection.start.row;
@@ -4566,34 +5046,275 @@ impl Editor {
         });
     }
 
-    pub fn sort_lines_case_sensitive(
-        &mut self,
-        _: &SortLinesCaseSensitive,
-        cx: &mut ViewContext<Self>,
-    ) {
-        self.manipulate_lines(cx, |lines| lines.sort())
-    }
+    /// Reflows the lines spanned by each selection, or the paragraph around each cursor, so
+    /// that they fit within the wrap column. The indentation and line comment prefix of the
+    /// first line are repeated on every rewrapped line.
+    pub fn rewrap(&mut self, _: &Rewrap, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
 
-    pub fn sort_lines_case_insensitive(
-        &mut self,
-        _: &SortLinesCaseInsensitive,
-        cx: &mut ViewContext<Self>,
-    ) {
-        self.manipulate_lines(cx, |lines| lines.sort_by_key(|line| line.to_lowercase()))
-    }
+        let wrap_column = match self.soft_wrap_mode(cx) {
+            SoftWrap::Column(column) => column,
+            SoftWrap::None | SoftWrap::EditorWidth => {
+                self.buffer
+                    .read(cx)
+                    .settings_at(0, cx)
+                    .preferred_line_length
+            }
+        } as usize;
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let max_row = buffer.max_point().row;
+        let line_text = |row: u32| {
+            buffer
+                .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+                .collect::<String>()
+        };
 
-    pub fn reverse_lines(&mut self, _: &ReverseLines, cx: &mut ViewContext<Self>) {
-        self.manipulate_lines(cx, |lines| lines.reverse())
-    }
+        let mut rewraps = Vec::new();
+        let mut next_row = 0;
+        for selection in self.selections.all::<Point>(cx) {
+            if selection.start.row < next_row {
+                continue;
+            }
 
-    pub fn shuffle_lines(&mut self, _: &ShuffleLines, cx: &mut ViewContext<Self>) {
-        self.manipulate_lines(cx, |lines| lines.shuffle(&mut thread_rng()))
-    }
+            let first_line = line_text(selection.start.row);
+            let indent = &first_line[..first_line.len() - first_line.trim_start().len()];
+            let comment_prefix = buffer
+                .language_scope_at(Point::new(selection.start.row, indent.len() as u32))
+                .and_then(|language| {
+                    language.line_comment_prefixes()?.iter().find_map(|prefix| {
+                        first_line[indent.len()..]
+                            .starts_with(prefix.trim_end())
+                            .then(|| prefix.clone())
+                    })
+                });
+            let line_prefix = format!("{indent}{}", comment_prefix.as_deref().unwrap_or(""));
+            let comment_marker = comment_prefix.as_deref().unwrap_or("").trim_end();
+            let continues_paragraph = |line: &str| {
+                line.strip_prefix(indent)
+                    .and_then(|line| line.strip_prefix(comment_marker))
+                    .map_or(false, |content| !content.trim().is_empty())
+            };
+
+            let mut start_row = selection.start.row;
+            let mut end_row = selection.end.row;
+            if selection.is_empty() {
+                if !continues_paragraph(&first_line) {
+                    continue;
+                }
+                while start_row > next_row && continues_paragraph(&line_text(start_row - 1)) {
+                    start_row -= 1;
+                }
+                while end_row < max_row && continues_paragraph(&line_text(end_row + 1)) {
+                    end_row += 1;
+                }
+            } else if selection.end.column == 0 && end_row > start_row {
+                end_row -= 1;
+            }
+            next_row = end_row + 1;
+
+            let mut words = Vec::new();
+            for row in start_row..=end_row {
+                let line = line_text(row);
+                let mut content = line.trim_start();
+                if let Some(comment_prefix) = comment_prefix.as_deref() {
+                    content = content
+                        .strip_prefix(comment_prefix.trim_end())
+                        .unwrap_or(content);
+                }
+                words.extend(content.split_whitespace().map(str::to_string));
+            }
+            if words.is_empty() {
+                continue;
+            }
+
+            let prefix_width = line_prefix.chars().count();
+            let mut lines = Vec::new();
+            let mut line = String::new();
+            for word in words {
+                if !line.is_empty()
+                    && prefix_width + line.chars().count() + 1 + word.chars().count() > wrap_column
+                {
+                    lines.push(mem::take(&mut line));
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&word);
+            }
+            lines.push(line);
+
+            let text = lines
+                .into_iter()
+                .map(|line| format!("{line_prefix}{line}"))
+                .join("\n");
+            let range = Point::new(start_row, 0).to_offset(&buffer)
+                ..Point::new(end_row, buffer.line_len(end_row)).to_offset(&buffer);
+            rewraps.push((range, text));
+        }
+
+        // Place a cursor at the end of each rewrapped paragraph.
+        let mut delta = 0isize;
+        let mut cursors = Vec::new();
+        let mut edits = Vec::new();
+        for (range, text) in rewraps {
+            let end = (range.start as isize + delta) as usize + text.len();
+            cursors.push(end..end);
+            delta += text.len() as isize - range.len() as isize;
+            if buffer.text_for_range(range.clone()).ne([text.as_str()]) {
+                edits.push((range, text));
+            }
+        }
+        if cursors.is_empty() {
+            return;
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select_ranges(cursors));
+        });
+    }
+
+    /// Replaces every occurrence of `query` within this editor's excerpts with `replacement`.
+    /// The edits are routed through the multi-buffer, so they land in each excerpt's source
+    /// buffer and can be undone as a single transaction.
+    pub fn replace_all_in_excerpts(
+        &mut self,
+        query: &str,
+        replacement: &str,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if query.is_empty() || self.read_only(cx) {
+            return;
+        }
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut matches = Vec::new();
+        for (excerpt_id, excerpt_buffer, excerpt_range) in buffer.excerpts() {
+            let context_start = excerpt_range.context.start.to_offset(excerpt_buffer);
+            let context_text = excerpt_buffer
+                .text_for_range(excerpt_range.context.clone())
+                .collect::<String>();
+            for (ix, _) in context_text.match_indices(query) {
+                let start = context_start + ix;
+                matches.push((excerpt_buffer, start..start + query.len(), excerpt_id));
+            }
+        }
+
+        // Several excerpts can show overlapping ranges of the same buffer, so only the
+        // first of any overlapping matches within a buffer is replaced.
+        matches.sort_by_key(|(excerpt_buffer, range, _)| (excerpt_buffer.remote_id(), range.start));
+        let mut edits = Vec::with_capacity(matches.len());
+        let mut last_match: Option<(u64, usize)> = None;
+        for (excerpt_buffer, range, excerpt_id) in matches {
+            let buffer_id = excerpt_buffer.remote_id();
+            if last_match.map_or(false, |(last_buffer_id, last_end)| {
+                last_buffer_id == buffer_id && range.start < last_end
+            }) {
+                continue;
+            }
+            last_match = Some((buffer_id, range.end));
+
+            let start = excerpt_buffer.anchor_after(range.start);
+            let end = excerpt_buffer.anchor_before(range.end);
+            edits.push((
+                buffer.anchor_in_excerpt(excerpt_id, start)
+                    ..buffer.anchor_in_excerpt(excerpt_id, end),
+                replacement,
+            ));
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+        });
+    }
+
+    /// Replaces the text of every selection with `text` as a single transaction, leaving a
+    /// cursor after each replacement. Unlike [`Self::insert`], no auto-indentation or
+    /// autoclosing is applied.
+    pub fn replace_all_selections(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
+        let selections = self.selections.all::<usize>(cx);
+        let mut delta = 0isize;
+        let mut cursors = Vec::with_capacity(selections.len());
+        let mut edits = Vec::with_capacity(selections.len());
+        for selection in selections {
+            let end = (selection.start as isize + delta) as usize + text.len();
+            cursors.push(end..end);
+            delta += text.len() as isize - selection.range().len() as isize;
+            edits.push((selection.range(), text));
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select_ranges(cursors));
+        });
+    }
+
+    pub fn sort_lines_case_sensitive(
+        &mut self,
+        _: &SortLinesCaseSensitive,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.sort_lines(true, false, cx)
+    }
+
+    pub fn sort_lines_case_insensitive(
+        &mut self,
+        _: &SortLinesCaseInsensitive,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.sort_lines(false, false, cx)
+    }
+
+    /// Sorts the selected lines. When `follow_content` is set, each selection becomes a cursor
+    /// on the line its head was on, wherever that line was sorted to, instead of selecting
+    /// the sorted lines.
+    pub fn sort_lines(
+        &mut self,
+        case_sensitive: bool,
+        follow_content: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if case_sensitive {
+            self.manipulate_lines(follow_content, cx, |lines| lines.sort())
+        } else {
+            self.manipulate_lines(follow_content, cx, |lines| {
+                lines.sort_by_key(|line| line.to_lowercase())
+            })
+        }
+    }
+
+    pub fn reverse_lines(&mut self, _: &ReverseLines, cx: &mut ViewContext<Self>) {
+        self.manipulate_lines(false, cx, |lines| lines.reverse())
+    }
+
+    pub fn shuffle_lines(&mut self, _: &ShuffleLines, cx: &mut ViewContext<Self>) {
+        self.manipulate_lines(false, cx, |lines| lines.shuffle(&mut thread_rng()))
+    }
+
+    fn manipulate_lines<Fn>(
+        &mut self,
+        follow_content: bool,
+        cx: &mut ViewContext<Self>,
+        mut callback: Fn,
+    ) where
+        Fn: FnMut(&mut [&str]),
+    {
+        if self.read_only(cx) {
+            return;
+        }
 
-    fn manipulate_lines<Fn>(&mut self, cx: &mut ViewContext<Self>, mut callback: Fn)
-    where
-        Fn: FnMut(&mut [&str]),
-    {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
 
@@ -4605,6 +5326,7 @@ impl Editor {
         let mut new_selections = Vec::new();
 
         while let Some(selection) = selections.next() {
+            let first_contiguous_selection = contiguous_row_selections.len();
             let (start_row, end_row) = consume_contiguous_rows(
                 &mut contiguous_row_selections,
                 selection,
@@ -4618,6 +5340,7 @@ impl Editor {
                 .text_for_range(start_point..end_point)
                 .collect::<String>();
             let mut lines = text.split("\n").collect_vec();
+            let original_lines = lines.clone();
 
             let lines_len = lines.len();
             callback(&mut lines);
@@ -4630,6 +5353,41 @@ impl Editor {
             );
 
             edits.push((start_point..end_point, lines.join("\n")));
+
+            if follow_content {
+                // Place a cursor on the new row of each selection head's line. The lines keep
+                // their total length, so offsets computed here are still valid after the edit.
+                let start_offset = start_point.to_offset(&buffer);
+                for selection in &contiguous_row_selections[first_contiguous_selection..] {
+                    let head = selection.head().min(end_point);
+                    let old_index = (head.row - start_row) as usize;
+                    let line = original_lines[old_index];
+                    let occurrence = original_lines[..old_index]
+                        .iter()
+                        .filter(|other| **other == line)
+                        .count();
+                    let new_index = lines
+                        .iter()
+                        .positions(|other| *other == line)
+                        .nth(occurrence)
+                        .unwrap_or(old_index);
+                    let cursor = start_offset
+                        + lines[..new_index]
+                            .iter()
+                            .map(|line| line.len() + 1)
+                            .sum::<usize>()
+                        + head.column as usize;
+                    new_selections.push(Selection {
+                        id: selection.id,
+                        start: cursor,
+                        end: cursor,
+                        goal: SelectionGoal::None,
+                        reversed: false,
+                    });
+                }
+                continue;
+            }
+
             let start_anchor = buffer.anchor_after(start_point);
             let end_anchor = buffer.anchor_before(end_point);
 
@@ -4704,10 +5462,32 @@ impl Editor {
         self.manipulate_text(cx, |text| text.to_case(Case::Camel))
     }
 
+    /// Uppercases the first letter of every word in the selections, or of the word
+    /// under the cursor for empty selections, leaving the other letters unchanged.
+    pub fn capitalize_word(&mut self, _: &CapitalizeWord, cx: &mut ViewContext<Self>) {
+        self.manipulate_text(cx, |text| {
+            let mut capitalized = String::with_capacity(text.len());
+            let mut at_word_start = true;
+            for ch in text.chars() {
+                if at_word_start && ch.is_alphabetic() {
+                    capitalized.extend(ch.to_uppercase());
+                } else {
+                    capitalized.push(ch);
+                }
+                at_word_start = ch.is_whitespace();
+            }
+            capitalized
+        })
+    }
+
     fn manipulate_text<Fn>(&mut self, cx: &mut ViewContext<Self>, mut callback: Fn)
     where
         Fn: FnMut(&str) -> String,
     {
+        if self.read_only(cx) {
+            return;
+        }
+
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
 
@@ -4760,34 +5540,63 @@ impl Editor {
     }
 
     pub fn duplicate_line(&mut self, _: &DuplicateLine, cx: &mut ViewContext<Self>) {
+        self.duplicate_line_impl(true, cx);
+    }
+
+    /// Like [`Self::duplicate_line`], but inserts the copy below each row region and
+    /// moves the selections onto the copy.
+    pub fn duplicate_line_down(&mut self, _: &DuplicateLineDown, cx: &mut ViewContext<Self>) {
+        self.duplicate_line_impl(false, cx);
+    }
+
+    fn duplicate_line_impl(&mut self, upwards: bool, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = &display_map.buffer_snapshot;
         let selections = self.selections.all::<Point>(cx);
 
         let mut edits = Vec::new();
+        let mut new_selections = Vec::new();
+        let mut row_delta = 0;
         let mut selections_iter = selections.iter().peekable();
         while let Some(selection) = selections_iter.next() {
             // Avoid duplicating the same lines twice.
             let mut rows = selection.spanned_rows(false, &display_map);
+            let mut region_selections = vec![selection];
 
             while let Some(next_selection) = selections_iter.peek() {
                 let next_rows = next_selection.spanned_rows(false, &display_map);
                 if next_rows.start < rows.end {
                     rows.end = next_rows.end;
-                    selections_iter.next().unwrap();
+                    region_selections.push(selections_iter.next().unwrap());
                 } else {
                     break;
                 }
             }
 
-            // Copy the text from the selected row region and splice it at the start of the region.
+            // Copy the text from the selected row region and splice it either at the start
+            // or at the end of the region.
             let start = Point::new(rows.start, 0);
             let end = Point::new(rows.end - 1, buffer.line_len(rows.end - 1));
-            let text = buffer
-                .text_for_range(start..end)
-                .chain(Some("\n"))
-                .collect::<String>();
-            edits.push((start..start, text));
+            let text = buffer.text_for_range(start..end).collect::<String>();
+            if upwards {
+                edits.push((start..start, text + "\n"));
+            } else {
+                edits.push((end..end, "\n".to_string() + &text));
+
+                // Each region is shifted down by its own copy and by the copies of the
+                // regions before it.
+                row_delta += rows.end - rows.start;
+                for selection in region_selections {
+                    let mut selection = selection.clone();
+                    selection.start.row += row_delta;
+                    selection.end.row += row_delta;
+                    new_selections.push(selection);
+                }
+            }
         }
 
         self.transact(cx, |this, cx| {
@@ -4795,11 +5604,21 @@ impl Editor {
                 buffer.edit(edits, None, cx);
             });
 
-            this.request_autoscroll(Autoscroll::fit(), cx);
+            if upwards {
+                this.request_autoscroll(Autoscroll::fit(), cx);
+            } else {
+                this.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.select(new_selections);
+                });
+            }
         });
     }
 
     pub fn move_line_up(&mut self, _: &MoveLineUp, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
 
@@ -4898,6 +5717,10 @@ impl Editor {
     }
 
     pub fn move_line_down(&mut self, _: &MoveLineDown, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
 
@@ -4987,6 +5810,10 @@ impl Editor {
     }
 
     pub fn transpose(&mut self, _: &Transpose, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let text_layout_details = &self.text_layout_details(cx);
         self.transact(cx, |this, cx| {
             let edits = this.change_selections(Some(Autoscroll::fit()), cx, |s| {
@@ -5045,6 +5872,10 @@ impl Editor {
     }
 
     pub fn cut(&mut self, _: &Cut, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let mut text = String::new();
         let buffer = self.buffer.read(cx).snapshot(cx);
         let mut selections = self.selections.all::<Point>(cx);
@@ -5121,7 +5952,17 @@ impl Editor {
             }
         }
 
+        drop(buffer);
+
         cx.write_to_clipboard(ClipboardItem::new(text).with_metadata(clipboard_selections));
+
+        if EditorSettings::get_global(cx).collapse_selection_after_copy {
+            self.change_selections(None, cx, |s| {
+                s.move_with(|_, selection| {
+                    selection.collapse_to(selection.head(), SelectionGoal::None)
+                });
+            });
+        }
     }
 
     pub fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
@@ -5132,12 +5973,38 @@ impl Editor {
         self.transact(cx, |this, cx| {
             if let Some(item) = cx.read_from_clipboard() {
                 let clipboard_text = Cow::Borrowed(item.text());
-                if let Some(mut clipboard_selections) = item.metadata::<Vec<ClipboardSelection>>() {
+                let clipboard_selections = item
+                    .metadata::<Vec<ClipboardSelection>>()
+                    .filter(|_| this.mode != EditorMode::SingleLine);
+                if let Some(mut clipboard_selections) = clipboard_selections {
                     let old_selections = this.selections.all::<usize>(cx);
                     let all_selections_were_entire_line =
                         clipboard_selections.iter().all(|s| s.is_entire_line);
                     let first_selection_indent_column =
                         clipboard_selections.first().map(|s| s.first_line_indent);
+
+                    // A single multi-line selection pasted onto as many cursors as it has lines
+                    // is distributed one line per cursor.
+                    if old_selections.len() > 1
+                        && clipboard_selections.len() == 1
+                        && !all_selections_were_entire_line
+                        && clipboard_text.split('\n').count() == old_selections.len()
+                    {
+                        clipboard_selections = clipboard_text
+                            .split('\n')
+                            .enumerate()
+                            .map(|(ix, line)| ClipboardSelection {
+                                len: line.len(),
+                                is_entire_line: false,
+                                first_line_indent: if ix == 0 {
+                                    first_selection_indent_column.unwrap_or(0)
+                                } else {
+                                    (line.len() - line.trim_start_matches([' ', '\t']).len()) as u32
+                                },
+                            })
+                            .collect();
+                    }
+
                     if clipboard_selections.len() != old_selections.len() {
                         clipboard_selections.drain(..);
                     }
@@ -5149,22 +6016,35 @@ impl Editor {
                         let mut original_indent_columns = Vec::new();
                         let line_mode = this.selections.line_mode;
                         for (ix, selection) in old_selections.iter().enumerate() {
-                            let to_insert;
+                            let mut to_insert;
                             let entire_line;
-                            let original_indent_column;
+                            let mut original_indent_column;
                             if let Some(clipboard_selection) = clipboard_selections.get(ix) {
                                 let end_offset = start_offset + clipboard_selection.len;
-                                to_insert = &clipboard_text[start_offset..end_offset];
+                                to_insert =
+                                    Cow::Borrowed(&clipboard_text[start_offset..end_offset]);
                                 entire_line = clipboard_selection.is_entire_line;
                                 start_offset = end_offset + 1;
                                 original_indent_column =
                                     Some(clipboard_selection.first_line_indent);
                             } else {
-                                to_insert = clipboard_text.as_str();
+                                to_insert = Cow::Borrowed(clipboard_text.as_str());
                                 entire_line = all_selections_were_entire_line;
                                 original_indent_column = first_selection_indent_column
                             }
 
+                            // Re-base a multi-line slice onto the indentation of the line being
+                            // pasted into. Its lines are then already indented relative to that
+                            // line when the block is auto-indented.
+                            if !entire_line && !line_mode && to_insert.contains('\n') {
+                                let (indent, at_indent) =
+                                    paste_indent(&snapshot, selection.start.to_point(&snapshot));
+                                to_insert = Cow::Owned(reindent_pasted_text(
+                                    &to_insert, &indent, at_indent,
+                                ));
+                                original_indent_column = Some(indent.len() as u32);
+                            }
+
                             // If the corresponding selection was empty when this slice of the
                             // clipboard text was written, then the entire line containing the
                             // selection was copied. If this selection is also currently empty,
@@ -5191,6 +6071,30 @@ impl Editor {
                         );
                     });
 
+                    let selections = this.selections.all::<usize>(cx);
+                    this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+                } else if this.mode != EditorMode::SingleLine && clipboard_text.contains('\n') {
+                    // Without metadata, re-base multi-line text onto the indentation of the
+                    // line being pasted into.
+                    let old_selections = this.selections.all::<Point>(cx);
+                    this.buffer.update(cx, |buffer, cx| {
+                        let snapshot = buffer.read(cx);
+                        let edits = old_selections
+                            .iter()
+                            .map(|selection| {
+                                let (indent, at_indent) = paste_indent(&snapshot, selection.start);
+                                let range = selection.start.to_offset(&snapshot)
+                                    ..selection.end.to_offset(&snapshot);
+                                (
+                                    range,
+                                    reindent_pasted_text(&clipboard_text, &indent, at_indent),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        drop(snapshot);
+                        buffer.edit(edits, None, cx);
+                    });
+
                     let selections = this.selections.all::<usize>(cx);
                     this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
                 } else {
@@ -5200,12 +6104,79 @@ impl Editor {
         });
     }
 
+    pub fn paste_above(&mut self, _: &PasteAbove, cx: &mut ViewContext<Self>) {
+        self.paste_lines(true, cx);
+    }
+
+    pub fn paste_below(&mut self, _: &PasteBelow, cx: &mut ViewContext<Self>) {
+        self.paste_lines(false, cx);
+    }
+
+    /// Inserts the clipboard text as whole lines above or below the line of each
+    /// selection, ignoring whether it was copied as entire lines, and places each
+    /// cursor at the start of the first pasted line.
+    fn paste_lines(&mut self, above: bool, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+        if matches!(self.mode, EditorMode::SingleLine) {
+            cx.propagate();
+            return;
+        }
+        let Some(item) = cx.read_from_clipboard() else {
+            return;
+        };
+        let text = item.text().strip_suffix('\n').unwrap_or(item.text());
+        let inserted_row_count = text.matches('\n').count() as u32 + 1;
+
+        self.transact(cx, |this, cx| {
+            let snapshot = this.buffer.read(cx).snapshot(cx);
+            let mut rows = this
+                .selections
+                .all::<Point>(cx)
+                .into_iter()
+                .map(|selection| {
+                    if above {
+                        selection.start.row
+                    } else {
+                        selection.end.row
+                    }
+                })
+                .collect::<Vec<_>>();
+            rows.dedup();
+
+            let mut edits = Vec::with_capacity(rows.len());
+            let mut new_cursors = Vec::with_capacity(rows.len());
+            for (ix, row) in rows.into_iter().enumerate() {
+                let rows_inserted_before = ix as u32 * inserted_row_count;
+                if above {
+                    let position = Point::new(row, 0);
+                    edits.push((position..position, format!("{text}\n")));
+                    new_cursors.push(Point::new(row + rows_inserted_before, 0));
+                } else {
+                    let position = Point::new(row, snapshot.line_len(row));
+                    edits.push((position..position, format!("\n{text}")));
+                    new_cursors.push(Point::new(row + rows_inserted_before + 1, 0));
+                }
+            }
+
+            this.buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges(new_cursors.into_iter().map(|cursor| cursor..cursor));
+            });
+        });
+    }
+
     pub fn undo(&mut self, _: &Undo, cx: &mut ViewContext<Self>) {
         if self.read_only(cx) {
             return;
         }
 
         if let Some(tx_id) = self.buffer.update(cx, |buffer, cx| buffer.undo(cx)) {
+            if let Some(entry) = self.fold_history.transaction(tx_id).cloned() {
+                self.replace_folds(&entry.added, &entry.removed, cx);
+            }
             if let Some((selections, _)) = self.selection_history.transaction(tx_id).cloned() {
                 self.change_selections(None, cx, |s| {
                     s.select_anchors(selections.to_vec());
@@ -5224,6 +6195,9 @@ impl Editor {
         }
 
         if let Some(tx_id) = self.buffer.update(cx, |buffer, cx| buffer.redo(cx)) {
+            if let Some(entry) = self.fold_history.transaction(tx_id).cloned() {
+                self.replace_folds(&entry.removed, &entry.added, cx);
+            }
             if let Some((_, Some(selections))) = self.selection_history.transaction(tx_id).cloned()
             {
                 self.change_selections(None, cx, |s| {
@@ -5322,8 +6296,10 @@ impl Editor {
             return;
         }
 
+        // Keep one line of context, but always move by at least one row so
+        // that editors shorter than two lines don't underflow.
         let row_count = if let Some(row_count) = self.visible_line_count() {
-            row_count as u32 - 1
+            (row_count as u32).saturating_sub(1).max(1)
         } else {
             return;
         };
@@ -5364,6 +6340,26 @@ impl Editor {
         })
     }
 
+    pub fn select_page_up(&mut self, _: &SelectPageUp, cx: &mut ViewContext<Self>) {
+        if matches!(self.mode, EditorMode::SingleLine) {
+            cx.propagate();
+            return;
+        }
+
+        let row_count = if let Some(row_count) = self.visible_line_count() {
+            (row_count as u32).saturating_sub(1).max(1)
+        } else {
+            return;
+        };
+
+        let text_layout_details = &self.text_layout_details(cx);
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_heads_with(|map, head, goal| {
+                movement::up_by_rows(map, head, row_count, goal, false, &text_layout_details)
+            })
+        })
+    }
+
     pub fn move_down(&mut self, _: &MoveDown, cx: &mut ViewContext<Self>) {
         self.take_rename(true, cx);
 
@@ -5412,7 +6408,7 @@ impl Editor {
         }
 
         let row_count = if let Some(row_count) = self.visible_line_count() {
-            row_count as u32 - 1
+            (row_count as u32).saturating_sub(1).max(1)
         } else {
             return;
         };
@@ -5452,9 +6448,29 @@ impl Editor {
         });
     }
 
-    pub fn context_menu_first(&mut self, _: &ContextMenuFirst, cx: &mut ViewContext<Self>) {
-        if let Some(context_menu) = self.context_menu.write().as_mut() {
-            context_menu.select_first(self.project.as_ref(), cx);
+    pub fn select_page_down(&mut self, _: &SelectPageDown, cx: &mut ViewContext<Self>) {
+        if matches!(self.mode, EditorMode::SingleLine) {
+            cx.propagate();
+            return;
+        }
+
+        let row_count = if let Some(row_count) = self.visible_line_count() {
+            (row_count as u32).saturating_sub(1).max(1)
+        } else {
+            return;
+        };
+
+        let text_layout_details = &self.text_layout_details(cx);
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_heads_with(|map, head, goal| {
+                movement::down_by_rows(map, head, row_count, goal, false, &text_layout_details)
+            })
+        });
+    }
+
+    pub fn context_menu_first(&mut self, _: &ContextMenuFirst, cx: &mut ViewContext<Self>) {
+        if let Some(context_menu) = self.context_menu.write().as_mut() {
+            context_menu.select_first(self.project.as_ref(), cx);
         }
     }
 
@@ -5649,15 +6665,43 @@ impl Editor {
         });
     }
 
+    /// Moves each cursor to the given display column of its display row, clamped to
+    /// the row's length. Display columns account for expanded tabs, so the column
+    /// matches what is shown on screen; a column inside a tab lands before the tab.
+    pub fn move_cursor_to_column(&mut self, column: u32, cx: &mut ViewContext<Self>) {
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_cursors_with(|map, head, _| {
+                let column = column.min(map.line_len(head.row()));
+                (
+                    map.clip_point(DisplayPoint::new(head.row(), column), Bias::Left),
+                    SelectionGoal::None,
+                )
+            });
+        })
+    }
+
+    /// Moves a single cursor to the given 1-based row and column, clamped to the buffer,
+    /// replacing any other selections.
+    pub fn go_to_line(&mut self, row: u32, column: u32, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let row = row.saturating_sub(1).min(buffer.max_point().row);
+        let column = column.saturating_sub(1).min(buffer.line_len(row));
+        let point = buffer.clip_point(Point::new(row, column), Bias::Left);
+        self.change_selections(Some(Autoscroll::center()), cx, |s| {
+            s.select_ranges([point..point])
+        });
+    }
+
     pub fn move_to_beginning_of_line(
         &mut self,
         _: &MoveToBeginningOfLine,
         cx: &mut ViewContext<Self>,
     ) {
+        let stop_at_soft_wraps = EditorSettings::get_global(cx).home_stops_at_soft_wraps;
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
             s.move_cursors_with(|map, head, _| {
                 (
-                    movement::indented_line_beginning(map, head, true),
+                    movement::indented_line_beginning(map, head, stop_at_soft_wraps, true),
                     SelectionGoal::None,
                 )
             });
@@ -5672,7 +6716,12 @@ impl Editor {
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
             s.move_heads_with(|map, head, _| {
                 (
-                    movement::indented_line_beginning(map, head, action.stop_at_soft_wraps),
+                    movement::indented_line_beginning(
+                        map,
+                        head,
+                        action.stop_at_soft_wraps,
+                        action.stop_at_soft_wraps,
+                    ),
                     SelectionGoal::None,
                 )
             });
@@ -5736,7 +6785,46 @@ impl Editor {
         });
     }
 
+    pub fn move_to_end_of_buffer_line(
+        &mut self,
+        _: &MoveToEndOfBufferLine,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_cursors_with(|map, head, _| {
+                (movement::buffer_line_end(map, head), SelectionGoal::None)
+            });
+        })
+    }
+
+    pub fn select_to_end_of_buffer_line(
+        &mut self,
+        _: &SelectToEndOfBufferLine,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_heads_with(|map, head, _| {
+                (movement::buffer_line_end(map, head), SelectionGoal::None)
+            });
+        })
+    }
+
+    pub fn delete_to_end_of_buffer_line(
+        &mut self,
+        _: &DeleteToEndOfBufferLine,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.transact(cx, |this, cx| {
+            this.select_to_end_of_buffer_line(&SelectToEndOfBufferLine, cx);
+            this.delete(&Delete, cx);
+        });
+    }
+
     pub fn cut_to_end_of_line(&mut self, _: &CutToEndOfLine, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         self.transact(cx, |this, cx| {
             this.select_to_end_of_line(
                 &SelectToEndOfLine {
@@ -5916,6 +7004,35 @@ impl Editor {
         });
     }
 
+    /// Adds a selection for each of the given ranges while keeping the existing ones.
+    /// Selections that overlap are merged.
+    pub fn add_selections<I, T>(
+        &mut self,
+        ranges: I,
+        autoscroll: Option<Autoscroll>,
+        cx: &mut ViewContext<Self>,
+    ) where
+        I: IntoIterator<Item = Range<T>>,
+        T: ToOffset,
+    {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut selections = self.selections.all::<usize>(cx);
+        self.change_selections(autoscroll, cx, |s| {
+            selections.extend(ranges.into_iter().map(|range| {
+                let start = range.start.to_offset(&buffer);
+                let end = range.end.to_offset(&buffer);
+                Selection {
+                    id: s.new_selection_id(),
+                    start: cmp::min(start, end),
+                    end: cmp::max(start, end),
+                    reversed: start > end,
+                    goal: SelectionGoal::None,
+                }
+            }));
+            s.select(selections);
+        });
+    }
+
     pub fn select_line(&mut self, _: &SelectLine, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let mut selections = self.selections.all::<Point>(cx);
@@ -5931,6 +7048,201 @@ impl Editor {
         });
     }
 
+    /// Selects the word around each cursor, like double-clicking. A non-empty selection
+    /// is extended to include the whole word at its head.
+    pub fn select_word(&mut self, _: &SelectWord, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let mut selections = self.selections.all::<Point>(cx);
+        for selection in &mut selections {
+            let head = selection.head().to_display_point(&display_map);
+            let word_range = movement::surrounding_word(&display_map, head);
+            let word_start = word_range.start.to_point(&display_map);
+            let word_end = word_range.end.to_point(&display_map);
+            if selection.is_empty() {
+                selection.start = word_start;
+                selection.end = word_end;
+                selection.reversed = false;
+            } else if selection.reversed {
+                selection.start = cmp::min(selection.start, word_start);
+            } else {
+                selection.end = cmp::max(selection.end, word_end);
+            }
+            selection.goal = SelectionGoal::None;
+        }
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select(selections);
+        });
+    }
+
+    /// Expands each selection to cover the full lines it spans. Unlike [`Self::select_line`],
+    /// repeating this leaves selections that already end at a line boundary unchanged.
+    pub fn expand_to_line_boundaries(
+        &mut self,
+        _: &ExpandToLineBoundaries,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let mut selections = self.selections.all::<Point>(cx);
+        let max_point = display_map.buffer_snapshot.max_point();
+        for selection in &mut selections {
+            let rows = selection.spanned_rows(false, &display_map);
+            selection.start = Point::new(rows.start, 0);
+            selection.end = cmp::min(max_point, Point::new(rows.end, 0));
+            selection.reversed = false;
+        }
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select(selections);
+        });
+    }
+
+    /// Selects the paragraph around each cursor: the run of non-blank lines bounded by
+    /// blank lines, or the run of blank lines if the cursor is on one. When `around` is
+    /// set, the blank lines following the paragraph are included too, or the preceding
+    /// ones if the paragraph ends the buffer.
+    pub fn select_paragraph(&mut self, around: bool, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let max_row = buffer.max_point().row;
+        let mut selections = self.selections.all::<Point>(cx);
+        for selection in &mut selections {
+            let head_row = selection.head().row;
+            let is_blank = buffer.is_line_blank(head_row);
+
+            let can_extend_up = |row: u32| row > 0 && !is_excerpt_start(buffer, row);
+            let can_extend_down = |row: u32| row < max_row && !is_excerpt_start(buffer, row + 1);
+
+            let mut start_row = head_row;
+            while can_extend_up(start_row) && buffer.is_line_blank(start_row - 1) == is_blank {
+                start_row -= 1;
+            }
+            let mut end_row = head_row;
+            while can_extend_down(end_row) && buffer.is_line_blank(end_row + 1) == is_blank {
+                end_row += 1;
+            }
+
+            if around {
+                if can_extend_down(end_row) {
+                    while can_extend_down(end_row) && buffer.is_line_blank(end_row + 1) != is_blank
+                    {
+                        end_row += 1;
+                    }
+                } else {
+                    while can_extend_up(start_row)
+                        && buffer.is_line_blank(start_row - 1) != is_blank
+                    {
+                        start_row -= 1;
+                    }
+                }
+            }
+
+            selection.start = Point::new(start_row, 0);
+            selection.end = if can_extend_down(end_row) {
+                Point::new(end_row + 1, 0)
+            } else {
+                Point::new(end_row, buffer.line_len(end_row))
+            };
+            selection.reversed = false;
+            selection.goal = SelectionGoal::None;
+        }
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select(selections);
+        });
+    }
+
+    /// Deletes the paragraph around each cursor, along with the blank lines following it.
+    pub fn delete_paragraph(&mut self, _: &DeleteParagraph, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
+        self.transact(cx, |this, cx| {
+            this.select_paragraph(true, cx);
+            this.insert("", cx);
+        });
+    }
+
+    /// Selects the sentence around each cursor within its paragraph. See
+    /// [`sentence_range`] for how sentence boundaries are detected.
+    pub fn select_sentence(&mut self, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let max_row = buffer.max_point().row;
+        let mut selections = self.selections.all::<Point>(cx);
+        for selection in &mut selections {
+            let head = selection.head();
+            if buffer.is_line_blank(head.row) {
+                continue;
+            }
+
+            let mut start_row = head.row;
+            while start_row > 0
+                && !is_excerpt_start(&buffer, start_row)
+                && !buffer.is_line_blank(start_row - 1)
+            {
+                start_row -= 1;
+            }
+            let mut end_row = head.row;
+            while end_row < max_row
+                && !is_excerpt_start(&buffer, end_row + 1)
+                && !buffer.is_line_blank(end_row + 1)
+            {
+                end_row += 1;
+            }
+
+            let paragraph_start = Point::new(start_row, 0).to_offset(&buffer);
+            let paragraph_end = Point::new(end_row, buffer.line_len(end_row)).to_offset(&buffer);
+            let paragraph = buffer
+                .text_for_range(paragraph_start..paragraph_end)
+                .collect::<String>();
+            let range = sentence_range(&paragraph, head.to_offset(&buffer) - paragraph_start);
+
+            selection.start = (paragraph_start + range.start).to_point(&buffer);
+            selection.end = (paragraph_start + range.end).to_point(&buffer);
+            selection.reversed = false;
+            selection.goal = SelectionGoal::None;
+        }
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select(selections);
+        });
+    }
+
+    pub fn select_indent_block(&mut self, _: &SelectIndentBlock, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let max_row = buffer.max_point().row;
+        let mut selections = self.selections.all::<Point>(cx);
+        for selection in &mut selections {
+            let head_row = selection.head().row;
+            let indent = buffer.indent_size_for_line(head_row).len;
+            let is_in_block =
+                |row| buffer.is_line_blank(row) || buffer.indent_size_for_line(row).len >= indent;
+
+            let mut start_row = selection.start.row;
+            while start_row > 0 && is_in_block(start_row - 1) {
+                start_row -= 1;
+            }
+            let mut end_row = selection.end.row;
+            while end_row < max_row && is_in_block(end_row + 1) {
+                end_row += 1;
+            }
+
+            // Blank lines surrounding the block don't belong to it.
+            while start_row < head_row && buffer.is_line_blank(start_row) {
+                start_row += 1;
+            }
+            while end_row > head_row && buffer.is_line_blank(end_row) {
+                end_row -= 1;
+            }
+
+            selection.start = Point::new(start_row, 0);
+            selection.end = Point::new(end_row, buffer.line_len(end_row));
+            selection.reversed = false;
+            selection.goal = SelectionGoal::None;
+        }
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select(selections);
+        });
+    }
+
     pub fn split_selection_into_lines(
         &mut self,
         _: &SplitSelectionIntoLines,
@@ -6071,6 +7383,7 @@ impl Editor {
         &mut self,
         display_map: &DisplaySnapshot,
         replace_newest: bool,
+        case_insensitive: bool,
         autoscroll: Option<Autoscroll>,
         cx: &mut ViewContext<Self>,
     ) -> Result<()> {
@@ -6093,6 +7406,17 @@ impl Editor {
         let buffer = &display_map.buffer_snapshot;
         let mut selections = self.selections.all::<usize>(cx);
         if let Some(mut select_next_state) = self.select_next_state.take() {
+            if select_next_state.case_insensitive != case_insensitive {
+                let newest_selection = selections.iter().max_by_key(|s| s.id).unwrap();
+                let query = buffer
+                    .text_for_range(newest_selection.range())
+                    .collect::<String>();
+                select_next_state.done = query.is_empty();
+                select_next_state.query = AhoCorasickBuilder::new()
+                    .ascii_case_insensitive(case_insensitive)
+                    .build(&[query])?;
+                select_next_state.case_insensitive = case_insensitive;
+            }
             let query = &select_next_state.query;
             if !select_next_state.done {
                 let first_selection = selections.iter().min_by_key(|s| s.id).unwrap();
@@ -6115,6 +7439,10 @@ impl Editor {
                     let query_match = query_match.unwrap(); // can only fail due to I/O
                     let offset_range =
                         start_offset + query_match.start()..start_offset + query_match.end();
+                    if crosses_excerpt_boundary(buffer, &offset_range) {
+                        continue;
+                    }
+
                     let display_range = offset_range.start.to_display_point(&display_map)
                         ..offset_range.end.to_display_point(&display_map);
 
@@ -6210,8 +7538,11 @@ impl Editor {
                         .collect::<String>();
                     let is_empty = query.is_empty();
                     let select_state = SelectNextState {
-                        query: AhoCorasick::new(&[query])?,
+                        query: AhoCorasickBuilder::new()
+                            .ascii_case_insensitive(case_insensitive)
+                            .build(&[query])?,
                         wordwise: true,
+                        case_insensitive,
                         done: is_empty,
                     };
                     self.select_next_state = Some(select_state);
@@ -6220,11 +7551,20 @@ impl Editor {
                 }
             } else if let Some(selected_text) = selected_text {
                 self.select_next_state = Some(SelectNextState {
-                    query: AhoCorasick::new(&[selected_text])?,
+                    query: AhoCorasickBuilder::new()
+                        .ascii_case_insensitive(case_insensitive)
+                        .build(&[selected_text])?,
                     wordwise: false,
+                    case_insensitive,
                     done: false,
                 });
-                self.select_next_match_internal(display_map, replace_newest, autoscroll, cx)?;
+                self.select_next_match_internal(
+                    display_map,
+                    replace_newest,
+                    case_insensitive,
+                    autoscroll,
+                    cx,
+                )?;
             }
         }
         Ok(())
@@ -6238,7 +7578,11 @@ impl Editor {
         self.push_to_selection_history();
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
 
-        self.select_next_match_internal(&display_map, false, None, cx)?;
+        let case_insensitive = self
+            .select_next_state
+            .as_ref()
+            .map_or(false, |state| state.case_insensitive);
+        self.select_next_match_internal(&display_map, false, case_insensitive, None, cx)?;
         let Some(select_next_state) = self.select_next_state.as_mut() else {
             return Ok(());
         };
@@ -6246,16 +7590,18 @@ impl Editor {
             return Ok(());
         }
 
-        let mut new_selections = self.selections.all::<usize>(cx);
-
         let buffer = &display_map.buffer_snapshot;
         let query_matches = select_next_state
             .query
             .stream_find_iter(buffer.bytes_in_range(0..buffer.len()));
 
+        let mut match_ranges = Vec::new();
         for query_match in query_matches {
             let query_match = query_match.unwrap(); // can only fail due to I/O
             let offset_range = query_match.start()..query_match.end();
+            if crosses_excerpt_boundary(buffer, &offset_range) {
+                continue;
+            }
             let display_range = offset_range.start.to_display_point(&display_map)
                 ..offset_range.end.to_display_point(&display_map);
 
@@ -6263,33 +7609,36 @@ impl Editor {
                 || (!movement::is_inside_word(&display_map, display_range.start)
                     && !movement::is_inside_word(&display_map, display_range.end))
             {
-                self.selections.change_with(cx, |selections| {
-                    new_selections.push(Selection {
-                        id: selections.new_selection_id(),
-                        start: offset_range.start,
-                        end: offset_range.end,
-                        reversed: false,
-                        goal: SelectionGoal::None,
-                    });
-                });
+                match_ranges.push(offset_range);
             }
         }
 
+        let mut new_selections = self.selections.all::<usize>(cx);
+        self.selections.change_with(cx, |selections| {
+            new_selections.extend(match_ranges.into_iter().map(|range| Selection {
+                id: selections.new_selection_id(),
+                start: range.start,
+                end: range.end,
+                reversed: false,
+                goal: SelectionGoal::None,
+            }));
+        });
+
+        // Drop overlapping matches in a single pass, keeping the oldest selection.
         new_selections.sort_by_key(|selection| selection.start);
-        let mut ix = 0;
-        while ix + 1 < new_selections.len() {
-            let current_selection = &new_selections[ix];
-            let next_selection = &new_selections[ix + 1];
-            if current_selection.range().overlaps(&next_selection.range()) {
-                if current_selection.id < next_selection.id {
-                    new_selections.remove(ix + 1);
-                } else {
-                    new_selections.remove(ix);
+        let mut merged_selections: Vec<Selection<usize>> = Vec::with_capacity(new_selections.len());
+        for selection in new_selections {
+            if let Some(previous) = merged_selections.last_mut() {
+                if previous.range().overlaps(&selection.range()) {
+                    if selection.id < previous.id {
+                        *previous = selection;
+                    }
+                    continue;
                 }
-            } else {
-                ix += 1;
             }
+            merged_selections.push(selection);
         }
+        let new_selections = merged_selections;
 
         select_next_state.done = true;
         self.unfold_ranges(
@@ -6311,6 +7660,7 @@ impl Editor {
         self.select_next_match_internal(
             &display_map,
             action.replace_newest,
+            action.case_insensitive,
             Some(Autoscroll::newest()),
             cx,
         )?;
@@ -6327,6 +7677,20 @@ impl Editor {
         let buffer = &display_map.buffer_snapshot;
         let mut selections = self.selections.all::<usize>(cx);
         if let Some(mut select_prev_state) = self.select_prev_state.take() {
+            if select_prev_state.case_insensitive != action.case_insensitive {
+                let newest_selection = selections.iter().max_by_key(|s| s.id).unwrap();
+                let query = buffer
+                    .text_for_range(newest_selection.range())
+                    .collect::<String>()
+                    .chars()
+                    .rev()
+                    .collect::<String>();
+                select_prev_state.done = query.is_empty();
+                select_prev_state.query = AhoCorasickBuilder::new()
+                    .ascii_case_insensitive(action.case_insensitive)
+                    .build(&[query])?;
+                select_prev_state.case_insensitive = action.case_insensitive;
+            }
             let query = &select_prev_state.query;
             if !select_prev_state.done {
                 let first_selection = selections.iter().min_by_key(|s| s.id).unwrap();
@@ -6349,6 +7713,9 @@ impl Editor {
                     let query_match = query_match.unwrap(); // can only fail due to I/O
                     let offset_range =
                         end_offset - query_match.end()..end_offset - query_match.start();
+                    if crosses_excerpt_boundary(buffer, &offset_range) {
+                        continue;
+                    }
                     let display_range = offset_range.start.to_display_point(&display_map)
                         ..offset_range.end.to_display_point(&display_map);
 
@@ -6429,8 +7796,11 @@ impl Editor {
                         .collect::<String>();
                     let is_empty = query.is_empty();
                     let select_state = SelectNextState {
-                        query: AhoCorasick::new(&[query.chars().rev().collect::<String>()])?,
+                        query: AhoCorasickBuilder::new()
+                            .ascii_case_insensitive(action.case_insensitive)
+                            .build(&[query.chars().rev().collect::<String>()])?,
                         wordwise: true,
+                        case_insensitive: action.case_insensitive,
                         done: is_empty,
                     };
                     self.select_prev_state = Some(select_state);
@@ -6449,8 +7819,11 @@ impl Editor {
                 });
             } else if let Some(selected_text) = selected_text {
                 self.select_prev_state = Some(SelectNextState {
-                    query: AhoCorasick::new(&[selected_text.chars().rev().collect::<String>()])?,
+                    query: AhoCorasickBuilder::new()
+                        .ascii_case_insensitive(action.case_insensitive)
+                        .build(&[selected_text.chars().rev().collect::<String>()])?,
                     wordwise: false,
+                    case_insensitive: action.case_insensitive,
                     done: false,
                 });
                 self.select_previous(action, cx)?;
@@ -6460,7 +7833,13 @@ impl Editor {
     }
 
     pub fn toggle_comments(&mut self, action: &ToggleComments, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+
         let text_layout_details = &self.text_layout_details(cx);
+        let comment_blank_lines =
+            action.comment_blank_lines || EditorSettings::get_global(cx).comment_blank_lines;
         self.transact(cx, |this, cx| {
             let mut selections = this.selections.all::<Point>(cx);
             let mut edits = Vec::new();
@@ -6538,6 +7917,53 @@ impl Editor {
                 }
             }
 
+            fn inline_comment_delimiter_ranges(
+                snapshot: &MultiBufferSnapshot,
+                range: Range<usize>,
+                full_comment_prefix: &str,
+                full_comment_suffix: &str,
+            ) -> Option<(Range<usize>, Range<usize>)> {
+                let comment_prefix = full_comment_prefix.trim_end_matches(' ');
+                let comment_suffix = full_comment_suffix.trim_start_matches(' ');
+                let text = snapshot.text_for_range(range.clone()).collect::<String>();
+                let trimmed_text = text.trim();
+
+                // The delimiters are either at the selection's edges, ignoring whitespace,
+                // or immediately surrounding the selection.
+                let (start, end) = if trimmed_text.len()
+                    >= comment_prefix.len() + comment_suffix.len()
+                    && trimmed_text.starts_with(comment_prefix)
+                    && trimmed_text.ends_with(comment_suffix)
+                {
+                    let start = range.start + text.len() - text.trim_start().len();
+                    (start, start + trimmed_text.len())
+                } else {
+                    let start = range.start.checked_sub(full_comment_prefix.len())?;
+                    if !snapshot.contains_str_at(start, full_comment_prefix)
+                        || !snapshot.contains_str_at(range.end, full_comment_suffix)
+                    {
+                        return None;
+                    }
+                    (start, range.end + full_comment_suffix.len())
+                };
+
+                // Include any whitespace that matches the delimiters' padding.
+                let mut prefix_end = start + comment_prefix.len();
+                if snapshot.contains_str_at(start, full_comment_prefix)
+                    && start + full_comment_prefix.len() + comment_suffix.len() <= end
+                {
+                    prefix_end = start + full_comment_prefix.len();
+                }
+                let mut suffix_start = end - comment_suffix.len();
+                if end >= prefix_end + full_comment_suffix.len()
+                    && snapshot
+                        .contains_str_at(end - full_comment_suffix.len(), full_comment_suffix)
+                {
+                    suffix_start = end - full_comment_suffix.len();
+                }
+                Some((start..prefix_end, suffix_start..end))
+            }
+
             // TODO: Handle selections that cross excerpts
             for selection in &mut selections {
                 let start_column = snapshot.indent_size_for_line(selection.start.row).len;
@@ -6551,6 +7977,48 @@ impl Editor {
 
                 selection_edit_ranges.clear();
 
+                // If the language has both kinds of comments, selections within a single
+                // line that don't cover the whole line are wrapped in a block comment.
+                let is_inline_selection = !selection.is_empty()
+                    && selection.start.row == selection.end.row
+                    && (selection.start.column > start_column
+                        || selection.end.column < snapshot.line_len(selection.end.row));
+                let has_line_comments = language
+                    .line_comment_prefixes()
+                    .map_or(false, |prefixes| !prefixes.is_empty());
+                if is_inline_selection && has_line_comments {
+                    if let Some((full_comment_prefix, comment_suffix)) =
+                        language.block_comment_delimiters()
+                    {
+                        let range = snapshot.point_to_offset(selection.start)
+                            ..snapshot.point_to_offset(selection.end);
+                        if let Some((prefix_range, suffix_range)) = inline_comment_delimiter_ranges(
+                            snapshot.deref(),
+                            range,
+                            full_comment_prefix,
+                            comment_suffix,
+                        ) {
+                            edits.push((
+                                snapshot.offset_to_point(prefix_range.start)
+                                    ..snapshot.offset_to_point(prefix_range.end),
+                                empty_str.clone(),
+                            ));
+                            edits.push((
+                                snapshot.offset_to_point(suffix_range.start)
+                                    ..snapshot.offset_to_point(suffix_range.end),
+                                empty_str.clone(),
+                            ));
+                        } else {
+                            edits.push((
+                                selection.start..selection.start,
+                                full_comment_prefix.clone(),
+                            ));
+                            edits.push((selection.end..selection.end, comment_suffix.clone()));
+                        }
+                        continue;
+                    }
+                }
+
                 // If multiple selections contain a given row, avoid processing that
                 // row more than once.
                 let mut start_row = selection.start.row;
@@ -6579,9 +8047,13 @@ impl Editor {
                     let comment_prefix = full_comment_prefix.trim_end_matches(' ');
                     let comment_prefix_whitespace = &full_comment_prefix[comment_prefix.len()..];
                     let mut all_selection_lines_are_comments = true;
+                    let mut blank_rows = Vec::new();
 
                     for row in start_row..=end_row {
                         if start_row < end_row && snapshot.is_line_blank(row) {
+                            if comment_blank_lines {
+                                blank_rows.push(row);
+                            }
                             continue;
                         }
 
@@ -6610,10 +8082,32 @@ impl Editor {
                             .map(|r| r.start.column)
                             .min()
                             .unwrap_or(0);
-                        edits.extend(selection_edit_ranges.iter().map(|range| {
-                            let position = Point::new(range.start.row, min_column);
-                            (position..position, full_comment_prefix.clone())
+                        let mut prefix_edits = selection_edit_ranges
+                            .iter()
+                            .map(|range| {
+                                let position = Point::new(range.start.row, min_column);
+                                (position..position, full_comment_prefix.clone())
+                            })
+                            .collect::<Vec<_>>();
+
+                        // Blank lines may be shorter than the block's indentation, so pad them
+                        // up to the column where the other lines' prefixes are inserted.
+                        let indent_char = selection_edit_ranges.first().map_or(' ', |range| {
+                            snapshot.indent_size_for_line(range.start.row).char()
+                        });
+                        prefix_edits.extend(blank_rows.iter().map(|&row| {
+                            let line_len = snapshot.line_len(row);
+                            let position = Point::new(row, line_len.min(min_column));
+                            let padding = indent_char
+                                .to_string()
+                                .repeat(min_column.saturating_sub(line_len) as usize);
+                            (
+                                position..position,
+                                format!("{padding}{full_comment_prefix}").into(),
+                            )
                         }));
+                        prefix_edits.sort_by_key(|(range, _)| range.start);
+                        edits.extend(prefix_edits);
                     }
                 } else if let Some((full_comment_prefix, comment_suffix)) =
                     language.block_comment_delimiters()
@@ -6779,6 +8273,234 @@ impl Editor {
         self.select_larger_syntax_node_stack = stack;
     }
 
+    /// Selects the contents of the innermost bracket pair enclosing each selection. Like
+    /// [`Self::select_larger_syntax_node`], the previous selections are pushed onto the
+    /// stack used by [`Self::select_smaller_syntax_node`] so that expansion can be undone.
+    pub fn select_enclosing_brackets(
+        &mut self,
+        _: &SelectEnclosingBrackets,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.select_enclosing_bracket_ranges(false, cx);
+    }
+
+    pub fn select_enclosing_bracket_pair(
+        &mut self,
+        _: &SelectEnclosingBracketPair,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.select_enclosing_bracket_ranges(true, cx);
+    }
+
+    fn select_enclosing_bracket_ranges(
+        &mut self,
+        include_brackets: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let old_selections = self.selections.all::<usize>(cx).into_boxed_slice();
+
+        let mut stack = mem::take(&mut self.select_larger_syntax_node_stack);
+        let mut selected_larger_range = false;
+        let new_selections = old_selections
+            .iter()
+            .map(|selection| {
+                let old_range = selection.start..selection.end;
+                let bracket_range = buffer
+                    .enclosing_bracket_ranges(old_range.clone())
+                    .into_iter()
+                    .flatten()
+                    .map(|(open, close)| {
+                        if include_brackets {
+                            open.start..close.end
+                        } else {
+                            open.end..close.start
+                        }
+                    })
+                    .filter(|range| {
+                        range.start <= old_range.start
+                            && range.end >= old_range.end
+                            && *range != old_range
+                    })
+                    .min_by_key(|range| range.len());
+
+                let new_range = bracket_range.unwrap_or(old_range);
+                selected_larger_range |= new_range != selection.range();
+                Selection {
+                    id: selection.id,
+                    start: new_range.start,
+                    end: new_range.end,
+                    goal: SelectionGoal::None,
+                    reversed: selection.reversed,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if selected_larger_range {
+            stack.push(old_selections);
+            self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select(new_selections);
+            });
+        }
+        self.select_larger_syntax_node_stack = stack;
+    }
+
+    /// Selects the given text object around each cursor, starting with its inner range.
+    /// Selecting the same object again without otherwise changing the selections toggles
+    /// between the inner range and the around range, which includes the object's
+    /// delimiters or trailing whitespace.
+    pub fn select_text_object(&mut self, object: TextObject, cx: &mut ViewContext<Self>) {
+        let current_selections = self.selections.disjoint_anchors();
+        let (origin, around) = match self.text_object_state.take() {
+            Some(state)
+                if state.object == object
+                    && Arc::ptr_eq(&state.selections, &current_selections) =>
+            {
+                (state.origin, !state.around)
+            }
+            _ => (current_selections, false),
+        };
+
+        self.change_selections(None, cx, |s| s.select_anchors(origin.to_vec()));
+        match object {
+            TextObject::Word => self.select_text_object_ranges(cx, |buffer, offset| {
+                Some(word_object_range(buffer, offset, around))
+            }),
+            TextObject::String => self.select_text_object_ranges(cx, |buffer, offset| {
+                string_object_range(buffer, offset, around)
+            }),
+            TextObject::Paragraph => self.select_paragraph(around, cx),
+            TextObject::Brackets => self.select_enclosing_bracket_ranges(around, cx),
+        }
+
+        self.text_object_state = Some(TextObjectState {
+            object,
+            around,
+            origin,
+            selections: self.selections.disjoint_anchors(),
+        });
+    }
+
+    fn select_text_object_ranges(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+        range_for_offset: impl Fn(&MultiBufferSnapshot, usize) -> Option<Range<usize>>,
+    ) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut selections = self.selections.all::<usize>(cx);
+        for selection in &mut selections {
+            if let Some(range) = range_for_offset(&buffer, selection.head()) {
+                selection.start = range.start;
+                selection.end = range.end;
+                selection.reversed = false;
+                selection.goal = SelectionGoal::None;
+            }
+        }
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+    }
+
+    /// Selects the contents of the next balanced `open`/`close` pair after each
+    /// selection. Selections without such a pair after them are left unchanged.
+    ///
+    /// Closing delimiters without a matching opening one are skipped, so repeated
+    /// invocations step from one pair's contents to the next. When `open` and `close`
+    /// are the same character (e.g. quotes), alternate occurrences form a pair and
+    /// pairs don't nest.
+    pub fn select_next_pair(&mut self, open: char, close: char, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let new_selections = self
+            .selections
+            .all::<usize>(cx)
+            .into_iter()
+            .map(|selection| {
+                let mut scan_start = selection.end;
+                // When the selection is already a pair's contents, skip its closing
+                // delimiter, which would otherwise open a pair if it's symmetric.
+                let selects_pair_contents = !selection.is_empty()
+                    && buffer.reversed_chars_at(selection.start).next() == Some(open)
+                    && buffer.chars_at(selection.end).next() == Some(close);
+                if selects_pair_contents {
+                    scan_start += close.len_utf8();
+                }
+
+                let mut contents_start = None;
+                let mut depth = 0;
+                let mut offset = scan_start;
+                for ch in buffer.chars_at(scan_start) {
+                    if ch == close && depth > 0 {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else if ch == open {
+                        if contents_start.is_none() {
+                            contents_start = Some(offset + ch.len_utf8());
+                        }
+                        depth += 1;
+                    }
+                    offset += ch.len_utf8();
+                }
+
+                match contents_start {
+                    Some(start) if depth == 0 => Selection {
+                        id: selection.id,
+                        start,
+                        end: offset,
+                        goal: SelectionGoal::None,
+                        reversed: false,
+                    },
+                    _ => selection,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select(new_selections);
+        });
+    }
+
+    /// Deletes from each cursor to the nearer bracket of its innermost enclosing pair,
+    /// leaving the brackets themselves in place.
+    pub fn delete_to_enclosing_bracket(
+        &mut self,
+        _: &DeleteToEnclosingBracket,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.read_only(cx) {
+            return;
+        }
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let mut selections = self.selections.all::<usize>(cx);
+        for selection in &mut selections {
+            let head = selection.head();
+            let contents = buffer
+                .enclosing_bracket_ranges(head..head)
+                .into_iter()
+                .flatten()
+                .map(|(open, close)| open.end..close.start)
+                .filter(|contents| contents.start <= head && head <= contents.end)
+                .min_by_key(|contents| contents.len());
+
+            let range = match contents {
+                Some(contents) if contents.end - head <= head - contents.start => {
+                    head..contents.end
+                }
+                Some(contents) => contents.start..head,
+                None => head..head,
+            };
+            selection.start = range.start;
+            selection.end = range.end;
+            selection.reversed = false;
+            selection.goal = SelectionGoal::None;
+        }
+
+        self.transact(cx, |this, cx| {
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+            this.insert("", cx);
+        });
+    }
+
     pub fn move_to_enclosing_bracket(
         &mut self,
         _: &MoveToEnclosingBracket,
@@ -6867,14 +8589,37 @@ impl Editor {
     }
 
     fn go_to_diagnostic(&mut self, _: &GoToDiagnostic, cx: &mut ViewContext<Self>) {
-        self.go_to_diagnostic_impl(Direction::Next, cx)
+        self.go_to_diagnostic_impl(Direction::Next, DiagnosticSeverity::WARNING, cx)
     }
 
     fn go_to_prev_diagnostic(&mut self, _: &GoToPrevDiagnostic, cx: &mut ViewContext<Self>) {
-        self.go_to_diagnostic_impl(Direction::Prev, cx)
+        self.go_to_diagnostic_impl(Direction::Prev, DiagnosticSeverity::WARNING, cx)
+    }
+
+    fn go_to_diagnostic_with_severity(
+        &mut self,
+        action: &GoToDiagnosticWithSeverity,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.go_to_diagnostic_impl(Direction::Next, action.min_severity.into(), cx)
+    }
+
+    fn go_to_prev_diagnostic_with_severity(
+        &mut self,
+        action: &GoToPrevDiagnosticWithSeverity,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.go_to_diagnostic_impl(Direction::Prev, action.min_severity.into(), cx)
     }
 
-    pub fn go_to_diagnostic_impl(&mut self, direction: Direction, cx: &mut ViewContext<Self>) {
+    /// Moves to the next or previous primary diagnostic that is at least as severe as
+    /// `min_severity`, wrapping around the buffer.
+    pub fn go_to_diagnostic_impl(
+        &mut self,
+        direction: Direction,
+        min_severity: DiagnosticSeverity,
+        cx: &mut ViewContext<Self>,
+    ) {
         let buffer = self.buffer.read(cx).snapshot(cx);
         let selection = self.selections.newest::<usize>(cx);
 
@@ -6917,7 +8662,7 @@ impl Editor {
             };
             let group = diagnostics.find_map(|entry| {
                 if entry.diagnostic.is_primary
-                    && entry.diagnostic.severity <= DiagnosticSeverity::WARNING
+                    && entry.diagnostic.severity <= min_severity
                     && !entry.range.is_empty()
                     && Some(entry.range.end) != active_primary_range.as_ref().map(|r| *r.end())
                     && !entry.range.contains(&search_start)
@@ -6960,6 +8705,16 @@ impl Editor {
         }
     }
 
+    /// Returns the diff markers for the given buffer rows, relative to the buffer's diff base.
+    pub fn diff_markers(&self, rows: Range<u32>, cx: &AppContext) -> Vec<(u32, DiffMarker)> {
+        self.buffer
+            .read(cx)
+            .snapshot(cx)
+            .git_diff_hunks_in_range(rows.clone())
+            .flat_map(|hunk| diff_hunk_to_markers(hunk, &rows))
+            .collect()
+    }
+
     fn go_to_hunk(&mut self, _: &GoToHunk, cx: &mut ViewContext<Self>) {
         let snapshot = self
             .display_map
@@ -7713,7 +9468,11 @@ impl Editor {
                 for (block_id, diagnostic) in &active_diagnostics.blocks {
                     new_styles.insert(
                         *block_id,
-                        diagnostic_block_renderer(diagnostic.clone(), is_valid),
+                        diagnostic_block_renderer(
+                            diagnostic.clone(),
+                            is_valid,
+                            diagnostic.is_primary,
+                        ),
                     );
                 }
                 self.display_map
@@ -7757,7 +9516,11 @@ impl Editor {
                             style: BlockStyle::Fixed,
                             position: buffer.anchor_after(entry.range.start),
                             height: message_height,
-                            render: diagnostic_block_renderer(diagnostic, true),
+                            render: diagnostic_block_renderer(
+                                diagnostic,
+                                true,
+                                entry.diagnostic.is_primary,
+                            ),
                             disposition: BlockDisposition::Below,
                         }
                     }),
@@ -7801,7 +9564,7 @@ impl Editor {
                 s.clear_pending();
             }
         });
-        self.selections_did_change(false, &old_cursor_position, cx);
+        self.selections_did_change(false, true, &old_cursor_position, cx);
     }
 
     fn push_to_selection_history(&mut self) {
@@ -7825,12 +9588,17 @@ impl Editor {
 
     fn start_transaction_at(&mut self, now: Instant, cx: &mut ViewContext<Self>) {
         self.end_selection(cx);
+        self.fold_history.transaction_depth += 1;
         if let Some(tx_id) = self
             .buffer
             .update(cx, |buffer, cx| buffer.start_transaction_at(now, cx))
         {
             self.selection_history
                 .insert_transaction(tx_id, self.selections.disjoint_anchors());
+            if self.fold_history.transaction_depth == 1 {
+                self.fold_history.folds_at_transaction_start =
+                    Some(self.fold_ranges_for_history(cx));
+            }
         }
     }
 
@@ -7839,10 +9607,17 @@ impl Editor {
         now: Instant,
         cx: &mut ViewContext<Self>,
     ) -> Option<TransactionId> {
-        if let Some(tx_id) = self
+        let tx_id = self
             .buffer
-            .update(cx, |buffer, cx| buffer.end_transaction_at(now, cx))
+            .update(cx, |buffer, cx| buffer.end_transaction_at(now, cx));
+        self.fold_history.transaction_depth = self.fold_history.transaction_depth.saturating_sub(1);
+        if self.fold_history.transaction_depth == 0
+            && self.fold_history.folds_at_transaction_start.is_some()
         {
+            let folds = self.fold_ranges_for_history(cx);
+            self.fold_history.end_transaction(tx_id, folds);
+        }
+        if let Some(tx_id) = tx_id {
             if let Some((_, end_selections)) = self.selection_history.transaction_mut(tx_id) {
                 *end_selections = Some(self.selections.disjoint_anchors());
             } else {
@@ -7856,6 +9631,38 @@ impl Editor {
         }
     }
 
+    fn fold_ranges_for_history(&mut self, cx: &mut ViewContext<Self>) -> Arc<[Range<Anchor>]> {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        display_map
+            .folds_in_range(0..display_map.buffer_snapshot.len())
+            .map(|fold| fold.range.start..fold.range.end)
+            .collect()
+    }
+
+    /// Unfolds the given folds and folds the given ranges, leaving other folds intact.
+    fn replace_folds(
+        &mut self,
+        unfold: &[Range<Anchor>],
+        fold: &[Range<Anchor>],
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.display_map.update(cx, |map, cx| {
+            map.unfold(unfold.iter().cloned(), false, cx);
+            map.fold(fold.iter().cloned(), cx);
+        });
+        cx.notify();
+    }
+
+    /// Replaces the current folds with the given ones.
+    fn restore_folds(&mut self, folds: &[Range<Anchor>], cx: &mut ViewContext<Self>) {
+        let len = self.buffer.read(cx).len(cx);
+        self.display_map.update(cx, |map, cx| {
+            map.unfold([0..len], true, cx);
+            map.fold(folds.iter().cloned(), cx);
+        });
+        cx.notify();
+    }
+
     pub fn fold(&mut self, _: &actions::Fold, cx: &mut ViewContext<Self>) {
         let mut fold_ranges = Vec::new();
 
@@ -7880,6 +9687,59 @@ impl Editor {
             }
         }
 
+        self.fold_manual_ranges(fold_ranges, true, cx);
+    }
+
+    pub fn fold_recursive(&mut self, _: &actions::FoldRecursive, cx: &mut ViewContext<Self>) {
+        let mut fold_ranges = Vec::new();
+
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+
+        let selections = self.selections.all_adjusted(cx);
+        for selection in selections {
+            let range = selection.range().sorted();
+            let buffer_start_row = range.start.row;
+
+            for row in (0..=range.end.row).rev() {
+                let fold_range = display_map.foldable_range(row);
+
+                if let Some(fold_range) = fold_range {
+                    if fold_range.end.row >= buffer_start_row {
+                        // Also fold every foldable block nested within this one.
+                        fold_ranges.extend(
+                            (fold_range.start.row + 1..fold_range.end.row)
+                                .filter_map(|row| display_map.foldable_range(row)),
+                        );
+                        fold_ranges.push(fold_range);
+                        if row <= range.start.row {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        fold_ranges.sort_unstable_by_key(|range| (range.start, range.end));
+        fold_ranges.dedup();
+        self.fold_manual_ranges(fold_ranges, true, cx);
+    }
+
+    pub fn fold_all(&mut self, _: &actions::FoldAll, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let max_row = display_map.buffer_snapshot.max_point().row;
+
+        let mut fold_ranges = Vec::new();
+        let mut row = 0;
+        while row <= max_row {
+            if let Some(fold_range) = display_map.foldable_range(row) {
+                // Skip the rows of this range, as anything nested within it gets folded with it.
+                row = fold_range.end.row + 1;
+                fold_ranges.push(fold_range);
+            } else {
+                row += 1;
+            }
+        }
+
         self.fold_ranges(fold_ranges, true, cx);
     }
 
@@ -7894,8 +9754,45 @@ impl Editor {
                 .iter()
                 .any(|selection| fold_range.overlaps(&selection.range()));
 
-            self.fold_ranges(std::iter::once(fold_range), autoscroll, cx);
+            self.fold_manual_ranges(vec![fold_range], autoscroll, cx);
+        }
+    }
+
+    /// Folds every block whose first line is indented at least `level` levels deep, so
+    /// that level 0 folds all top-level blocks and level 1 keeps them open but folds
+    /// the blocks nested directly within them.
+    pub fn fold_at_level(&mut self, fold_at_level: &FoldAtLevel, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let max_row = buffer.max_point().row;
+
+        let mut fold_ranges = Vec::new();
+        let mut row = 0;
+        while row <= max_row {
+            let indent = buffer.indent_size_for_line(row);
+            let indent_level = match indent.kind {
+                IndentKind::Tab => indent.len,
+                IndentKind::Space => {
+                    let tab_size = self
+                        .buffer
+                        .read(cx)
+                        .settings_at(Point::new(row, 0), cx)
+                        .tab_size;
+                    indent.len / tab_size.get()
+                }
+            };
+
+            if indent_level >= fold_at_level.level {
+                if let Some(fold_range) = display_map.foldable_range(row) {
+                    row = fold_range.end.row + 1;
+                    fold_ranges.push(fold_range);
+                    continue;
+                }
+            }
+            row += 1;
         }
+
+        self.fold_ranges(fold_ranges, true, cx);
     }
 
     pub fn unfold_lines(&mut self, _: &UnfoldLines, cx: &mut ViewContext<Self>) {
@@ -7917,6 +9814,31 @@ impl Editor {
         self.unfold_ranges(ranges, true, true, cx);
     }
 
+    pub fn unfold_all(&mut self, _: &actions::UnfoldAll, cx: &mut ViewContext<Self>) {
+        let len = self.buffer.read(cx).len(cx);
+        self.unfold_ranges([0..len], true, true, cx);
+    }
+
+    /// Unfolds every fold that wasn't created manually, such as those created by
+    /// [`Self::fold_all`]. Manual folds that haven't been unfolded since are kept.
+    pub fn unfold_all_automatic(&mut self, _: &UnfoldAllAutomatic, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let to_offset_range =
+            |range: &Range<Anchor>| range.start.to_offset(buffer)..range.end.to_offset(buffer);
+        let mut current_folds = display_map
+            .folds_in_range(0..buffer.len())
+            .map(|fold| to_offset_range(&fold.range))
+            .collect::<HashSet<_>>();
+
+        let mut manual_folds = mem::take(&mut self.manual_folds);
+        manual_folds.retain(|range| current_folds.remove(&to_offset_range(range)));
+        if !current_folds.is_empty() {
+            self.restore_folds(&manual_folds, cx);
+        }
+        self.manual_folds = manual_folds;
+    }
+
     pub fn unfold_at(&mut self, unfold_at: &UnfoldAt, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
 
@@ -7932,60 +9854,123 @@ impl Editor {
             .iter()
             .any(|selection| selection.range().overlaps(&intersection_range));
 
-        self.unfold_ranges(std::iter::once(intersection_range), true, autoscroll, cx)
+        self.unfold_ranges(std::iter::once(intersection_range), true, autoscroll, cx);
+    }
+
+    /// Unfolds every fold containing the given position, including folds that start or
+    /// end at it, so that it becomes visible. Returns whether anything was unfolded.
+    pub fn unfold_intersecting(&mut self, position: Anchor, cx: &mut ViewContext<Self>) -> bool {
+        let offset = position.to_offset(&self.buffer.read(cx).snapshot(cx));
+        self.unfold_ranges([offset..offset], true, false, cx)
     }
 
     pub fn fold_selected_ranges(&mut self, _: &FoldSelectedRanges, cx: &mut ViewContext<Self>) {
         let selections = self.selections.all::<Point>(cx);
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let line_mode = self.selections.line_mode;
-        let ranges = selections.into_iter().map(|s| {
+        let ranges = selections.into_iter().filter_map(|s| {
             if line_mode {
                 let start = Point::new(s.start.row, 0);
                 let end = Point::new(s.end.row, display_map.buffer_snapshot.line_len(s.end.row));
-                start..end
+                Some(start..end)
+            } else if s.is_empty() {
+                // An empty selection folds the innermost foldable region containing it.
+                (0..=s.head().row).rev().find_map(|row| {
+                    display_map
+                        .foldable_range(row)
+                        .filter(|range| range.end.row >= s.head().row)
+                })
             } else {
-                s.start..s.end
+                Some(s.start..s.end)
             }
         });
-        self.fold_ranges(ranges, true, cx);
+        self.fold_manual_ranges(ranges.collect(), true, cx);
+    }
+
+    /// Folds ranges chosen by the user, remembering them so that
+    /// [`Self::unfold_all_automatic`] keeps them folded.
+    fn fold_manual_ranges(
+        &mut self,
+        ranges: Vec<Range<Point>>,
+        auto_scroll: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        self.manual_folds.extend(
+            ranges
+                .iter()
+                .map(|range| buffer.anchor_after(range.start)..buffer.anchor_before(range.end)),
+        );
+        self.fold_ranges(ranges, auto_scroll, cx);
     }
 
+    /// Folds the given ranges, returning whether the folded text actually changed. The
+    /// editor is only notified and autoscrolled when it did.
     pub fn fold_ranges<T: ToOffset + Clone>(
         &mut self,
         ranges: impl IntoIterator<Item = Range<T>>,
         auto_scroll: bool,
         cx: &mut ViewContext<Self>,
-    ) {
+    ) -> bool {
         let mut ranges = ranges.into_iter().peekable();
-        if ranges.peek().is_some() {
-            self.display_map.update(cx, |map, cx| map.fold(ranges, cx));
+        if ranges.peek().is_none() {
+            return false;
+        }
 
+        let changed = self.display_map.update(cx, |map, cx| map.fold(ranges, cx));
+        if changed {
             if auto_scroll {
                 self.request_autoscroll(Autoscroll::fit(), cx);
             }
 
             cx.notify();
         }
+        changed
     }
 
+    /// Unfolds the folds intersecting the given ranges, returning whether the folded text
+    /// actually changed. The editor is only notified and autoscrolled when it did.
     pub fn unfold_ranges<T: ToOffset + Clone>(
         &mut self,
         ranges: impl IntoIterator<Item = Range<T>>,
         inclusive: bool,
         auto_scroll: bool,
         cx: &mut ViewContext<Self>,
-    ) {
-        let mut ranges = ranges.into_iter().peekable();
-        if ranges.peek().is_some() {
-            self.display_map
-                .update(cx, |map, cx| map.unfold(ranges, inclusive, cx));
+    ) -> bool {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let ranges = ranges
+            .into_iter()
+            .map(|range| range.start.to_offset(&buffer)..range.end.to_offset(&buffer))
+            .collect::<Vec<_>>();
+        if ranges.is_empty() {
+            return false;
+        }
+
+        let changed = self.display_map.update(cx, |map, cx| {
+            map.unfold(ranges.iter().cloned(), inclusive, cx)
+        });
+        if changed {
+            // Forget the manual folds that were just unfolded, along with those whose
+            // text has since been deleted.
+            self.manual_folds.retain(|fold| {
+                let fold = fold.to_offset(&buffer);
+                let unfolded = ranges.iter().any(|range| {
+                    if inclusive {
+                        fold.start <= range.end && fold.end >= range.start
+                    } else {
+                        fold.start < range.end && fold.end > range.start
+                    }
+                });
+                fold.start < fold.end && !unfolded
+            });
+
             if auto_scroll {
                 self.request_autoscroll(Autoscroll::fit(), cx);
             }
 
             cx.notify();
         }
+        changed
     }
 
     pub fn set_gutter_hovered(&mut self, hovered: bool, cx: &mut ViewContext<Self>) {
@@ -8043,6 +10028,15 @@ impl Editor {
             .longest_row()
     }
 
+    /// Returns the width of the longest display row, laid out with the editor's text style.
+    pub fn content_width(&self, cx: &mut WindowContext) -> Pixels {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let text_layout_details = self.text_layout_details(cx);
+        display_map
+            .layout_row(display_map.longest_row(), &text_layout_details)
+            .width
+    }
+
     pub fn max_point(&self, cx: &mut AppContext) -> DisplayPoint {
         self.display_map
             .update(cx, |map, cx| map.snapshot(cx))
@@ -8053,6 +10047,32 @@ impl Editor {
         self.buffer.read(cx).read(cx).text()
     }
 
+    /// Returns the text of every selection, joined by newlines.
+    pub fn selected_text(&self, cx: &AppContext) -> String {
+        let selections = self.selections.all::<usize>(cx);
+        let buffer = self.buffer.read(cx).read(cx);
+        selections
+            .iter()
+            .map(|selection| buffer.text_for_range(selection.range()).collect::<String>())
+            .join("\n")
+    }
+
+    /// Returns the number of whitespace-delimited words in the buffer.
+    pub fn word_count(&self, cx: &AppContext) -> usize {
+        let buffer = self.buffer.read(cx).read(cx);
+        count_words(buffer.text_for_range(0..buffer.len()))
+    }
+
+    /// Returns the number of whitespace-delimited words within the selections.
+    pub fn selected_word_count(&self, cx: &AppContext) -> usize {
+        let selections = self.selections.all::<usize>(cx);
+        let buffer = self.buffer.read(cx).read(cx);
+        selections
+            .iter()
+            .map(|selection| count_words(buffer.text_for_range(selection.range())))
+            .sum()
+    }
+
     pub fn text_option(&self, cx: &AppContext) -> Option<String> {
         let text = self.text(cx);
         let text = text.trim();
@@ -8064,16 +10084,35 @@ impl Editor {
         Some(text.to_string())
     }
 
+    /// Replaces the buffer's entire text. A leading byte order mark is preserved, and
+    /// is not duplicated if the new text begins with one too.
     pub fn set_text(&mut self, text: impl Into<Arc<str>>, cx: &mut ViewContext<Self>) {
         self.transact(cx, |this, cx| {
             this.buffer
                 .read(cx)
                 .as_singleton()
                 .expect("you can only call set_text on editors for singleton buffers")
-                .update(cx, |buffer, cx| buffer.set_text(text, cx));
+                .update(cx, |buffer, cx| {
+                    if buffer.has_bom() {
+                        let text: Arc<str> = text.into();
+                        let text = text.strip_prefix(BYTE_ORDER_MARK).unwrap_or(&text);
+                        buffer.edit([(BYTE_ORDER_MARK.len_utf8()..buffer.len(), text)], None, cx);
+                    } else {
+                        buffer.set_text(text, cx);
+                    }
+                });
         });
     }
 
+    /// Returns whether this editor's buffer begins with a byte order mark. Always false
+    /// for multi-buffers.
+    pub fn has_bom(&self, cx: &AppContext) -> bool {
+        self.buffer
+            .read(cx)
+            .as_singleton()
+            .map_or(false, |buffer| buffer.read(cx).has_bom())
+    }
+
     pub fn display_text(&self, cx: &mut AppContext) -> String {
         self.display_map
             .update(cx, |map, cx| map.snapshot(cx))
@@ -8106,10 +10145,23 @@ impl Editor {
         match mode {
             language_settings::SoftWrap::None => SoftWrap::None,
             language_settings::SoftWrap::EditorWidth => SoftWrap::EditorWidth,
-            language_settings::SoftWrap::PreferredLineLength => {
-                SoftWrap::Column(settings.preferred_line_length)
-            }
+            language_settings::SoftWrap::PreferredLineLength => SoftWrap::Column(
+                self.soft_wrap_column_override
+                    .unwrap_or(settings.preferred_line_length),
+            ),
+        }
+    }
+
+    /// Soft-wraps this editor at the given column, or disables soft wrapping when it is `0`.
+    pub fn set_wrap_column(&mut self, action: &SetWrapColumn, cx: &mut ViewContext<Self>) {
+        if action.column == 0 {
+            self.soft_wrap_mode_override = Some(language_settings::SoftWrap::None);
+            self.soft_wrap_column_override = None;
+        } else {
+            self.soft_wrap_mode_override = Some(language_settings::SoftWrap::PreferredLineLength);
+            self.soft_wrap_column_override = Some(action.column);
         }
+        cx.notify();
     }
 
     pub fn set_soft_wrap_mode(
@@ -8148,6 +10200,7 @@ impl Editor {
     pub fn toggle_soft_wrap(&mut self, _: &ToggleSoftWrap, cx: &mut ViewContext<Self>) {
         if self.soft_wrap_mode_override.is_some() {
             self.soft_wrap_mode_override.take();
+            self.soft_wrap_column_override.take();
         } else {
             let soft_wrap = match self.soft_wrap_mode(cx) {
                 SoftWrap::None => language_settings::SoftWrap::EditorWidth,
@@ -8155,6 +10208,28 @@ impl Editor {
             };
             self.soft_wrap_mode_override = Some(soft_wrap);
         }
+        // Rewrapping can move the cursor's display row, so keep it in view.
+        self.request_autoscroll(Autoscroll::fit(), cx);
+        cx.notify();
+    }
+
+    pub fn line_numbers_mode(&self, cx: &AppContext) -> LineNumbersMode {
+        self.line_numbers_mode_override.unwrap_or_else(|| {
+            if EditorSettings::get_global(cx).relative_line_numbers {
+                LineNumbersMode::Relative
+            } else {
+                LineNumbersMode::Absolute
+            }
+        })
+    }
+
+    pub fn cycle_line_numbers(&mut self, _: &CycleLineNumbers, cx: &mut ViewContext<Self>) {
+        let line_numbers_mode = match self.line_numbers_mode(cx) {
+            LineNumbersMode::Absolute => LineNumbersMode::Relative,
+            LineNumbersMode::Relative => LineNumbersMode::Hidden,
+            LineNumbersMode::Hidden => LineNumbersMode::Absolute,
+        };
+        self.line_numbers_mode_override = Some(line_numbers_mode);
         cx.notify();
     }
 
@@ -8196,12 +10271,46 @@ impl Editor {
         }
     }
 
+    /// Highlights the given range of display rows, replacing any rows highlighted before.
     pub fn highlight_rows(&mut self, rows: Option<Range<u32>>) {
-        self.highlighted_rows = rows;
+        self.highlighted_rows = rows.into_iter().filter(|rows| !rows.is_empty()).collect();
     }
 
+    /// Returns the range spanning all highlighted display rows.
     pub fn highlighted_rows(&self) -> Option<Range<u32>> {
-        self.highlighted_rows.clone()
+        let first = self.highlighted_rows.first()?;
+        let last = self.highlighted_rows.last()?;
+        Some(first.start..last.end)
+    }
+
+    /// Highlights each of the given display rows, which don't need to be contiguous. Like
+    /// [`Editor::highlight_rows`], this replaces any rows highlighted before.
+    pub fn set_highlighted_rows(&mut self, rows: impl IntoIterator<Item = u32>) {
+        let mut ranges: Vec<Range<u32>> = Vec::new();
+        for row in rows.into_iter().collect::<BTreeSet<_>>() {
+            match ranges.last_mut() {
+                Some(range) if range.end == row => range.end += 1,
+                _ => ranges.push(row..row + 1),
+            }
+        }
+        self.highlighted_rows = ranges;
+    }
+
+    pub fn clear_highlighted_rows(&mut self) {
+        self.highlight_rows(None);
+    }
+
+    pub fn highlighted_row_set(&self) -> BTreeSet<u32> {
+        self.highlighted_rows.iter().cloned().flatten().collect()
+    }
+
+    /// Returns the contiguous runs of highlighted display rows within the given range.
+    pub fn highlighted_row_ranges(&self, rows: Range<u32>) -> Vec<Range<u32>> {
+        self.highlighted_rows
+            .iter()
+            .map(|range| range.start.max(rows.start)..range.end.min(rows.end))
+            .filter(|range| !range.is_empty())
+            .collect()
     }
 
     pub fn highlight_background<T: 'static>(
@@ -8477,6 +10586,17 @@ impl Editor {
                 cx.emit(EditorEvent::BufferEdited);
                 cx.emit(SearchEvent::MatchesInvalidated);
 
+                // Once the content of an auto-height editor fits again, scroll back to
+                // the top so that no blank gap is left below the last line.
+                if let EditorMode::AutoHeight { max_lines } = self.mode {
+                    let scroll_position = self.scroll_position(cx);
+                    let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+                    let row_count = display_map.max_point().row() as usize + 1;
+                    if row_count <= max_lines && scroll_position.y > 0. {
+                        self.set_scroll_position(point(scroll_position.x, 0.), cx);
+                    }
+                }
+
                 if *singleton_buffer_edited {
                     if let Some(project) = &self.project {
                         let project = project.read(cx);
@@ -8546,6 +10666,10 @@ impl Editor {
     }
 
     fn settings_changed(&mut self, cx: &mut ViewContext<Self>) {
+        self.scroll_manager.refresh_vertical_scroll_margin(cx);
+        if !self.cursor_shape_overridden {
+            self.cursor_shape = EditorSettings::get_global(cx).cursor_shape;
+        }
         self.refresh_copilot_suggestions(true, cx);
         self.refresh_inlay_hints(
             InlayHintRefreshReason::SettingsChange(inlay_hint_settings(
@@ -9104,6 +11228,190 @@ fn ending_row(next_selection: &Selection<Point>, display_map: &DisplaySnapshot)
     }
 }
 
+/// Returns the range of the word at `offset`. When `around` is set, the whitespace
+/// following the word is included, or the whitespace preceding it if there is none.
+fn word_object_range(buffer: &MultiBufferSnapshot, offset: usize, around: bool) -> Range<usize> {
+    let (mut range, _) = buffer.surrounding_word(offset);
+    if around {
+        let is_space = |ch: &char| *ch == ' ' || *ch == '\t';
+        let trailing: usize = buffer
+            .chars_at(range.end)
+            .take_while(is_space)
+            .map(char::len_utf8)
+            .sum();
+        if trailing > 0 {
+            range.end += trailing;
+        } else {
+            range.start -= buffer
+                .reversed_chars_at(range.start)
+                .take_while(is_space)
+                .map(char::len_utf8)
+                .sum::<usize>();
+        }
+    }
+    range
+}
+
+/// Returns the range of the quoted string containing `offset` on its line, excluding the
+/// quotes unless `around` is set.
+fn string_object_range(
+    buffer: &MultiBufferSnapshot,
+    offset: usize,
+    around: bool,
+) -> Option<Range<usize>> {
+    let row = offset.to_point(buffer).row;
+    let line_start = Point::new(row, 0).to_offset(buffer);
+    let mut open_quote: Option<(char, usize)> = None;
+    let mut escaped = false;
+    let mut position = line_start;
+    for ch in buffer.chars_at(line_start).take_while(|ch| *ch != '\n') {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if matches!(ch, '"' | '\'' | '`') {
+            match open_quote {
+                Some((quote, start)) if quote == ch => {
+                    if (start..=position).contains(&offset) {
+                        return Some(if around {
+                            start..position + 1
+                        } else {
+                            start + 1..position
+                        });
+                    }
+                    open_quote = None;
+                }
+                Some(_) => {}
+                None => open_quote = Some((ch, position)),
+            }
+        }
+        position += ch.len_utf8();
+    }
+    None
+}
+
+/// Returns the indentation of the line containing `position`, up to `position`, and
+/// whether `position` lies within that line's indentation.
+fn paste_indent(snapshot: &MultiBufferSnapshot, position: Point) -> (String, bool) {
+    let indent_size = snapshot.indent_size_for_line(position.row);
+    let indent = indent_size
+        .chars()
+        .take(indent_size.len.min(position.column) as usize)
+        .collect();
+    (indent, position.column <= indent_size.len)
+}
+
+/// Strips the indentation shared by the non-blank lines of `text`, then indents
+/// every line after the first with `indent`. The first line is pasted at the
+/// cursor, so its indentation is only stripped when `strip_first_line` is set,
+/// for a cursor within the destination line's indentation.
+fn reindent_pasted_text(text: &str, indent: &str, strip_first_line: bool) -> String {
+    let indent_len = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    // The first line is usually copied from after its indentation, so it only
+    // determines the base indent when no other line does.
+    let mut lines = text.split('\n');
+    let first_line = lines.next().unwrap_or_default();
+    let base_indent = lines
+        .filter(|line| !line.trim().is_empty())
+        .map(indent_len)
+        .min()
+        .unwrap_or_else(|| indent_len(first_line));
+
+    let mut result = String::with_capacity(text.len());
+    for (ix, line) in text.split('\n').enumerate() {
+        if ix > 0 {
+            result.push('\n');
+        } else if !strip_first_line {
+            result.push_str(line);
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if ix > 0 {
+            result.push_str(indent);
+        }
+        result.push_str(&line[cmp::min(base_indent, indent_len(line))..]);
+    }
+    result
+}
+
+/// Returns whether `range` spans the synthesized separator between two excerpts, in
+/// which case it doesn't correspond to any contiguous text.
+fn crosses_excerpt_boundary(buffer: &MultiBufferSnapshot, range: &Range<usize>) -> bool {
+    buffer
+        .excerpt_boundaries_in_range((Bound::Excluded(range.start), Bound::Excluded(range.end)))
+        .next()
+        .is_some()
+}
+
+/// Returns whether an excerpt begins at the given row, which line-based text objects
+/// shouldn't extend across.
+fn is_excerpt_start(buffer: &MultiBufferSnapshot, row: u32) -> bool {
+    let start = Point::new(row, 0);
+    buffer
+        .excerpt_boundaries_in_range(start..=start)
+        .next()
+        .is_some()
+}
+
+/// Returns the range of the sentence containing `offset` in `text`, without its surrounding
+/// whitespace. Sentences end with `.`, `!` or `?` followed by whitespace, except for periods
+/// ending single letters or common abbreviations, such as "e.g." or "Dr.".
+fn sentence_range(text: &str, offset: usize) -> Range<usize> {
+    const ABBREVIATIONS: &[&str] = &["Dr", "Jr", "Mr", "Mrs", "Ms", "Prof", "Sr", "St", "vs"];
+
+    let sentence_ends = text.char_indices().filter_map(|(ix, ch)| {
+        let end = ix + ch.len_utf8();
+        if !matches!(ch, '.' | '!' | '?')
+            || !text[end..].chars().next().map_or(true, char::is_whitespace)
+        {
+            return None;
+        }
+        if ch == '.' {
+            let word = text[..ix]
+                .rsplit(|c: char| !c.is_alphabetic())
+                .next()
+                .unwrap_or("");
+            if word.chars().count() == 1 || ABBREVIATIONS.contains(&word) {
+                return None;
+            }
+        }
+        Some(end)
+    });
+
+    let mut start = 0;
+    let mut end = text.len();
+    for sentence_end in sentence_ends {
+        if sentence_end > offset {
+            end = sentence_end;
+            break;
+        }
+        start = sentence_end;
+    }
+
+    let sentence = &text[start..end];
+    let start = start + sentence.len() - sentence.trim_start().len();
+    let end = start.max(end - (sentence.len() - sentence.trim_end().len()));
+    start..end
+}
+
+/// Counts whitespace-delimited words across chunks of text, including words that
+/// span chunk boundaries.
+fn count_words<'a>(chunks: impl Iterator<Item = &'a str>) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for ch in chunks.flat_map(str::chars) {
+        if ch.is_whitespace() {
+            in_word = false;
+        } else if !in_word {
+            in_word = true;
+            count += 1;
+        }
+    }
+    count
+}
+
 impl EditorSnapshot {
     pub fn remote_selections_in_range<'a>(
         &'a self,
@@ -9152,6 +11460,58 @@ impl EditorSnapshot {
         self.scroll_anchor.scroll_position(&self.display_snapshot)
     }
 
+    /// Returns the brackets within the given display rows along with the nesting depth
+    /// of their pair, sorted by position. Returns nothing unless rainbow brackets are
+    /// enabled.
+    pub fn bracket_depths(&self, display_rows: Range<u32>) -> Vec<(Range<DisplayPoint>, usize)> {
+        if !self.rainbow_brackets {
+            return Vec::new();
+        }
+
+        let buffer = &self.display_snapshot.buffer_snapshot;
+        let start =
+            DisplayPoint::new(display_rows.start, 0).to_offset(&self.display_snapshot, Bias::Left);
+        let end = if display_rows.end > self.max_point().row() {
+            buffer.len()
+        } else {
+            DisplayPoint::new(display_rows.end, 0).to_offset(&self.display_snapshot, Bias::Right)
+        };
+        let Some(pairs) = buffer.bracket_ranges(start..end) else {
+            return Vec::new();
+        };
+        let mut pairs = pairs.collect::<Vec<_>>();
+        pairs.sort_unstable_by_key(|(open, close)| (open.start, Reverse(close.end)));
+        pairs.dedup();
+
+        let mut enclosing_pair_ends = Vec::new();
+        let mut brackets = Vec::with_capacity(pairs.len() * 2);
+        for (open, close) in pairs {
+            while enclosing_pair_ends
+                .last()
+                .map_or(false, |end| *end <= open.start)
+            {
+                enclosing_pair_ends.pop();
+            }
+            let depth = enclosing_pair_ends.len();
+            enclosing_pair_ends.push(close.end);
+            brackets.push((open, depth));
+            brackets.push((close, depth));
+        }
+        brackets.sort_unstable_by_key(|(range, _)| range.start);
+
+        brackets
+            .into_iter()
+            .filter(|(range, _)| {
+                range.start >= start && range.end <= end && !self.intersects_fold(range.start)
+            })
+            .map(|(range, depth)| {
+                let range = range.start.to_display_point(&self.display_snapshot)
+                    ..range.end.to_display_point(&self.display_snapshot);
+                (range, depth)
+            })
+            .collect()
+    }
+
     pub fn gutter_dimensions(
         &self,
         font_id: FontId,
@@ -9166,8 +11526,12 @@ impl EditorSnapshot {
             let gutter_padding = (em_width * gutter_padding_factor).round();
             // Avoid flicker-like gutter resizes when the line number gains another digit and only resize the gutter on files with N*10^5 lines.
             let min_width_for_number_on_gutter = em_width * 4.0;
-            let gutter_width =
-                max_line_number_width.max(min_width_for_number_on_gutter) + gutter_padding * 2.0;
+            let line_numbers_width = if self.line_numbers_mode == LineNumbersMode::Hidden {
+                px(0.)
+            } else {
+                max_line_number_width.max(min_width_for_number_on_gutter)
+            };
+            let gutter_width = line_numbers_width + gutter_padding * 2.0;
             let gutter_margin = -descent;
 
             GutterDimensions {
@@ -9471,7 +11835,10 @@ impl ViewInputHandler for Editor {
                 );
             }
 
+            // Don't autoclose brackets while the text is still being composed.
+            let use_autoclose = mem::replace(&mut this.use_autoclose, false);
             this.handle_input(text, cx);
+            this.use_autoclose = use_autoclose;
 
             if let Some(new_selected_range) = new_selected_range_utf16 {
                 let snapshot = this.buffer.read(cx).read(cx);
@@ -9655,7 +12022,13 @@ impl InvalidationRegion for SnippetState {
     }
 }
 
-pub fn diagnostic_block_renderer(diagnostic: Diagnostic, _is_valid: bool) -> RenderBlock {
+/// Renders a diagnostic's message below its range. The primary diagnostic of a group is
+/// emboldened so that it stands out from its related information.
+pub fn diagnostic_block_renderer(
+    diagnostic: Diagnostic,
+    _is_valid: bool,
+    is_primary: bool,
+) -> RenderBlock {
     let (text_without_backticks, code_ranges) = highlight_diagnostic_message(&diagnostic);
 
     Arc::new(move |cx: &mut BlockContext| {
@@ -9663,6 +12036,9 @@ pub fn diagnostic_block_renderer(diagnostic: Diagnostic, _is_valid: bool) -> Ren
 
         let mut text_style = cx.text_style().clone();
         text_style.color = diagnostic_style(diagnostic.severity, true, cx.theme().status());
+        if is_primary {
+            text_style.font_weight = FontWeight::BOLD;
+        }
 
         h_flex()
             .id(cx.block_id)
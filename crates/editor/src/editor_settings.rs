@@ -1,3 +1,4 @@
+use language::CursorShape;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
@@ -12,6 +13,13 @@ pub struct EditorSettings {
     pub scrollbar: Scrollbar,
     pub relative_line_numbers: bool,
     pub seed_search_query_from_cursor: SeedQuerySetting,
+    pub scroll_margin_rows: u32,
+    pub tab_over_closing_brackets: bool,
+    pub collapse_selection_after_copy: bool,
+    pub rainbow_brackets: bool,
+    pub comment_blank_lines: bool,
+    pub home_stops_at_soft_wraps: bool,
+    pub cursor_shape: CursorShape,
 }
 
 /// When to populate a new search's query based on the text under the cursor.
@@ -86,6 +94,39 @@ pub struct EditorSettingsContent {
     ///
     /// Default: always
     pub seed_search_query_from_cursor: Option<SeedQuerySetting>,
+    /// The number of rows to keep visible above and below the cursor when scrolling.
+    ///
+    /// Default: 3
+    pub scroll_margin_rows: Option<u32>,
+    /// Whether pressing tab right before an autoclosed closing bracket moves
+    /// the cursor past it instead of inserting indentation.
+    ///
+    /// Default: false
+    pub tab_over_closing_brackets: Option<bool>,
+    /// Whether copying collapses each selection to its cursor position
+    /// instead of keeping the copied text selected.
+    ///
+    /// Default: false
+    pub collapse_selection_after_copy: Option<bool>,
+    /// Whether to color brackets according to their nesting depth, cycling
+    /// through the theme's accent colors.
+    ///
+    /// Default: false
+    pub rainbow_brackets: Option<bool>,
+    /// Whether toggling comments on a block of lines also comments the blank
+    /// lines within it, aligned with the rest of the block.
+    ///
+    /// Default: false
+    pub comment_blank_lines: Option<bool>,
+    /// Whether moving to the beginning of a soft-wrapped line stops at the start
+    /// of the wrapped row before the line's indentation and first column.
+    ///
+    /// Default: true
+    pub home_stops_at_soft_wraps: Option<bool>,
+    /// The shape of the cursor: "bar", "block", "underscore" or "hollow".
+    ///
+    /// Default: bar
+    pub cursor_shape: Option<CursorShape>,
 }
 
 /// Scrollbar related settings
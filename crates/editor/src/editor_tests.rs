@@ -149,6 +149,54 @@ fn test_edit_events(cx: &mut TestAppContext) {
     assert_eq!(mem::take(&mut *events.borrow_mut()), []);
 }
 
+#[gpui::test]
+fn test_selections_changed_events(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let view = cx.add_window({
+        let events = events.clone();
+        |cx| {
+            cx.subscribe(&cx.view().clone(), move |_, _, event: &EditorEvent, _| {
+                if matches!(event, EditorEvent::SelectionsChanged { .. }) {
+                    events.borrow_mut().push(event.clone());
+                }
+            })
+            .detach();
+            build_editor(MultiBuffer::build_simple("abc\ndef", cx), cx)
+        }
+    });
+
+    _ = view.update(cx, |view, cx| view.move_right(&MoveRight, cx));
+    assert_eq!(
+        mem::take(&mut *events.borrow_mut()),
+        [EditorEvent::SelectionsChanged { local: true }]
+    );
+
+    _ = view.update(cx, |view, cx| {
+        view.select_next(&SelectNext::default(), cx).unwrap();
+        assert!(view.select_next_state.is_some());
+    });
+    mem::take(&mut *events.borrow_mut());
+
+    // Selecting the same ranges again doesn't emit an event, but still resets
+    // the state that depends on the previous selections.
+    _ = view.update(cx, |view, cx| {
+        let selections = view.selections.all::<usize>(cx);
+        view.change_selections(None, cx, |s| s.select(selections));
+        assert!(view.select_next_state.is_none());
+        let anchors = view.selections.disjoint_anchors();
+        view.change_selections(None, cx, |s| s.select_anchors(anchors.to_vec()));
+    });
+    assert_eq!(mem::take(&mut *events.borrow_mut()), []);
+
+    _ = view.update(cx, |view, cx| view.move_down(&MoveDown, cx));
+    assert_eq!(
+        mem::take(&mut *events.borrow_mut()),
+        [EditorEvent::SelectionsChanged { local: true }]
+    );
+}
+
 #[gpui::test]
 fn test_undo_redo_with_selection_restoration(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -220,6 +268,82 @@ fn test_undo_redo_with_selection_restoration(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_undo_redo_with_multiple_cursors(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        oneˇ
+        twoˇ
+        «threeˇ»
+        ˇfour
+        fiˇve
+    "});
+
+    cx.update_editor(|editor, cx| editor.handle_input("x", cx));
+    cx.assert_editor_state(indoc! {"
+        onexˇ
+        twoxˇ
+        xˇ
+        xˇfour
+        fixˇve
+    "});
+
+    // A single undo reverts the edits at every cursor and restores all of them.
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        oneˇ
+        twoˇ
+        «threeˇ»
+        ˇfour
+        fiˇve
+    "});
+
+    cx.update_editor(|editor, cx| editor.redo(&Redo, cx));
+    cx.assert_editor_state(indoc! {"
+        onexˇ
+        twoxˇ
+        xˇ
+        xˇfour
+        fixˇve
+    "});
+}
+
+#[gpui::test]
+async fn test_undo_redo_selection_without_edits(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("ˇone two three");
+    cx.update_editor(|editor, cx| {
+        editor.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        editor.move_to_next_word_end(&MoveToNextWordEnd, cx);
+    });
+    cx.assert_editor_state("one twoˇ three");
+
+    // Cursor jumps are stepped through without touching the text.
+    cx.update_editor(|editor, cx| editor.undo_selection(&UndoSelection, cx));
+    cx.assert_editor_state("oneˇ two three");
+    cx.update_editor(|editor, cx| editor.undo_selection(&UndoSelection, cx));
+    cx.assert_editor_state("ˇone two three");
+    cx.update_editor(|editor, cx| editor.redo_selection(&RedoSelection, cx));
+    cx.assert_editor_state("oneˇ two three");
+
+    // Movements that leave the selections unchanged only record a single history entry.
+    cx.update_editor(|editor, cx| {
+        editor.move_to_beginning(&MoveToBeginning, cx);
+        let undo_stack_len = editor.selection_history.undo_stack.len();
+        editor.move_left(&MoveLeft, cx);
+        editor.move_left(&MoveLeft, cx);
+        editor.move_left(&MoveLeft, cx);
+        assert_eq!(
+            editor.selection_history.undo_stack.len(),
+            undo_stack_len + 1
+        );
+    });
+}
+
 #[gpui::test]
 fn test_ime_composition(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -323,6 +447,62 @@ fn test_ime_composition(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_composition_without_autoclose(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            brackets: BracketPairConfig {
+                pairs: vec![BracketPair {
+                    start: "(".to_string(),
+                    end: ")".to_string(),
+                    close: true,
+                    newline: true,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    cx.set_state("fooˇ");
+
+    // Brackets aren't autoclosed while the text is being composed.
+    cx.update_editor(|editor, cx| {
+        editor.replace_composition("(", cx);
+        assert_eq!(
+            editor.marked_text_ranges(cx),
+            Some(vec![OffsetUtf16(3)..OffsetUtf16(4)])
+        );
+    });
+    cx.assert_editor_state("foo(ˇ");
+
+    cx.update_editor(|editor, cx| {
+        editor.replace_composition("(a", cx);
+        assert_eq!(
+            editor.marked_text_ranges(cx),
+            Some(vec![OffsetUtf16(3)..OffsetUtf16(5)])
+        );
+    });
+    cx.assert_editor_state("foo(aˇ");
+
+    // Committing replaces the composed text and ends the composition.
+    cx.update_editor(|editor, cx| {
+        editor.commit_composition("(b", cx);
+        assert_eq!(editor.marked_text_ranges(cx), None);
+    });
+    cx.assert_editor_state("foo(bˇ");
+
+    // The whole composition is undone at once.
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    cx.assert_editor_state("fooˇ");
+}
+
 #[gpui::test]
 fn test_selection_with_mouse(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -423,6 +603,175 @@ fn test_selection_with_mouse(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+fn test_newest_cursor_position(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaaaaa\nbbbbbb\ncccccc\ndddddd", cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = editor.update(cx, |view, cx| {
+        view.fold_ranges([Point::new(0, 0)..Point::new(1, 0)], true, cx);
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(2, 1)..Point::new(2, 1)])
+        });
+        assert_eq!(view.newest_cursor_position(cx), Point::new(2, 1));
+        assert_eq!(
+            view.newest_cursor_display_position(cx),
+            DisplayPoint::new(1, 1)
+        );
+
+        // The pending selection is the newest while the mouse is being dragged.
+        view.begin_selection(DisplayPoint::new(2, 2), true, 1, cx);
+        view.update_selection(
+            DisplayPoint::new(2, 4),
+            0,
+            gpui::Point::<f32>::default(),
+            cx,
+        );
+        assert_eq!(view.newest_cursor_position(cx), Point::new(3, 4));
+        assert_eq!(
+            view.newest_cursor_display_position(cx),
+            DisplayPoint::new(2, 4)
+        );
+
+        view.end_selection(cx);
+        assert_eq!(view.newest_cursor_position(cx), Point::new(3, 4));
+    });
+}
+
+#[gpui::test]
+async fn test_collapse_to_newest(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("onˇe\ntwo\nthree");
+    cx.update_editor(|editor, cx| {
+        editor.add_selection_below(&Default::default(), cx);
+        editor.add_selection_below(&Default::default(), cx);
+    });
+    cx.assert_editor_state("onˇe\ntwˇo\nthˇree");
+
+    cx.update_editor(|editor, cx| editor.collapse_to_newest(&CollapseToNewest, cx));
+    cx.assert_editor_state("one\ntwo\nthˇree");
+
+    // A non-empty newest selection collapses to its head.
+    cx.set_state("«oneˇ»\nt«ˇwo»");
+    cx.update_editor(|editor, cx| editor.collapse_to_newest(&CollapseToNewest, cx));
+    cx.assert_editor_state("one\ntˇwo");
+}
+
+#[gpui::test]
+fn test_merging_adjacent_selections(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abcdef", cx);
+        build_editor(buffer, cx)
+    });
+
+    // By default, selections that touch are merged.
+    _ = editor.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| s.select_ranges([0..2, 2..4, 5..5, 5..5]));
+        assert_eq!(view.selections.ranges::<usize>(cx), [0..4, 5..5]);
+
+        view.change_selections(None, cx, |s| s.select_ranges([0..2]));
+        view.begin_selection(DisplayPoint::new(0, 2), true, 1, cx);
+        view.update_selection(
+            DisplayPoint::new(0, 4),
+            0,
+            gpui::Point::<f32>::default(),
+            cx,
+        );
+        assert_eq!(view.selections.ranges::<usize>(cx), [0..4]);
+        view.end_selection(cx);
+        assert_eq!(view.selections.ranges::<usize>(cx), [0..4]);
+    });
+
+    // Otherwise, only overlapping or identical selections are merged.
+    _ = editor.update(cx, |view, cx| {
+        view.set_merge_adjacent_selections(false);
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([0..2, 2..4, 3..5, 5..5, 5..5])
+        });
+        assert_eq!(view.selections.ranges::<usize>(cx), [0..2, 2..5, 5..5]);
+
+        view.change_selections(None, cx, |s| s.select_ranges([0..2]));
+        view.begin_selection(DisplayPoint::new(0, 2), true, 1, cx);
+        view.update_selection(
+            DisplayPoint::new(0, 4),
+            0,
+            gpui::Point::<f32>::default(),
+            cx,
+        );
+        assert_eq!(view.selections.ranges::<usize>(cx), [0..2, 2..4]);
+        view.end_selection(cx);
+        assert_eq!(view.selections.ranges::<usize>(cx), [0..2, 2..4]);
+    });
+}
+
+#[gpui::test]
+async fn test_select_to(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("one «twoˇ» three");
+    cx.update_editor(|editor, cx| {
+        let position = editor.buffer().read(cx).snapshot(cx).anchor_before(12);
+        editor.select_to(position, cx);
+        assert!(!editor.selections.newest::<usize>(cx).reversed);
+    });
+    cx.assert_editor_state("one «two thˇ»ree");
+
+    cx.update_editor(|editor, cx| {
+        let position = editor.buffer().read(cx).snapshot(cx).anchor_before(1);
+        editor.select_to(position, cx);
+        assert!(editor.selections.newest::<usize>(cx).reversed);
+    });
+    cx.assert_editor_state("o«ˇne »two three");
+}
+
+#[gpui::test]
+fn test_merging_unsorted_selections(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abcdefghij", cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        let selection = |id, range: Range<usize>| Selection {
+            id,
+            start: range.start,
+            end: range.end,
+            reversed: false,
+            goal: SelectionGoal::None,
+        };
+        view.change_selections(None, cx, |s| {
+            s.select(vec![
+                selection(100, 6..8),
+                selection(101, 2..5),
+                selection(102, 0..3),
+            ])
+        });
+
+        assert_eq!(
+            view.selections
+                .all::<usize>(cx)
+                .into_iter()
+                .map(|s| s.range())
+                .collect::<Vec<_>>(),
+            &[0..5, 6..8]
+        );
+        let newest = view.selections.newest::<usize>(cx);
+        assert_eq!(newest.id, 102);
+        assert_eq!(newest.range(), 0..5);
+    });
+}
+
 #[gpui::test]
 fn test_canceling_pending_selection(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -801,2491 +1150,4836 @@ fn test_fold_action(cx: &mut TestAppContext) {
 }
 
 #[gpui::test]
-fn test_move_cursor(cx: &mut TestAppContext) {
+fn test_fold_selected_ranges(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
-    let buffer = cx.update(|cx| MultiBuffer::build_simple(&sample_text(6, 6, 'a'), cx));
-    let view = cx.add_window(|cx| build_editor(buffer.clone(), cx));
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(
+            &"
+                impl Foo {
+                    // Hello!
 
-    _ = buffer.update(cx, |buffer, cx| {
-        buffer.edit(
-            vec![
-                (Point::new(1, 0)..Point::new(1, 0), "\t"),
-                (Point::new(1, 1)..Point::new(1, 1), "\t"),
-            ],
-            None,
+                    fn a() {
+                        1
+                    }
+
+                    fn b() {
+                        2
+                    }
+                }
+            "
+            .unindent(),
             cx,
         );
+        build_editor(buffer.clone(), cx)
     });
+
     _ = view.update(cx, |view, cx| {
+        // A cursor inside a function body folds the whole function
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(8, 5)..DisplayPoint::new(8, 5)]);
+        });
+        view.fold_selected_ranges(&FoldSelectedRanges, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)]
-        );
+            view.display_text(cx),
+            "
+                impl Foo {
+                    // Hello!
 
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0)]
-        );
+                    fn a() {
+                        1
+                    }
 
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4)]
+                    fn b() {⋯
+                    }
+                }
+            "
+            .unindent(),
         );
 
-        view.move_left(&MoveLeft, cx);
+        // A non-empty selection folds exactly the selected range
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(1, 4)..DisplayPoint::new(1, 13)]);
+        });
+        view.fold_selected_ranges(&FoldSelectedRanges, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0)]
-        );
+            view.display_text(cx),
+            "
+                impl Foo {
+                    ⋯
 
-        view.move_up(&MoveUp, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)]
-        );
+                    fn a() {
+                        1
+                    }
 
-        view.move_to_end(&MoveToEnd, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(5, 6)..DisplayPoint::new(5, 6)]
+                    fn b() {⋯
+                    }
+                }
+            "
+            .unindent(),
         );
+    });
+}
 
-        view.move_to_beginning(&MoveToBeginning, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)]
+#[gpui::test]
+fn test_unfold_all_automatic_keeps_manual_folds(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(
+            &"
+                impl Foo {
+                    fn a() {
+                        1
+                    }
+
+                    fn b() {
+                        2
+                    }
+                }
+            "
+            .unindent(),
+            cx,
         );
+        build_editor(buffer.clone(), cx)
+    });
 
+    _ = view.update(cx, |view, cx| {
         view.change_selections(None, cx, |s| {
-            s.select_display_ranges([DisplayPoint::new(0, 1)..DisplayPoint::new(0, 2)]);
+            s.select_display_ranges([DisplayPoint::new(6, 4)..DisplayPoint::new(6, 4)]);
         });
-        view.select_to_beginning(&SelectToBeginning, cx);
+        view.fold(&Fold, cx);
+        view.fold_all(&FoldAll, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(0, 1)..DisplayPoint::new(0, 0)]
+            view.display_text(cx),
+            "
+                impl Foo {⋯
+                }
+            "
+            .unindent(),
         );
 
-        view.select_to_end(&SelectToEnd, cx);
+        view.unfold_all_automatic(&UnfoldAllAutomatic, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[DisplayPoint::new(0, 1)..DisplayPoint::new(5, 6)]
+            view.display_text(cx),
+            "
+                impl Foo {
+                    fn a() {
+                        1
+                    }
+
+                    fn b() {⋯
+                    }
+                }
+            "
+            .unindent(),
         );
+
+        // A manual fold unfolded by hand isn't restored, even if it gets folded again.
+        view.unfold_lines(&UnfoldLines, cx);
+        let fold_range = view
+            .display_map
+            .update(cx, |map, cx| map.snapshot(cx))
+            .foldable_range(5)
+            .unwrap();
+        view.fold_ranges([fold_range], true, cx);
+        view.unfold_all_automatic(&UnfoldAllAutomatic, cx);
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
+
+        // Once unfolded, a manual fold is no longer restored.
+        view.fold(&Fold, cx);
+        view.unfold_all(&UnfoldAll, cx);
+        view.fold_all(&FoldAll, cx);
+        view.unfold_all_automatic(&UnfoldAllAutomatic, cx);
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
     });
 }
 
 #[gpui::test]
-fn test_move_cursor_multibyte(cx: &mut TestAppContext) {
+fn test_fold_all_and_unfold_all(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("ⓐⓑⓒⓓⓔ\nabcde\nαβγδε", cx);
-        build_editor(buffer.clone(), cx)
-    });
+        let buffer = MultiBuffer::build_simple(
+            &"
+                impl Foo {
+                    // Hello!
 
-    assert_eq!('ⓐ'.len_utf8(), 3);
-    assert_eq!('α'.len_utf8(), 2);
+                    fn a() {
+                        1
+                    }
 
-    _ = view.update(cx, |view, cx| {
-        view.fold_ranges(
-            vec![
-                Point::new(0, 6)..Point::new(0, 12),
-                Point::new(1, 2)..Point::new(1, 4),
-                Point::new(2, 4)..Point::new(2, 8),
-            ],
-            true,
-            cx,
-        );
-        assert_eq!(view.display_text(cx), "ⓐⓑ⋯ⓔ\nab⋯e\nαβ⋯ε");
+                    fn b() {
+                        2
+                    }
+                }
 
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(0, "ⓐ".len())]
-        );
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(0, "ⓐⓑ".len())]
-        );
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(0, "ⓐⓑ⋯".len())]
+                fn c() {
+                    3
+                }
+            "
+            .unindent(),
+            cx,
         );
+        build_editor(buffer.clone(), cx)
+    });
 
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "ab⋯e".len())]
-        );
-        view.move_left(&MoveLeft, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "ab⋯".len())]
-        );
-        view.move_left(&MoveLeft, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "ab".len())]
-        );
-        view.move_left(&MoveLeft, cx);
+    _ = view.update(cx, |view, cx| {
+        view.fold_all(&FoldAll, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "a".len())]
-        );
+            view.display_text(cx),
+            "
+                impl Foo {⋯
+                }
 
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "α".len())]
-        );
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "αβ".len())]
-        );
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "αβ⋯".len())]
-        );
-        view.move_right(&MoveRight, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "αβ⋯ε".len())]
+                fn c() {⋯
+                }
+            "
+            .unindent(),
         );
 
-        view.move_up(&MoveUp, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "ab⋯e".len())]
-        );
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "αβ⋯ε".len())]
-        );
-        view.move_up(&MoveUp, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "ab⋯e".len())]
-        );
+        view.unfold_all(&UnfoldAll, cx);
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
 
-        view.move_up(&MoveUp, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(0, "ⓐⓑ".len())]
-        );
-        view.move_left(&MoveLeft, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(0, "ⓐ".len())]
-        );
-        view.move_left(&MoveLeft, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(0, "".len())]
-        );
+        // Nested folds are removed along with the outer ones.
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(4, 0)..DisplayPoint::new(8, 0)]);
+        });
+        view.fold(&Fold, cx);
+        view.fold_all(&FoldAll, cx);
+        view.unfold_all(&UnfoldAll, cx);
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
     });
 }
 
 #[gpui::test]
-fn test_move_cursor_different_line_lengths(cx: &mut TestAppContext) {
+fn test_fold_ranges_reports_changes(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("ⓐⓑⓒⓓⓔ\nabcd\nαβγ\nabcd\nⓐⓑⓒⓓⓔ\n", cx);
-        build_editor(buffer.clone(), cx)
+        let buffer = MultiBuffer::build_simple(&sample_text(6, 4, 'a'), cx);
+        build_editor(buffer, cx)
     });
-    _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([empty_range(0, "ⓐⓑⓒⓓⓔ".len())]);
-        });
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(1, "abcd".len())]
-        );
 
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "αβγ".len())]
-        );
+    _ = view.update(cx, |view, cx| {
+        // Changing nothing doesn't request an autoscroll.
+        assert!(!view.unfold_ranges([Point::new(1, 0)..Point::new(3, 0)], true, true, cx));
+        assert!(!view.fold_ranges(Vec::<Range<Point>>::new(), true, cx));
+        assert!(!view.scroll_manager.has_autoscroll_request());
 
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(3, "abcd".len())]
-        );
+        assert!(view.fold_ranges([Point::new(1, 2)..Point::new(3, 2)], true, cx));
+        assert!(view.scroll_manager.has_autoscroll_request());
+        assert_eq!(view.display_text(cx), "aaaa\nbb⋯dd\neeee\nffff");
 
-        view.move_down(&MoveDown, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(4, "ⓐⓑⓒⓓⓔ".len())]
-        );
+        // Folding an already folded range, or a range within it, changes nothing.
+        assert!(!view.fold_ranges([Point::new(1, 2)..Point::new(3, 2)], true, cx));
+        assert!(!view.fold_ranges([Point::new(2, 0)..Point::new(2, 2)], true, cx));
 
-        view.move_up(&MoveUp, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(3, "abcd".len())]
-        );
+        assert!(view.unfold_ranges([Point::new(1, 3)..Point::new(1, 3)], false, true, cx));
+        assert_eq!(view.display_text(cx), "aaaa\nbbbb\n⋯cc\ndddd\neeee\nffff");
+        assert!(!view.unfold_ranges([Point::new(1, 3)..Point::new(1, 3)], false, true, cx));
 
-        view.move_up(&MoveUp, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[empty_range(2, "αβγ".len())]
-        );
+        assert!(view.unfold_ranges([Point::new(2, 1)..Point::new(2, 1)], false, true, cx));
+        assert_eq!(view.display_text(cx), view.buffer.read(cx).read(cx).text());
     });
 }
 
 #[gpui::test]
-fn test_beginning_end_of_line(cx: &mut TestAppContext) {
+fn test_fold_at_level(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("abc\n  def", cx);
-        build_editor(buffer, cx)
-    });
-    _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
-                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4),
-            ]);
-        });
-    });
+        let buffer = MultiBuffer::build_simple(
+            &"
+                mod a {
+                    fn b() {
+                        if x {
+                            1
+                        }
+                    }
+                }
 
-    _ = view.update(cx, |view, cx| {
-        view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
-            ]
+                mod c {
+                    fn d() {
+                        2
+                    }
+                }
+            "
+            .unindent(),
+            cx,
         );
+        build_editor(buffer.clone(), cx)
     });
 
     _ = view.update(cx, |view, cx| {
-        view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
+        view.fold_at_level(&FoldAtLevel { level: 1 }, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0),
-            ]
-        );
-    });
+            view.display_text(cx),
+            "
+                mod a {
+                    fn b() {⋯
+                    }
+                }
 
-    _ = view.update(cx, |view, cx| {
-        view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
-            ]
+                mod c {
+                    fn d() {⋯
+                    }
+                }
+            "
+            .unindent(),
         );
-    });
 
-    _ = view.update(cx, |view, cx| {
-        view.move_to_end_of_line(&MoveToEndOfLine, cx);
+        view.unfold_all(&UnfoldAll, cx);
+        view.fold_at_level(&FoldAtLevel { level: 2 }, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 3)..DisplayPoint::new(0, 3),
-                DisplayPoint::new(1, 5)..DisplayPoint::new(1, 5),
-            ]
-        );
-    });
+            view.display_text(cx),
+            "
+                mod a {
+                    fn b() {
+                        if x {⋯
+                        }
+                    }
+                }
 
-    // Moving to the end of line again is a no-op.
-    _ = view.update(cx, |view, cx| {
-        view.move_to_end_of_line(&MoveToEndOfLine, cx);
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 3)..DisplayPoint::new(0, 3),
-                DisplayPoint::new(1, 5)..DisplayPoint::new(1, 5),
-            ]
+                mod c {
+                    fn d() {
+                        2
+                    }
+                }
+            "
+            .unindent(),
         );
-    });
 
-    _ = view.update(cx, |view, cx| {
-        view.move_left(&MoveLeft, cx);
-        view.select_to_beginning_of_line(
-            &SelectToBeginningOfLine {
-                stop_at_soft_wraps: true,
-            },
-            cx,
-        );
+        view.unfold_all(&UnfoldAll, cx);
+        view.fold_at_level(&FoldAtLevel { level: 0 }, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 2),
-            ]
-        );
-    });
+            view.display_text(cx),
+            "
+                mod a {⋯
+                }
 
-    _ = view.update(cx, |view, cx| {
-        view.select_to_beginning_of_line(
-            &SelectToBeginningOfLine {
-                stop_at_soft_wraps: true,
-            },
-            cx,
-        );
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 0),
-            ]
+                mod c {⋯
+                }
+            "
+            .unindent(),
         );
     });
+}
 
-    _ = view.update(cx, |view, cx| {
-        view.select_to_beginning_of_line(
-            &SelectToBeginningOfLine {
-                stop_at_soft_wraps: true,
-            },
+#[gpui::test]
+fn test_fold_recursive_action(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(
+            &"
+                impl Foo {
+                    fn a() {
+                        if true {
+                            1
+                        }
+                    }
+
+                    fn b() {
+                        2
+                    }
+                }
+            "
+            .unindent(),
             cx,
         );
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 2),
-            ]
-        );
+        build_editor(buffer.clone(), cx)
     });
 
     _ = view.update(cx, |view, cx| {
-        view.select_to_end_of_line(
-            &SelectToEndOfLine {
-                stop_at_soft_wraps: true,
-            },
-            cx,
-        );
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2)]);
+        });
+        view.fold_recursive(&FoldRecursive, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 3),
-                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 5),
-            ]
+            view.display_text(cx),
+            "
+                impl Foo {⋯
+                }
+            "
+            .unindent(),
         );
-    });
 
-    _ = view.update(cx, |view, cx| {
-        view.delete_to_end_of_line(&DeleteToEndOfLine, cx);
-        assert_eq!(view.display_text(cx), "ab\n  de");
+        // Nested blocks were folded too, and remain folded when the outer block is unfolded.
+        view.unfold_lines(&UnfoldLines, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
-                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4),
-            ]
+            view.display_text(cx),
+            "
+                impl Foo {
+                    fn a() {⋯
+                    }
+
+                    fn b() {⋯
+                    }
+                }
+            "
+            .unindent(),
         );
-    });
 
-    _ = view.update(cx, |view, cx| {
-        view.delete_to_beginning_of_line(&DeleteToBeginningOfLine, cx);
-        assert_eq!(view.display_text(cx), "\n");
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4)]);
+        });
+        view.unfold_lines(&UnfoldLines, cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            &[
-                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0),
-            ]
+            view.display_text(cx),
+            "
+                impl Foo {
+                    fn a() {
+                        if true {⋯
+                        }
+                    }
+
+                    fn b() {⋯
+                    }
+                }
+            "
+            .unindent(),
         );
     });
 }
 
 #[gpui::test]
-fn test_prev_next_word_boundary(cx: &mut TestAppContext) {
+fn test_unfold_intersecting(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("use std::str::{foo, bar}\n\n  {baz.qux()}", cx);
+        let buffer = MultiBuffer::build_simple("one two three four five", cx);
         build_editor(buffer, cx)
     });
     _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(0, 11)..DisplayPoint::new(0, 11),
-                DisplayPoint::new(2, 4)..DisplayPoint::new(2, 4),
-            ])
-        });
-
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
-        assert_selection_ranges("use std::ˇstr::{foo, bar}\n\n  {ˇbaz.qux()}", view, cx);
-
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
-        assert_selection_ranges("use stdˇ::str::{foo, bar}\n\n  ˇ{baz.qux()}", view, cx);
-
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
-        assert_selection_ranges("use ˇstd::str::{foo, bar}\n\nˇ  {baz.qux()}", view, cx);
-
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
-        assert_selection_ranges("ˇuse std::str::{foo, bar}\nˇ\n  {baz.qux()}", view, cx);
-
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
-        assert_selection_ranges("ˇuse std::str::{foo, barˇ}\n\n  {baz.qux()}", view, cx);
-
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
-        assert_selection_ranges("useˇ std::str::{foo, bar}ˇ\n\n  {baz.qux()}", view, cx);
-
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
-        assert_selection_ranges("use stdˇ::str::{foo, bar}\nˇ\n  {baz.qux()}", view, cx);
-
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
-        assert_selection_ranges("use std::ˇstr::{foo, bar}\n\n  {ˇbaz.qux()}", view, cx);
+        view.fold_ranges(vec![8..13, 19..23], true, cx);
+        view.fold_ranges(vec![4..18], true, cx);
+        assert_eq!(view.display_text(cx), "one ⋯ ⋯");
 
-        view.move_right(&MoveRight, cx);
-        view.select_to_previous_word_start(&SelectToPreviousWordStart, cx);
-        assert_selection_ranges("use std::«ˇs»tr::{foo, bar}\n\n  {«ˇb»az.qux()}", view, cx);
+        // Every fold around the position is unfolded, while other folds are left alone.
+        let position = view.buffer().read(cx).snapshot(cx).anchor_before(10);
+        assert!(view.unfold_intersecting(position, cx));
+        assert_eq!(view.display_text(cx), "one two three four ⋯");
 
-        view.select_to_previous_word_start(&SelectToPreviousWordStart, cx);
-        assert_selection_ranges("use std«ˇ::s»tr::{foo, bar}\n\n  «ˇ{b»az.qux()}", view, cx);
+        // A position at the boundary of a fold unfolds it too.
+        let position = view.buffer().read(cx).snapshot(cx).anchor_before(23);
+        assert!(view.unfold_intersecting(position, cx));
+        assert_eq!(view.display_text(cx), "one two three four five");
 
-        view.select_to_next_word_end(&SelectToNextWordEnd, cx);
-        assert_selection_ranges("use std::«ˇs»tr::{foo, bar}\n\n  {«ˇb»az.qux()}", view, cx);
+        let position = view.buffer().read(cx).snapshot(cx).anchor_before(10);
+        assert!(!view.unfold_intersecting(position, cx));
     });
 }
 
 #[gpui::test]
-fn test_prev_next_word_bounds_with_soft_wrap(cx: &mut TestAppContext) {
+fn test_move_cursor(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
-    let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("use one::{\n    two::three::four::five\n};", cx);
-        build_editor(buffer, cx)
-    });
+    let buffer = cx.update(|cx| MultiBuffer::build_simple(&sample_text(6, 6, 'a'), cx));
+    let view = cx.add_window(|cx| build_editor(buffer.clone(), cx));
 
-    _ = view.update(cx, |view, cx| {
-        view.set_wrap_width(Some(140.0.into()), cx);
+    _ = buffer.update(cx, |buffer, cx| {
+        buffer.edit(
+            vec![
+                (Point::new(1, 0)..Point::new(1, 0), "\t"),
+                (Point::new(1, 1)..Point::new(1, 1), "\t"),
+            ],
+            None,
+            cx,
+        );
+    });
+    _ = view.update(cx, |view, cx| {
         assert_eq!(
-            view.display_text(cx),
-            "use one::{\n    two::three::\n    four::five\n};"
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)]
         );
 
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([DisplayPoint::new(1, 7)..DisplayPoint::new(1, 7)]);
-        });
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0)]
+        );
 
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        view.move_right(&MoveRight, cx);
         assert_eq!(
             view.selections.display_ranges(cx),
-            &[DisplayPoint::new(1, 9)..DisplayPoint::new(1, 9)]
+            &[DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4)]
         );
 
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        view.move_left(&MoveLeft, cx);
         assert_eq!(
             view.selections.display_ranges(cx),
-            &[DisplayPoint::new(1, 14)..DisplayPoint::new(1, 14)]
+            &[DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0)]
         );
 
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        view.move_up(&MoveUp, cx);
         assert_eq!(
             view.selections.display_ranges(cx),
-            &[DisplayPoint::new(2, 4)..DisplayPoint::new(2, 4)]
+            &[DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)]
         );
 
-        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        view.move_to_end(&MoveToEnd, cx);
         assert_eq!(
             view.selections.display_ranges(cx),
-            &[DisplayPoint::new(2, 8)..DisplayPoint::new(2, 8)]
+            &[DisplayPoint::new(5, 6)..DisplayPoint::new(5, 6)]
         );
 
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        view.move_to_beginning(&MoveToBeginning, cx);
         assert_eq!(
             view.selections.display_ranges(cx),
-            &[DisplayPoint::new(2, 4)..DisplayPoint::new(2, 4)]
+            &[DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0)]
         );
 
-        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(0, 1)..DisplayPoint::new(0, 2)]);
+        });
+        view.select_to_beginning(&SelectToBeginning, cx);
         assert_eq!(
             view.selections.display_ranges(cx),
-            &[DisplayPoint::new(1, 14)..DisplayPoint::new(1, 14)]
+            &[DisplayPoint::new(0, 1)..DisplayPoint::new(0, 0)]
+        );
+
+        view.select_to_end(&SelectToEnd, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(0, 1)..DisplayPoint::new(5, 6)]
         );
     });
 }
 
 #[gpui::test]
-async fn test_move_start_of_paragraph_end_of_paragraph(cx: &mut gpui::TestAppContext) {
+fn test_move_cursor_multibyte(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
-    let mut cx = EditorTestContext::new(cx).await;
 
-    let line_height = cx.editor(|editor, cx| {
-        editor
-            .style()
-            .unwrap()
-            .text
-            .line_height_in_pixels(cx.rem_size())
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("ⓐⓑⓒⓓⓔ\nabcde\nαβγδε", cx);
+        build_editor(buffer.clone(), cx)
     });
-    cx.simulate_window_resize(cx.window, size(px(100.), 4. * line_height));
-
-    cx.set_state(
-        &r#"ˇone
-        two
-
-        three
-        fourˇ
-        five
-
-        six"#
-            .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_to_end_of_paragraph(&MoveToEndOfParagraph, cx));
-    cx.assert_editor_state(
-        &r#"one
-        two
-        ˇ
-        three
-        four
-        five
-        ˇ
-        six"#
-            .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_to_end_of_paragraph(&MoveToEndOfParagraph, cx));
-    cx.assert_editor_state(
-        &r#"one
-        two
-
-        three
-        four
-        five
-        ˇ
-        sixˇ"#
-            .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_to_end_of_paragraph(&MoveToEndOfParagraph, cx));
-    cx.assert_editor_state(
-        &r#"one
-        two
-
-        three
-        four
-        five
-
-        sixˇ"#
-            .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_to_start_of_paragraph(&MoveToStartOfParagraph, cx));
-    cx.assert_editor_state(
-        &r#"one
-        two
-
-        three
-        four
-        five
-        ˇ
-        six"#
-            .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_to_start_of_paragraph(&MoveToStartOfParagraph, cx));
-    cx.assert_editor_state(
-        &r#"one
-        two
-        ˇ
-        three
-        four
-        five
-
-        six"#
-            .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_to_start_of_paragraph(&MoveToStartOfParagraph, cx));
-    cx.assert_editor_state(
-        &r#"ˇone
-        two
 
-        three
-        four
-        five
-
-        six"#
-            .unindent(),
-    );
-}
+    assert_eq!('ⓐ'.len_utf8(), 3);
+    assert_eq!('α'.len_utf8(), 2);
 
-#[gpui::test]
-async fn test_scroll_page_up_page_down(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |_| {});
-    let mut cx = EditorTestContext::new(cx).await;
-    let line_height = cx.editor(|editor, cx| {
-        editor
-            .style()
-            .unwrap()
-            .text
-            .line_height_in_pixels(cx.rem_size())
-    });
-    let window = cx.window;
-    cx.simulate_window_resize(window, size(px(1000.), 4. * line_height + px(0.5)));
+    _ = view.update(cx, |view, cx| {
+        view.fold_ranges(
+            vec![
+                Point::new(0, 6)..Point::new(0, 12),
+                Point::new(1, 2)..Point::new(1, 4),
+                Point::new(2, 4)..Point::new(2, 8),
+            ],
+            true,
+            cx,
+        );
+        assert_eq!(view.display_text(cx), "ⓐⓑ⋯ⓔ\nab⋯e\nαβ⋯ε");
 
-    cx.set_state(
-        &r#"ˇone
-        two
-        three
-        four
-        five
-        six
-        seven
-        eight
-        nine
-        ten
-        "#,
-    );
+        view.move_right(&MoveRight, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(0, "ⓐ".len())]
+        );
+        view.move_right(&MoveRight, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(0, "ⓐⓑ".len())]
+        );
+        view.move_right(&MoveRight, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(0, "ⓐⓑ⋯".len())]
+        );
 
-    cx.update_editor(|editor, cx| {
+        view.move_down(&MoveDown, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 0.)
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "ab⋯e".len())]
         );
-        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+        view.move_left(&MoveLeft, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 3.)
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "ab⋯".len())]
         );
-        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+        view.move_left(&MoveLeft, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 6.)
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "ab".len())]
         );
-        editor.scroll_screen(&ScrollAmount::Page(-1.), cx);
+        view.move_left(&MoveLeft, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 3.)
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "a".len())]
         );
 
-        editor.scroll_screen(&ScrollAmount::Page(-0.5), cx);
+        view.move_down(&MoveDown, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 1.)
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "α".len())]
         );
-        editor.scroll_screen(&ScrollAmount::Page(0.5), cx);
+        view.move_right(&MoveRight, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 3.)
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "αβ".len())]
         );
-    });
-}
-
-#[gpui::test]
-async fn test_autoscroll(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |_| {});
-    let mut cx = EditorTestContext::new(cx).await;
-
-    let line_height = cx.update_editor(|editor, cx| {
-        editor.set_vertical_scroll_margin(2, cx);
-        editor
-            .style()
-            .unwrap()
-            .text
-            .line_height_in_pixels(cx.rem_size())
-    });
-    let window = cx.window;
-    cx.simulate_window_resize(window, size(px(1000.), 6. * line_height));
-
-    cx.set_state(
-        &r#"ˇone
-            two
-            three
-            four
-            five
-            six
-            seven
-            eight
-            nine
-            ten
-        "#,
-    );
-    cx.update_editor(|editor, cx| {
+        view.move_right(&MoveRight, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 0.0)
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "αβ⋯".len())]
         );
-    });
-
-    // Add a cursor below the visible area. Since both cursors cannot fit
-    // on screen, the editor autoscrolls to reveal the newest cursor, and
-    // allows the vertical scroll margin below that cursor.
-    cx.update_editor(|editor, cx| {
-        editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
-            selections.select_ranges([
-                Point::new(0, 0)..Point::new(0, 0),
-                Point::new(6, 0)..Point::new(6, 0),
-            ]);
-        })
-    });
-    cx.update_editor(|editor, cx| {
+        view.move_right(&MoveRight, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 3.0)
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "αβ⋯ε".len())]
         );
-    });
 
-    // Move down. The editor cursor scrolls down to track the newest cursor.
-    cx.update_editor(|editor, cx| {
-        editor.move_down(&Default::default(), cx);
-    });
-    cx.update_editor(|editor, cx| {
+        view.move_up(&MoveUp, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 4.0)
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "ab⋯e".len())]
+        );
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "αβ⋯ε".len())]
+        );
+        view.move_up(&MoveUp, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "ab⋯e".len())]
         );
-    });
 
-    // Add a cursor above the visible area. Since both cursors fit on screen,
-    // the editor scrolls to show both.
-    cx.update_editor(|editor, cx| {
-        editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
-            selections.select_ranges([
-                Point::new(1, 0)..Point::new(1, 0),
-                Point::new(6, 0)..Point::new(6, 0),
-            ]);
-        })
-    });
-    cx.update_editor(|editor, cx| {
+        view.move_up(&MoveUp, cx);
         assert_eq!(
-            editor.snapshot(cx).scroll_position(),
-            gpui::Point::new(0., 1.0)
+            view.selections.display_ranges(cx),
+            &[empty_range(0, "ⓐⓑ".len())]
+        );
+        view.move_left(&MoveLeft, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(0, "ⓐ".len())]
+        );
+        view.move_left(&MoveLeft, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(0, "".len())]
         );
     });
 }
 
 #[gpui::test]
-async fn test_move_page_up_page_down(cx: &mut gpui::TestAppContext) {
+fn test_move_cursor_by_word_across_fold(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
-    let mut cx = EditorTestContext::new(cx).await;
 
-    let line_height = cx.editor(|editor, cx| {
-        editor
-            .style()
-            .unwrap()
-            .text
-            .line_height_in_pixels(cx.rem_size())
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one two three four five", cx);
+        build_editor(buffer, cx)
     });
-    let window = cx.window;
-    cx.simulate_window_resize(window, size(px(100.), 4. * line_height));
-    cx.set_state(
-        &r#"
-        ˇone
-        two
-        threeˇ
-        four
-        five
-        six
-        seven
-        eight
-        nine
-        ten
-        "#
-        .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_page_down(&MovePageDown::default(), cx));
-    cx.assert_editor_state(
-        &r#"
-        one
-        two
-        three
-        ˇfour
-        five
-        sixˇ
-        seven
-        eight
-        nine
-        ten
-        "#
-        .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_page_down(&MovePageDown::default(), cx));
-    cx.assert_editor_state(
-        &r#"
-        one
-        two
-        three
-        four
-        five
-        six
-        ˇseven
-        eight
-        nineˇ
-        ten
-        "#
-        .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_page_up(&MovePageUp::default(), cx));
-    cx.assert_editor_state(
-        &r#"
-        one
-        two
-        three
-        ˇfour
-        five
-        sixˇ
-        seven
-        eight
-        nine
-        ten
-        "#
-        .unindent(),
-    );
-
-    cx.update_editor(|editor, cx| editor.move_page_up(&MovePageUp::default(), cx));
-    cx.assert_editor_state(
-        &r#"
-        ˇone
-        two
-        threeˇ
-        four
-        five
-        six
-        seven
-        eight
-        nine
-        ten
-        "#
-        .unindent(),
-    );
+    _ = view.update(cx, |view, cx| {
+        let folded_range = 4..13;
+        view.fold_ranges(vec![folded_range.clone()], true, cx);
+        assert_eq!(view.display_text(cx), "one ⋯ four five");
+
+        let assert_cursor_is_visible = |view: &mut Editor, cx: &mut ViewContext<Editor>| {
+            let head = view.selections.newest::<usize>(cx).head();
+            assert!(
+                head <= folded_range.start || head >= folded_range.end,
+                "cursor at {head} is inside the folded range {folded_range:?}"
+            );
+        };
 
-    // Test select collapsing
-    cx.update_editor(|editor, cx| {
-        editor.move_page_down(&MovePageDown::default(), cx);
-        editor.move_page_down(&MovePageDown::default(), cx);
-        editor.move_page_down(&MovePageDown::default(), cx);
-    });
-    cx.assert_editor_state(
-        &r#"
-        one
-        two
-        three
-        four
-        five
-        six
-        seven
-        eight
-        nine
-        ˇten
-        ˇ"#
-        .unindent(),
-    );
-}
+        for _ in 0..5 {
+            view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+            assert_cursor_is_visible(view, cx);
+        }
+        assert_eq!(view.selections.newest::<usize>(cx).head(), 23);
 
-#[gpui::test]
-async fn test_delete_to_beginning_of_line(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |_| {});
-    let mut cx = EditorTestContext::new(cx).await;
-    cx.set_state("one «two threeˇ» four");
-    cx.update_editor(|editor, cx| {
-        editor.delete_to_beginning_of_line(&DeleteToBeginningOfLine, cx);
-        assert_eq!(editor.text(cx), " four");
+        for _ in 0..5 {
+            view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+            assert_cursor_is_visible(view, cx);
+        }
+        assert_eq!(view.selections.newest::<usize>(cx).head(), 0);
     });
 }
 
 #[gpui::test]
-fn test_delete_to_word_boundary(cx: &mut TestAppContext) {
+fn test_move_cursor_different_line_lengths(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("one two three four", cx);
+        let buffer = MultiBuffer::build_simple("ⓐⓑⓒⓓⓔ\nabcd\nαβγ\nabcd\nⓐⓑⓒⓓⓔ\n", cx);
         build_editor(buffer.clone(), cx)
     });
-
     _ = view.update(cx, |view, cx| {
         view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                // an empty selection - the preceding word fragment is deleted
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
-                // characters selected - they are deleted
-                DisplayPoint::new(0, 9)..DisplayPoint::new(0, 12),
-            ])
+            s.select_display_ranges([empty_range(0, "ⓐⓑⓒⓓⓔ".len())]);
         });
-        view.delete_to_previous_word_start(&DeleteToPreviousWordStart, cx);
-        assert_eq!(view.buffer.read(cx).read(cx).text(), "e two te four");
-    });
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(1, "abcd".len())]
+        );
 
-    _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                // an empty selection - the following word fragment is deleted
-                DisplayPoint::new(0, 3)..DisplayPoint::new(0, 3),
-                // characters selected - they are deleted
-                DisplayPoint::new(0, 9)..DisplayPoint::new(0, 10),
-            ])
-        });
-        view.delete_to_next_word_end(&DeleteToNextWordEnd, cx);
-        assert_eq!(view.buffer.read(cx).read(cx).text(), "e t te our");
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "αβγ".len())]
+        );
+
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(3, "abcd".len())]
+        );
+
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(4, "ⓐⓑⓒⓓⓔ".len())]
+        );
+
+        view.move_up(&MoveUp, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(3, "abcd".len())]
+        );
+
+        view.move_up(&MoveUp, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[empty_range(2, "αβγ".len())]
+        );
     });
 }
 
 #[gpui::test]
-fn test_newline(cx: &mut TestAppContext) {
+fn test_beginning_end_of_line(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("aaaa\n    bbbb\n", cx);
-        build_editor(buffer.clone(), cx)
+        let buffer = MultiBuffer::build_simple("abc\n  def", cx);
+        build_editor(buffer, cx)
     });
-
     _ = view.update(cx, |view, cx| {
         view.change_selections(None, cx, |s| {
             s.select_display_ranges([
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
-                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
-                DisplayPoint::new(1, 6)..DisplayPoint::new(1, 6),
-            ])
+                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
+                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4),
+            ]);
         });
-
-        view.newline(&Newline, cx);
-        assert_eq!(view.text(cx), "aa\naa\n  \n    bb\n    bb\n");
     });
-}
-
-#[gpui::test]
-fn test_newline_with_old_selections(cx: &mut TestAppContext) {
-    init_test(cx, |_| {});
 
-    let editor = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple(
-            "
-                a
-                b(
-                    X
-                )
-                c(
-                    X
-                )
-            "
-            .unindent()
-            .as_str(),
-            cx,
+    _ = view.update(cx, |view, cx| {
+        view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
+            ]
         );
-        let mut editor = build_editor(buffer.clone(), cx);
-        editor.change_selections(None, cx, |s| {
-            s.select_ranges([
-                Point::new(2, 4)..Point::new(2, 5),
-                Point::new(5, 4)..Point::new(5, 5),
-            ])
-        });
-        editor
     });
 
-    _ = editor.update(cx, |editor, cx| {
-        // Edit the buffer directly, deleting ranges surrounding the editor's selections
-        editor.buffer.update(cx, |buffer, cx| {
-            buffer.edit(
-                [
-                    (Point::new(1, 2)..Point::new(3, 0), ""),
-                    (Point::new(4, 2)..Point::new(6, 0), ""),
-                ],
-                None,
-                cx,
-            );
-            assert_eq!(
-                buffer.read(cx).text(),
-                "
-                    a
-                    b()
-                    c()
-                "
-                .unindent()
-            );
-        });
+    _ = view.update(cx, |view, cx| {
+        view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
         assert_eq!(
-            editor.selections.ranges(cx),
+            view.selections.display_ranges(cx),
             &[
-                Point::new(1, 2)..Point::new(1, 2),
-                Point::new(2, 2)..Point::new(2, 2),
-            ],
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0),
+            ]
         );
+    });
 
-        editor.newline(&Newline, cx);
+    _ = view.update(cx, |view, cx| {
+        view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
         assert_eq!(
-            editor.text(cx),
-            "
-                a
-                b(
-                )
-                c(
-                )
-            "
-            .unindent()
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
+            ]
         );
+    });
 
-        // The selections are moved after the inserted newlines
+    _ = view.update(cx, |view, cx| {
+        view.move_to_end_of_line(&MoveToEndOfLine, cx);
         assert_eq!(
-            editor.selections.ranges(cx),
+            view.selections.display_ranges(cx),
             &[
-                Point::new(2, 0)..Point::new(2, 0),
-                Point::new(4, 0)..Point::new(4, 0),
-            ],
+                DisplayPoint::new(0, 3)..DisplayPoint::new(0, 3),
+                DisplayPoint::new(1, 5)..DisplayPoint::new(1, 5),
+            ]
         );
     });
-}
 
-#[gpui::test]
-async fn test_newline_above(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.tab_size = NonZeroU32::new(4)
+    // Moving to the end of line again is a no-op.
+    _ = view.update(cx, |view, cx| {
+        view.move_to_end_of_line(&MoveToEndOfLine, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 3)..DisplayPoint::new(0, 3),
+                DisplayPoint::new(1, 5)..DisplayPoint::new(1, 5),
+            ]
+        );
     });
 
-    let language = Arc::new(
-        Language::new(
-            LanguageConfig::default(),
-            Some(tree_sitter_rust::language()),
-        )
-        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
-        .unwrap(),
-    );
-
-    let mut cx = EditorTestContext::new(cx).await;
-    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
-    cx.set_state(indoc! {"
-        const a: ˇA = (
-            (ˇ
-                «const_functionˇ»(ˇ),
-                so«mˇ»et«hˇ»ing_ˇelse,ˇ
-            )ˇ
-        ˇ);ˇ
-    "});
-
-    cx.update_editor(|e, cx| e.newline_above(&NewlineAbove, cx));
-    cx.assert_editor_state(indoc! {"
-        ˇ
-        const a: A = (
-            ˇ
-            (
-                ˇ
-                ˇ
-                const_function(),
-                ˇ
-                ˇ
-                ˇ
-                ˇ
-                something_else,
-                ˇ
-            )
-            ˇ
-            ˇ
+    _ = view.update(cx, |view, cx| {
+        view.move_left(&MoveLeft, cx);
+        view.select_to_beginning_of_line(
+            &SelectToBeginningOfLine {
+                stop_at_soft_wraps: true,
+            },
+            cx,
         );
-    "});
-}
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 2),
+            ]
+        );
+    });
 
-#[gpui::test]
-async fn test_newline_below(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.tab_size = NonZeroU32::new(4)
+    _ = view.update(cx, |view, cx| {
+        view.select_to_beginning_of_line(
+            &SelectToBeginningOfLine {
+                stop_at_soft_wraps: true,
+            },
+            cx,
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 0),
+            ]
+        );
     });
 
-    let language = Arc::new(
-        Language::new(
-            LanguageConfig::default(),
-            Some(tree_sitter_rust::language()),
-        )
-        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
-        .unwrap(),
-    );
+    _ = view.update(cx, |view, cx| {
+        view.select_to_beginning_of_line(
+            &SelectToBeginningOfLine {
+                stop_at_soft_wraps: true,
+            },
+            cx,
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 2),
+            ]
+        );
+    });
 
-    let mut cx = EditorTestContext::new(cx).await;
-    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
-    cx.set_state(indoc! {"
-        const a: ˇA = (
-            (ˇ
-                «const_functionˇ»(ˇ),
-                so«mˇ»et«hˇ»ing_ˇelse,ˇ
-            )ˇ
-        ˇ);ˇ
-    "});
+    _ = view.update(cx, |view, cx| {
+        view.select_to_end_of_line(
+            &SelectToEndOfLine {
+                stop_at_soft_wraps: true,
+            },
+            cx,
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 3),
+                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 5),
+            ]
+        );
+    });
 
-    cx.update_editor(|e, cx| e.newline_below(&NewlineBelow, cx));
-    cx.assert_editor_state(indoc! {"
-        const a: A = (
-            ˇ
-            (
-                ˇ
-                const_function(),
-                ˇ
-                ˇ
-                something_else,
-                ˇ
-                ˇ
-                ˇ
-                ˇ
-            )
-            ˇ
+    _ = view.update(cx, |view, cx| {
+        view.delete_to_end_of_line(&DeleteToEndOfLine, cx);
+        assert_eq!(view.display_text(cx), "ab\n  de");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
+                DisplayPoint::new(1, 4)..DisplayPoint::new(1, 4),
+            ]
         );
-        ˇ
-        ˇ
-    "});
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.delete_to_beginning_of_line(&DeleteToBeginningOfLine, cx);
+        assert_eq!(view.display_text(cx), "\n");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0),
+            ]
+        );
+    });
 }
 
 #[gpui::test]
-async fn test_newline_comments(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.tab_size = NonZeroU32::new(4)
+fn test_prev_next_word_boundary(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("use std::str::{foo, bar}\n\n  {baz.qux()}", cx);
+        build_editor(buffer, cx)
     });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 11)..DisplayPoint::new(0, 11),
+                DisplayPoint::new(2, 4)..DisplayPoint::new(2, 4),
+            ])
+        });
 
-    let language = Arc::new(Language::new(
-        LanguageConfig {
-            line_comments: vec!["//".into()],
-            ..LanguageConfig::default()
-        },
-        None,
-    ));
-    {
-        let mut cx = EditorTestContext::new(cx).await;
-        cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
-        cx.set_state(indoc! {"
-        // Fooˇ
-    "});
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_selection_ranges("use std::ˇstr::{foo, bar}\n\n  {ˇbaz.qux()}", view, cx);
 
-        cx.update_editor(|e, cx| e.newline(&Newline, cx));
-        cx.assert_editor_state(indoc! {"
-        // Foo
-        //ˇ
-    "});
-        // Ensure that if cursor is before the comment start, we do not actually insert a comment prefix.
-        cx.set_state(indoc! {"
-        ˇ// Foo
-    "});
-        cx.update_editor(|e, cx| e.newline(&Newline, cx));
-        cx.assert_editor_state(indoc! {"
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_selection_ranges("use stdˇ::str::{foo, bar}\n\n  ˇ{baz.qux()}", view, cx);
 
-        ˇ// Foo
-    "});
-    }
-    // Ensure that comment continuations can be disabled.
-    update_test_language_settings(cx, |settings| {
-        settings.defaults.extend_comment_on_newline = Some(false);
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_selection_ranges("use ˇstd::str::{foo, bar}\n\nˇ  {baz.qux()}", view, cx);
+
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_selection_ranges("ˇuse std::str::{foo, bar}\nˇ\n  {baz.qux()}", view, cx);
+
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_selection_ranges("ˇuse std::str::{foo, barˇ}\n\n  {baz.qux()}", view, cx);
+
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_selection_ranges("useˇ std::str::{foo, bar}ˇ\n\n  {baz.qux()}", view, cx);
+
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_selection_ranges("use stdˇ::str::{foo, bar}\nˇ\n  {baz.qux()}", view, cx);
+
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_selection_ranges("use std::ˇstr::{foo, bar}\n\n  {ˇbaz.qux()}", view, cx);
+
+        view.move_right(&MoveRight, cx);
+        view.select_to_previous_word_start(&SelectToPreviousWordStart, cx);
+        assert_selection_ranges("use std::«ˇs»tr::{foo, bar}\n\n  {«ˇb»az.qux()}", view, cx);
+
+        view.select_to_previous_word_start(&SelectToPreviousWordStart, cx);
+        assert_selection_ranges("use std«ˇ::s»tr::{foo, bar}\n\n  «ˇ{b»az.qux()}", view, cx);
+
+        view.select_to_next_word_end(&SelectToNextWordEnd, cx);
+        assert_selection_ranges("use std::«ˇs»tr::{foo, bar}\n\n  {«ˇb»az.qux()}", view, cx);
     });
-    let mut cx = EditorTestContext::new(cx).await;
-    cx.set_state(indoc! {"
-        // Fooˇ
-    "});
-    cx.update_editor(|e, cx| e.newline(&Newline, cx));
-    cx.assert_editor_state(indoc! {"
-        // Foo
-        ˇ
-    "});
 }
 
 #[gpui::test]
-fn test_insert_with_old_selections(cx: &mut TestAppContext) {
-    init_test(cx, |_| {});
+async fn test_toggle_soft_wrap(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.soft_wrap = Some(language_settings::SoftWrap::None);
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(200.), px(500.)));
+    cx.set_state("one two three four five six seven eight nine tenˇ");
 
-    let editor = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("a( X ), b( Y ), c( Z )", cx);
-        let mut editor = build_editor(buffer.clone(), cx);
-        editor.change_selections(None, cx, |s| s.select_ranges([3..4, 11..12, 19..20]));
-        editor
+    cx.update_editor(|editor, cx| {
+        editor.toggle_soft_wrap(&ToggleSoftWrap, cx);
+        assert!(matches!(editor.soft_wrap_mode(cx), SoftWrap::EditorWidth));
+    });
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert!(editor.display_text(cx).lines().count() > 1);
     });
 
-    _ = editor.update(cx, |editor, cx| {
-        // Edit the buffer directly, deleting ranges surrounding the editor's selections
-        editor.buffer.update(cx, |buffer, cx| {
-            buffer.edit([(2..5, ""), (10..13, ""), (18..21, "")], None, cx);
-            assert_eq!(buffer.read(cx).text(), "a(), b(), c()".unindent());
-        });
-        assert_eq!(editor.selections.ranges(cx), &[2..2, 7..7, 12..12],);
+    // Toggling again restores the mode from the settings.
+    cx.update_editor(|editor, cx| {
+        editor.toggle_soft_wrap(&ToggleSoftWrap, cx);
+        assert!(matches!(editor.soft_wrap_mode(cx), SoftWrap::None));
+    });
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.display_text(cx),
+            "one two three four five six seven eight nine ten"
+        );
+    });
+    cx.assert_editor_state("one two three four five six seven eight nine tenˇ");
+}
 
-        editor.insert("Z", cx);
-        assert_eq!(editor.text(cx), "a(Z), b(Z), c(Z)");
+#[gpui::test]
+async fn test_cursor_shape_setting(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
 
-        // The selections are moved after the inserted characters
-        assert_eq!(editor.selections.ranges(cx), &[3..3, 9..9, 15..15],);
+    for shape in [
+        CursorShape::Bar,
+        CursorShape::Block,
+        CursorShape::Underscore,
+        CursorShape::Hollow,
+    ] {
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.cursor_shape = Some(shape);
+                });
+            });
+        });
+        cx.update_editor(|editor, cx| assert_eq!(editor.snapshot(cx).cursor_shape, shape));
+    }
+
+    // An explicitly set shape takes precedence over the settings until it is reset.
+    cx.update_editor(|editor, cx| {
+        editor.set_cursor_shape(CursorShape::Block, cx);
+        assert_eq!(editor.snapshot(cx).cursor_shape, CursorShape::Block);
+    });
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.cursor_shape = Some(CursorShape::Underscore);
+            });
+        });
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.snapshot(cx).cursor_shape, CursorShape::Block);
+        editor.reset_cursor_shape(cx);
+        assert_eq!(editor.snapshot(cx).cursor_shape, CursorShape::Underscore);
     });
 }
 
 #[gpui::test]
-async fn test_tab(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.tab_size = NonZeroU32::new(3)
+fn test_set_cursor_shape(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc", cx);
+        build_editor(buffer, cx)
+    });
+    let notifications = Rc::new(RefCell::new(0));
+    let _subscription = cx.update(|cx| {
+        let notifications = notifications.clone();
+        cx.observe(&view.root_view(cx).unwrap(), move |_, _| {
+            *notifications.borrow_mut() += 1;
+        })
     });
 
+    _ = view.update(cx, |view, cx| {
+        assert_eq!(view.cursor_shape, CursorShape::Bar);
+        view.set_cursor_shape(CursorShape::Underscore, cx);
+        assert_eq!(view.cursor_shape, CursorShape::Underscore);
+    });
+    assert!(*notifications.borrow() > 0);
+}
+
+#[gpui::test]
+async fn test_set_wrap_column(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
     let mut cx = EditorTestContext::new(cx).await;
-    cx.set_state(indoc! {"
-        ˇabˇc
-        ˇ🏀ˇ🏀ˇefg
-        dˇ
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-           ˇab ˇc
-           ˇ🏀  ˇ🏀  ˇefg
-        d  ˇ
-    "});
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), px(500.)));
+    cx.set_state("ˇone two three four five six seven eight");
 
-    cx.set_state(indoc! {"
-        a
-        «🏀ˇ»🏀«🏀ˇ»🏀«🏀ˇ»
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        a
-           «🏀ˇ»🏀«🏀ˇ»🏀«🏀ˇ»
-    "});
+    cx.update_editor(|editor, cx| {
+        editor.set_wrap_column(&SetWrapColumn { column: 10 }, cx);
+        assert!(matches!(editor.soft_wrap_mode(cx), SoftWrap::Column(10)));
+    });
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        let display_text = editor.display_text(cx);
+        assert!(display_text.lines().count() > 1);
+        assert!(display_text.lines().all(|line| line.len() <= 10));
+    });
+
+    // A column of zero disables soft wrapping.
+    cx.update_editor(|editor, cx| {
+        editor.set_wrap_column(&SetWrapColumn { column: 0 }, cx);
+        assert!(matches!(editor.soft_wrap_mode(cx), SoftWrap::None));
+    });
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.display_text(cx),
+            "one two three four five six seven eight"
+        );
+    });
 }
 
 #[gpui::test]
-async fn test_tab_in_leading_whitespace_auto_indents_lines(cx: &mut gpui::TestAppContext) {
+fn test_prev_next_word_bounds_with_soft_wrap(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
-    let mut cx = EditorTestContext::new(cx).await;
-    let language = Arc::new(
-        Language::new(
-            LanguageConfig::default(),
-            Some(tree_sitter_rust::language()),
-        )
-        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
-        .unwrap(),
-    );
-    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("use one::{\n    two::three::four::five\n};", cx);
+        build_editor(buffer, cx)
+    });
 
-    // cursors that are already at the suggested indent level insert
-    // a soft tab. cursors that are to the left of the suggested indent
-    // auto-indent their line.
-    cx.set_state(indoc! {"
-        ˇ
-        const a: B = (
-            c(
-                d(
-        ˇ
-                )
-        ˇ
-        ˇ    )
+    _ = view.update(cx, |view, cx| {
+        view.set_wrap_width(Some(140.0.into()), cx);
+        assert_eq!(
+            view.display_text(cx),
+            "use one::{\n    two::three::\n    four::five\n};"
         );
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-            ˇ
-        const a: B = (
-            c(
-                d(
-                    ˇ
-                )
-                ˇ
-            ˇ)
+
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(1, 7)..DisplayPoint::new(1, 7)]);
+        });
+
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(1, 9)..DisplayPoint::new(1, 9)]
         );
-    "});
 
-    // handle auto-indent when there are multiple cursors on the same line
-    cx.set_state(indoc! {"
-        const a: B = (
-            c(
-        ˇ    ˇ
-        ˇ    )
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(1, 14)..DisplayPoint::new(1, 14)]
         );
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        const a: B = (
-            c(
-                ˇ
-            ˇ)
+
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(2, 4)..DisplayPoint::new(2, 4)]
         );
-    "});
+
+        view.move_to_next_word_end(&MoveToNextWordEnd, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(2, 8)..DisplayPoint::new(2, 8)]
+        );
+
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(2, 4)..DisplayPoint::new(2, 4)]
+        );
+
+        view.move_to_previous_word_start(&MoveToPreviousWordStart, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(1, 14)..DisplayPoint::new(1, 14)]
+        );
+    });
 }
 
 #[gpui::test]
-async fn test_tab_with_mixed_whitespace(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.tab_size = NonZeroU32::new(4)
+fn test_beginning_of_line_with_soft_wrap(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("use one::{\n    two::three::four::five\n};", cx);
+        build_editor(buffer, cx)
     });
 
-    let language = Arc::new(
-        Language::new(
-            LanguageConfig::default(),
-            Some(tree_sitter_rust::language()),
-        )
-        .with_indents_query(r#"(_ "{" "}" @end) @indent"#)
-        .unwrap(),
-    );
+    let move_to_beginning_of_line = |cx: &mut TestAppContext| {
+        view.update(cx, |view, cx| {
+            view.move_to_beginning_of_line(&MoveToBeginningOfLine, cx);
+            view.selections.newest::<Point>(cx).head()
+        })
+        .unwrap()
+    };
 
-    let mut cx = EditorTestContext::new(cx).await;
-    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
-    cx.set_state(indoc! {"
-        fn a() {
-            if b {
-        \t ˇc
-            }
-        }
-    "});
+    // Stops at the start of the wrapped row, then cycles between the indentation and
+    // the start of the line.
+    _ = view.update(cx, |view, cx| {
+        view.set_wrap_width(Some(140.0.into()), cx);
+        assert_eq!(
+            view.display_text(cx),
+            "use one::{\n    two::three::\n    four::five\n};"
+        );
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 20)..Point::new(1, 20)])
+        });
+    });
+    assert_eq!(move_to_beginning_of_line(cx), Point::new(1, 16));
+    assert_eq!(move_to_beginning_of_line(cx), Point::new(1, 4));
+    assert_eq!(move_to_beginning_of_line(cx), Point::new(1, 0));
+    assert_eq!(move_to_beginning_of_line(cx), Point::new(1, 4));
 
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        fn a() {
-            if b {
-                ˇc
-            }
-        }
-    "});
+    // The wrapped row is skipped when the setting is disabled.
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.home_stops_at_soft_wraps = Some(false);
+            });
+        });
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 20)..Point::new(1, 20)])
+        });
+    });
+    assert_eq!(move_to_beginning_of_line(cx), Point::new(1, 4));
+    assert_eq!(move_to_beginning_of_line(cx), Point::new(1, 0));
 }
 
 #[gpui::test]
-async fn test_indent_outdent(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.tab_size = NonZeroU32::new(4);
+fn test_end_of_buffer_line_with_soft_wrap(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("use one::{\n    two::three::four::five\n};", cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.set_wrap_width(Some(140.0.into()), cx);
+        assert_eq!(
+            view.display_text(cx),
+            "use one::{\n    two::three::\n    four::five\n};"
+        );
+
+        // Moving to the end of the line stops at the soft wrap.
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 4)..Point::new(1, 4)])
+        });
+        view.move_to_end_of_line(&MoveToEndOfLine, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            &[DisplayPoint::new(1, 15)..DisplayPoint::new(1, 15)]
+        );
+
+        // Moving to the end of the buffer line crosses it.
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 4)..Point::new(1, 4)])
+        });
+        view.move_to_end_of_buffer_line(&MoveToEndOfBufferLine, cx);
+        assert_eq!(
+            view.selections.ranges::<Point>(cx),
+            &[Point::new(1, 26)..Point::new(1, 26)]
+        );
+
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 4)..Point::new(1, 4)])
+        });
+        view.select_to_end_of_buffer_line(&SelectToEndOfBufferLine, cx);
+        assert_eq!(
+            view.selections.ranges::<Point>(cx),
+            &[Point::new(1, 4)..Point::new(1, 26)]
+        );
+
+        view.delete_to_end_of_buffer_line(&DeleteToEndOfBufferLine, cx);
+        assert_eq!(view.text(cx), "use one::{\n    \n};");
     });
+}
 
+#[gpui::test]
+async fn test_active_line_annotation(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
     let mut cx = EditorTestContext::new(cx).await;
 
-    cx.set_state(indoc! {"
-          «oneˇ» «twoˇ»
-        three
-         four
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-            «oneˇ» «twoˇ»
-        three
-         four
-    "});
+    cx.set_state("ˇone\ntwo\nthree\nfour");
+    cx.update_editor(|editor, cx| {
+        editor.set_active_line_annotation_provider(
+            Some(Box::new(|_, row| {
+                (row != 2).then(|| format!("annotation for row {row}").into())
+            })),
+            cx,
+        )
+    });
+    let annotation = |cx: &mut EditorTestContext| {
+        cx.update_editor(|editor, cx| editor.active_line_annotation(cx))
+    };
+    assert_eq!(annotation(&mut cx), None);
+    cx.executor()
+        .advance_clock(ACTIVE_LINE_ANNOTATION_DEBOUNCE_TIMEOUT);
+    assert_eq!(
+        annotation(&mut cx),
+        Some((0, "annotation for row 0".into()))
+    );
 
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
-    cx.assert_editor_state(indoc! {"
-        «oneˇ» «twoˇ»
-        three
-         four
-    "});
+    // Moving within the line keeps the annotation.
+    cx.update_editor(|editor, cx| editor.move_right(&MoveRight, cx));
+    assert_eq!(
+        annotation(&mut cx),
+        Some((0, "annotation for row 0".into()))
+    );
 
-    // select across line ending
-    cx.set_state(indoc! {"
-        one two
-        t«hree
-        ˇ» four
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        one two
-            t«hree
-        ˇ» four
-    "});
+    // Moving to another line clears the annotation until it is recomputed there.
+    cx.update_editor(|editor, cx| editor.move_down(&MoveDown, cx));
+    assert_eq!(annotation(&mut cx), None);
+    cx.executor()
+        .advance_clock(ACTIVE_LINE_ANNOTATION_DEBOUNCE_TIMEOUT);
+    assert_eq!(
+        annotation(&mut cx),
+        Some((1, "annotation for row 1".into()))
+    );
 
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
-    cx.assert_editor_state(indoc! {"
-        one two
-        t«hree
-        ˇ» four
-    "});
+    cx.update_editor(|editor, cx| editor.move_down(&MoveDown, cx));
+    cx.executor()
+        .advance_clock(ACTIVE_LINE_ANNOTATION_DEBOUNCE_TIMEOUT);
+    assert_eq!(annotation(&mut cx), None);
 
-    // Ensure that indenting/outdenting works when the cursor is at column 0.
-    cx.set_state(indoc! {"
-        one two
-        ˇthree
-            four
-    "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        one two
-            ˇthree
-            four
-    "});
+    cx.update_editor(|editor, cx| editor.move_down(&MoveDown, cx));
+    cx.executor()
+        .advance_clock(ACTIVE_LINE_ANNOTATION_DEBOUNCE_TIMEOUT);
+    assert_eq!(
+        annotation(&mut cx),
+        Some((3, "annotation for row 3".into()))
+    );
 
-    cx.set_state(indoc! {"
-        one two
-        ˇ    three
-            four
-    "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
-    cx.assert_editor_state(indoc! {"
-        one two
-        ˇthree
-            four
-    "});
+    cx.update_editor(|editor, cx| editor.set_active_line_annotation_provider(None, cx));
+    assert_eq!(annotation(&mut cx), None);
 }
 
 #[gpui::test]
-async fn test_indent_outdent_with_hard_tabs(cx: &mut gpui::TestAppContext) {
-    init_test(cx, |settings| {
-        settings.defaults.hard_tabs = Some(true);
-    });
-
+async fn test_move_cursor_to_column(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
     let mut cx = EditorTestContext::new(cx).await;
 
-    // select two ranges on one line
-    cx.set_state(indoc! {"
-        «oneˇ» «twoˇ»
+    // Columns are clamped to the line length, and count tabs as their display width.
+    cx.set_state("aˇbcdefgh\nˇab\nˇ\tx\n\t\tˇz");
+    cx.update_editor(|editor, cx| editor.move_cursor_to_column(6, cx));
+    cx.assert_editor_state("abcdefˇgh\nabˇ\n\txˇ\n\tˇ\tz");
+
+    // Selections collapse to a cursor at the column.
+    cx.set_state("a«bcdˇ»ef\nab");
+    cx.update_editor(|editor, cx| editor.move_cursor_to_column(1, cx));
+    cx.assert_editor_state("aˇbcdef\nab");
+}
+
+#[gpui::test]
+async fn test_go_to_line(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Other cursors are removed.
+    cx.set_state("ˇone\ntwo\nthrˇee");
+    cx.update_editor(|editor, cx| editor.go_to_line(2, 3, cx));
+    cx.assert_editor_state("one\ntwˇo\nthree");
+
+    // Rows past the end of the buffer are clamped to the last line.
+    cx.update_editor(|editor, cx| editor.go_to_line(10, 2, cx));
+    cx.assert_editor_state("one\ntwo\ntˇhree");
+
+    // Columns past the end of the line are clamped to its length.
+    cx.update_editor(|editor, cx| editor.go_to_line(1, 20, cx));
+    cx.assert_editor_state("oneˇ\ntwo\nthree");
+
+    // Zero is treated as the first row and column.
+    cx.update_editor(|editor, cx| editor.go_to_line(0, 0, cx));
+    cx.assert_editor_state("ˇone\ntwo\nthree");
+}
+
+#[gpui::test]
+async fn test_move_start_of_paragraph_end_of_paragraph(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    cx.simulate_window_resize(cx.window, size(px(100.), 4. * line_height));
+
+    cx.set_state(
+        &r#"ˇone
+        two
+
+        three
+        fourˇ
+        five
+
+        six"#
+            .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_to_end_of_paragraph(&MoveToEndOfParagraph, cx));
+    cx.assert_editor_state(
+        &r#"one
+        two
+        ˇ
+        three
+        four
+        five
+        ˇ
+        six"#
+            .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_to_end_of_paragraph(&MoveToEndOfParagraph, cx));
+    cx.assert_editor_state(
+        &r#"one
+        two
+
+        three
+        four
+        five
+        ˇ
+        sixˇ"#
+            .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_to_end_of_paragraph(&MoveToEndOfParagraph, cx));
+    cx.assert_editor_state(
+        &r#"one
+        two
+
+        three
+        four
+        five
+
+        sixˇ"#
+            .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_to_start_of_paragraph(&MoveToStartOfParagraph, cx));
+    cx.assert_editor_state(
+        &r#"one
+        two
+
+        three
+        four
+        five
+        ˇ
+        six"#
+            .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_to_start_of_paragraph(&MoveToStartOfParagraph, cx));
+    cx.assert_editor_state(
+        &r#"one
+        two
+        ˇ
+        three
+        four
+        five
+
+        six"#
+            .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_to_start_of_paragraph(&MoveToStartOfParagraph, cx));
+    cx.assert_editor_state(
+        &r#"ˇone
+        two
+
+        three
+        four
+        five
+
+        six"#
+            .unindent(),
+    );
+}
+
+#[gpui::test]
+fn test_scroll_to_anchor(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(100, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = editor.update(cx, |editor, cx| {
+        // Fold some rows so that display rows differ from buffer rows.
+        editor.fold_ranges([Point::new(2, 0)..Point::new(12, 0)], true, cx);
+        editor.set_scroll_position(point(1., 0.), cx);
+
+        let anchor = editor
+            .buffer()
+            .read(cx)
+            .snapshot(cx)
+            .anchor_before(Point::new(60, 2));
+        editor.scroll_to_anchor(anchor, cx);
+
+        let snapshot = editor.snapshot(cx);
+        let display_row = anchor.to_display_point(&snapshot).row();
+        assert_eq!(display_row, 50);
+        assert_eq!(editor.scroll_position(cx), point(1., display_row as f32));
+        assert_eq!(
+            editor
+                .snapshot(cx)
+                .scroll_anchor
+                .top_row(&snapshot.buffer_snapshot),
+            60
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_scroll_page_up_page_down(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let line_height = cx.editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 4. * line_height + px(0.5)));
+
+    cx.set_state(
+        &r#"ˇone
+        two
         three
         four
+        five
+        six
+        seven
+        eight
+        nine
+        ten
+        "#,
+    );
+
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 0.)
+        );
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 3.)
+        );
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 6.)
+        );
+        editor.scroll_screen(&ScrollAmount::Page(-1.), cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 3.)
+        );
+
+        editor.scroll_screen(&ScrollAmount::Page(-0.5), cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 1.)
+        );
+        editor.scroll_screen(&ScrollAmount::Page(0.5), cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 3.)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_scroll_cursor_center(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let line_height = cx.editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 6. * line_height + px(0.5)));
+
+    cx.set_state(&sample_text(20, 3, 'a').replacen("aaa", "ˇaaa", 1));
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(10, 1)..Point::new(10, 1)])
+        });
+        editor.scroll_cursor_center(&ScrollCursorCenter, cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 7.)
+        );
+    });
+
+    // The viewport is recentered even if the cursor is already visible.
+    cx.update_editor(|editor, cx| {
+        editor.set_scroll_position(gpui::Point::new(0., 0.), cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(4, 0)..Point::new(4, 0)])
+        });
+        editor.scroll_cursor_center(&ScrollCursorCenter, cx);
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 1.)
+        );
+        assert_eq!(
+            editor.selections.newest::<Point>(cx).head(),
+            Point::new(4, 0)
+        );
+    });
+}
+
+#[gpui::test]
+fn test_center_on_cursors(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(40, 3, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+
+    // With six visible lines, cursors spanning four rows are centered together.
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([
+                Point::new(13, 0)..Point::new(13, 0),
+                Point::new(10, 0)..Point::new(10, 0),
+            ])
+        });
+        view.center_on_cursors(&CenterOnCursors, cx);
+        view.autoscroll_vertically(px(60.), px(10.), cx);
+        assert_eq!(view.scroll_position(cx), gpui::Point::new(0., 9.));
+    });
+
+    // Cursors that don't fit in the viewport center on the newest one instead.
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([
+                Point::new(2, 0)..Point::new(2, 0),
+                Point::new(30, 0)..Point::new(30, 0),
+            ])
+        });
+        view.center_on_cursors(&CenterOnCursors, cx);
+        view.autoscroll_vertically(px(60.), px(10.), cx);
+        assert_eq!(view.scroll_position(cx), gpui::Point::new(0., 28.));
+    });
+}
+
+#[gpui::test]
+fn test_swap_selection_ends(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(40, 3, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(5, 1)..Point::new(30, 2)])
+        });
+        view.set_scroll_position(gpui::Point::new(0., 26.), cx);
+
+        view.swap_selection_ends(&SwapSelectionEnds, cx);
+        let selection = view.selections.newest::<Point>(cx);
+        assert!(selection.reversed);
+        assert_eq!(selection.head(), Point::new(5, 1));
+
+        // The new head at the start of the selection is scrolled into view.
+        view.autoscroll_vertically(px(60.), px(10.), cx);
+        let scroll_top = view.scroll_position(cx).y;
+        assert!(scroll_top <= 5. && scroll_top + 6. > 5., "{scroll_top}");
+
+        view.swap_selection_ends(&SwapSelectionEnds, cx);
+        let selection = view.selections.newest::<Point>(cx);
+        assert!(!selection.reversed);
+        assert_eq!(selection.head(), Point::new(30, 2));
+    });
+}
+
+#[gpui::test]
+fn test_cycle_line_numbers(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        assert_eq!(view.line_numbers_mode(cx), LineNumbersMode::Absolute);
+        assert_eq!(
+            view.snapshot(cx).line_numbers_mode,
+            LineNumbersMode::Absolute
+        );
+
+        view.cycle_line_numbers(&CycleLineNumbers, cx);
+        assert_eq!(
+            view.snapshot(cx).line_numbers_mode,
+            LineNumbersMode::Relative
+        );
+
+        view.cycle_line_numbers(&CycleLineNumbers, cx);
+        assert_eq!(view.snapshot(cx).line_numbers_mode, LineNumbersMode::Hidden);
+
+        view.cycle_line_numbers(&CycleLineNumbers, cx);
+        assert_eq!(
+            view.snapshot(cx).line_numbers_mode,
+            LineNumbersMode::Absolute
+        );
+    });
+
+    // Hidden line numbers don't reserve any width in the gutter.
+    _ = view.update(cx, |view, cx| {
+        let font_id = cx.text_system().resolve_font(&gpui::font("Helvetica"));
+        let (font_size, em_width, max_line_number_width) = (px(14.), px(8.), px(24.));
+        let gutter_width = |view: &mut Editor, cx: &mut ViewContext<Editor>| {
+            view.snapshot(cx)
+                .gutter_dimensions(font_id, font_size, em_width, max_line_number_width, cx)
+                .width
+        };
+
+        let padding = px(32.);
+        assert_eq!(gutter_width(view, cx), em_width * 4. + padding * 2.);
+        view.cycle_line_numbers(&CycleLineNumbers, cx);
+        assert_eq!(gutter_width(view, cx), em_width * 4. + padding * 2.);
+        view.cycle_line_numbers(&CycleLineNumbers, cx);
+        assert_eq!(gutter_width(view, cx), padding * 2.);
+    });
+}
+
+#[gpui::test]
+async fn test_animate_scroll_to(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(&format!("ˇ{}", sample_text(30, 5, 'a')));
+
+    cx.update_editor(|editor, cx| {
+        editor.animate_scroll_to(gpui::Point::new(0., 10.), Duration::from_millis(160), cx);
+        assert!(editor.scroll_manager.is_animating());
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 0.)
+        );
+    });
+
+    cx.executor().advance_clock(Duration::from_millis(48));
+    cx.run_until_parked();
+    let halfway = cx.update_editor(|editor, cx| editor.snapshot(cx).scroll_position());
+    assert!(halfway.y > 0. && halfway.y < 10.);
+
+    cx.executor().advance_clock(Duration::from_millis(200));
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert!(!editor.scroll_manager.is_animating());
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 10.)
+        );
+    });
+
+    // Scrolling while an animation is in flight cancels it.
+    cx.update_editor(|editor, cx| {
+        editor.animate_scroll_to(gpui::Point::new(0., 0.), Duration::from_millis(160), cx);
+        editor.set_scroll_position(gpui::Point::new(0., 5.), cx);
+        assert!(!editor.scroll_manager.is_animating());
+    });
+    cx.executor().advance_clock(Duration::from_millis(200));
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 5.)
+        );
+    });
+
+    // So does setting the scroll anchor explicitly.
+    cx.update_editor(|editor, cx| {
+        editor.animate_scroll_to(gpui::Point::new(0., 0.), Duration::from_millis(160), cx);
+        let anchor = editor.scroll_manager.anchor();
+        editor.set_scroll_anchor(anchor, cx);
+        assert!(!editor.scroll_manager.is_animating());
+    });
+    cx.executor().advance_clock(Duration::from_millis(200));
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 5.)
+        );
+    });
+}
+
+#[gpui::test]
+fn test_visible_row_range(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(20, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        assert_eq!(view.visible_row_range(cx), 0..0);
+
+        view.set_visible_line_count(5., cx);
+        view.set_scroll_position(gpui::Point::new(0., 3.), cx);
+        assert_eq!(view.visible_row_range(cx), 3..8);
+
+        // Partially visible rows are included.
+        view.set_scroll_position(gpui::Point::new(0., 3.5), cx);
+        assert_eq!(view.visible_row_range(cx), 3..9);
+
+        // The range is clamped to the end of the buffer.
+        view.set_scroll_position(gpui::Point::new(0., 17.), cx);
+        assert_eq!(view.visible_row_range(cx), 17..20);
+    });
+}
+
+#[gpui::test]
+async fn test_autoscroll_horizontally_past_max_line_len(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(500.), 6. * line_height));
+
+    let line = "a".repeat(2000);
+    cx.set_state(&format!("ˇ{line}\n"));
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+            selections.select_ranges([Point::new(0, 1500)..Point::new(0, 1500)]);
+        })
+    });
+    cx.run_until_parked();
+
+    // Only the first `MAX_LINE_LEN` columns are laid out, so the editor scrolls
+    // to reveal the end of that portion rather than an x beyond the line.
+    let scroll_position = cx.update_editor(|editor, cx| editor.snapshot(cx).scroll_position());
+    assert!(scroll_position.x.is_finite());
+    assert!(scroll_position.x > 0.);
+    assert!(scroll_position.x <= MAX_LINE_LEN as f32);
+}
+
+#[gpui::test]
+async fn test_autoscroll(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        editor.set_vertical_scroll_margin(2, cx);
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 6. * line_height));
+
+    cx.set_state(
+        &r#"ˇone
+            two
+            three
+            four
+            five
+            six
+            seven
+            eight
+            nine
+            ten
+        "#,
+    );
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 0.0)
+        );
+    });
+
+    // Add a cursor below the visible area. Since both cursors cannot fit
+    // on screen, the editor autoscrolls to reveal the newest cursor, and
+    // allows the vertical scroll margin below that cursor.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+            selections.select_ranges([
+                Point::new(0, 0)..Point::new(0, 0),
+                Point::new(6, 0)..Point::new(6, 0),
+            ]);
+        })
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 3.0)
+        );
+    });
+
+    // Move down. The editor cursor scrolls down to track the newest cursor.
+    cx.update_editor(|editor, cx| {
+        editor.move_down(&Default::default(), cx);
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 4.0)
+        );
+    });
+
+    // Add a cursor above the visible area. Since both cursors fit on screen,
+    // the editor scrolls to show both.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::fit()), cx, |selections| {
+            selections.select_ranges([
+                Point::new(1, 0)..Point::new(1, 0),
+                Point::new(6, 0)..Point::new(6, 0),
+            ]);
+        })
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 1.0)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_autoscroll_with_scroll_margin_setting(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.scroll_margin_rows = Some(8);
+            });
+        })
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        assert_eq!(editor.vertical_scroll_margin(), 8);
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 20. * line_height));
+
+    cx.set_state(&format!("ˇ{}", sample_text(40, 3, 'a')));
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select_ranges([Point::new(11, 0)..Point::new(11, 0)])
+        });
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 0.0)
+        );
+    });
+
+    // Moving down near the bottom of the viewport keeps 8 rows visible below the cursor.
+    cx.update_editor(|editor, cx| {
+        editor.move_down(&Default::default(), cx);
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 1.0)
+        );
+    });
+    cx.update_editor(|editor, cx| {
+        editor.move_down(&Default::default(), cx);
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 2.0)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_scroll_margin_clamped_to_half_the_viewport(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.scroll_margin_rows = Some(8);
+            });
+        })
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 10. * line_height));
+
+    cx.set_state(&format!("ˇ{}", sample_text(40, 3, 'a')));
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.vertical_scroll_margin(), 5);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(20, 0)..Point::new(20, 0)])
+        });
+    });
+
+    cx.update_editor(|editor, cx| {
+        editor.scroll_cursor_top(&ScrollCursorTop, cx);
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 15.0)
+        );
+    });
+
+    cx.update_editor(|editor, cx| {
+        editor.scroll_cursor_bottom(&ScrollCursorBottom, cx);
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 15.0)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_autoscroll_top(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.update_editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(1000.), 10. * line_height));
+
+    cx.set_state(&format!("ˇ{}", sample_text(20, 3, 'a')));
+
+    // The target row is placed near the top of the viewport, leaving the
+    // vertical scroll margin above it.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::top()), cx, |s| {
+            s.select_ranges([Point::new(12, 0)..Point::new(12, 0)])
+        });
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 9.0)
+        );
+    });
+
+    // Near the start of the buffer, the scroll position is clamped.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::top()), cx, |s| {
+            s.select_ranges([Point::new(1, 0)..Point::new(1, 0)])
+        });
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 0.0)
+        );
+    });
+
+    // Cycling through the strategies places the row at the very top, without a margin.
+    cx.update_editor(|editor, cx| {
+        editor.change_selections(Some(Autoscroll::Next), cx, |s| {
+            s.select_ranges([Point::new(12, 0)..Point::new(12, 0)])
+        });
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 8.0)
+        );
+        editor.request_autoscroll(Autoscroll::Next, cx);
+    });
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.snapshot(cx).scroll_position(),
+            gpui::Point::new(0., 12.0)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_move_page_up_page_down(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(100.), 4. * line_height));
+    cx.set_state(
+        &r#"
+        ˇone
+        two
+        threeˇ
+        four
+        five
+        six
+        seven
+        eight
+        nine
+        ten
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_page_down(&MovePageDown::default(), cx));
+    cx.assert_editor_state(
+        &r#"
+        one
+        two
+        three
+        ˇfour
+        five
+        sixˇ
+        seven
+        eight
+        nine
+        ten
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_page_down(&MovePageDown::default(), cx));
+    cx.assert_editor_state(
+        &r#"
+        one
+        two
+        three
+        four
+        five
+        six
+        ˇseven
+        eight
+        nineˇ
+        ten
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_page_up(&MovePageUp::default(), cx));
+    cx.assert_editor_state(
+        &r#"
+        one
+        two
+        three
+        ˇfour
+        five
+        sixˇ
+        seven
+        eight
+        nine
+        ten
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.move_page_up(&MovePageUp::default(), cx));
+    cx.assert_editor_state(
+        &r#"
+        ˇone
+        two
+        threeˇ
+        four
+        five
+        six
+        seven
+        eight
+        nine
+        ten
+        "#
+        .unindent(),
+    );
+
+    // Test select collapsing
+    cx.update_editor(|editor, cx| {
+        editor.move_page_down(&MovePageDown::default(), cx);
+        editor.move_page_down(&MovePageDown::default(), cx);
+        editor.move_page_down(&MovePageDown::default(), cx);
+    });
+    cx.assert_editor_state(
+        &r#"
+        one
+        two
+        three
+        four
+        five
+        six
+        seven
+        eight
+        nine
+        ˇten
+        ˇ"#
+        .unindent(),
+    );
+}
+
+#[gpui::test]
+fn test_move_page_up_down_with_less_than_one_visible_line(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one\ntwo\nthree\n", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.set_visible_line_count(0.5, cx);
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 0)..Point::new(1, 0)])
+        });
+
+        view.move_page_down(&MovePageDown::default(), cx);
+        assert_eq!(
+            view.selections.ranges::<Point>(cx),
+            &[Point::new(2, 0)..Point::new(2, 0)]
+        );
+
+        view.move_page_up(&MovePageUp::default(), cx);
+        view.move_page_up(&MovePageUp::default(), cx);
+        assert_eq!(
+            view.selections.ranges::<Point>(cx),
+            &[Point::new(0, 0)..Point::new(0, 0)]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_select_page_up_page_down(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let line_height = cx.editor(|editor, cx| {
+        editor
+            .style()
+            .unwrap()
+            .text
+            .line_height_in_pixels(cx.rem_size())
+    });
+    let window = cx.window;
+    cx.simulate_window_resize(window, size(px(100.), 4. * line_height));
+    cx.set_state(
+        &r#"
+        oˇne
+        two
+        three
+        four
+        five
+        six
+        seven
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.select_page_down(&SelectPageDown, cx));
+    cx.assert_editor_state(
+        &r#"
+        o«ne
+        two
+        three
+        fˇ»our
+        five
+        six
+        seven
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| editor.select_page_down(&SelectPageDown, cx));
+    cx.assert_editor_state(
+        &r#"
+        o«ne
+        two
+        three
+        four
+        five
+        six
+        sˇ»even
+        "#
+        .unindent(),
+    );
+
+    cx.update_editor(|editor, cx| {
+        editor.select_page_up(&SelectPageUp, cx);
+        editor.select_page_up(&SelectPageUp, cx);
+    });
+    cx.assert_editor_state(
+        &r#"
+        oˇne
+        two
+        three
+        four
+        five
+        six
+        seven
+        "#
+        .unindent(),
+    );
+}
+
+#[gpui::test]
+async fn test_delete_to_beginning_of_line(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("one «two threeˇ» four");
+    cx.update_editor(|editor, cx| {
+        editor.delete_to_beginning_of_line(&DeleteToBeginningOfLine, cx);
+        assert_eq!(editor.text(cx), " four");
+    });
+}
+
+#[gpui::test]
+fn test_delete_to_word_boundary(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one two three four", cx);
+        build_editor(buffer.clone(), cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                // an empty selection - the preceding word fragment is deleted
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
+                // characters selected - they are deleted
+                DisplayPoint::new(0, 9)..DisplayPoint::new(0, 12),
+            ])
+        });
+        view.delete_to_previous_word_start(&DeleteToPreviousWordStart, cx);
+        assert_eq!(view.buffer.read(cx).read(cx).text(), "e two te four");
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                // an empty selection - the following word fragment is deleted
+                DisplayPoint::new(0, 3)..DisplayPoint::new(0, 3),
+                // characters selected - they are deleted
+                DisplayPoint::new(0, 9)..DisplayPoint::new(0, 10),
+            ])
+        });
+        view.delete_to_next_word_end(&DeleteToNextWordEnd, cx);
+        assert_eq!(view.buffer.read(cx).read(cx).text(), "e t te our");
+    });
+}
+
+#[gpui::test]
+fn test_newline(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaaa\n    bbbb\n", cx);
+        build_editor(buffer.clone(), cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
+                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
+                DisplayPoint::new(1, 6)..DisplayPoint::new(1, 6),
+            ])
+        });
+
+        view.newline(&Newline, cx);
+        assert_eq!(view.text(cx), "aa\naa\n  \n    bb\n    bb\n");
+    });
+}
+
+#[gpui::test]
+fn test_newline_with_old_selections(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(
+            "
+                a
+                b(
+                    X
+                )
+                c(
+                    X
+                )
+            "
+            .unindent()
+            .as_str(),
+            cx,
+        );
+        let mut editor = build_editor(buffer.clone(), cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([
+                Point::new(2, 4)..Point::new(2, 5),
+                Point::new(5, 4)..Point::new(5, 5),
+            ])
+        });
+        editor
+    });
+
+    _ = editor.update(cx, |editor, cx| {
+        // Edit the buffer directly, deleting ranges surrounding the editor's selections
+        editor.buffer.update(cx, |buffer, cx| {
+            buffer.edit(
+                [
+                    (Point::new(1, 2)..Point::new(3, 0), ""),
+                    (Point::new(4, 2)..Point::new(6, 0), ""),
+                ],
+                None,
+                cx,
+            );
+            assert_eq!(
+                buffer.read(cx).text(),
+                "
+                    a
+                    b()
+                    c()
+                "
+                .unindent()
+            );
+        });
+        assert_eq!(
+            editor.selections.ranges(cx),
+            &[
+                Point::new(1, 2)..Point::new(1, 2),
+                Point::new(2, 2)..Point::new(2, 2),
+            ],
+        );
+
+        editor.newline(&Newline, cx);
+        assert_eq!(
+            editor.text(cx),
+            "
+                a
+                b(
+                )
+                c(
+                )
+            "
+            .unindent()
+        );
+
+        // The selections are moved after the inserted newlines
+        assert_eq!(
+            editor.selections.ranges(cx),
+            &[
+                Point::new(2, 0)..Point::new(2, 0),
+                Point::new(4, 0)..Point::new(4, 0),
+            ],
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_newline_above(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4)
+    });
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
+        .unwrap(),
+    );
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(indoc! {"
+        const a: ˇA = (
+            (ˇ
+                «const_functionˇ»(ˇ),
+                so«mˇ»et«hˇ»ing_ˇelse,ˇ
+            )ˇ
+        ˇ);ˇ
+    "});
+
+    cx.update_editor(|e, cx| e.newline_above(&NewlineAbove, cx));
+    cx.assert_editor_state(indoc! {"
+        ˇ
+        const a: A = (
+            ˇ
+            (
+                ˇ
+                ˇ
+                const_function(),
+                ˇ
+                ˇ
+                ˇ
+                ˇ
+                something_else,
+                ˇ
+            )
+            ˇ
+            ˇ
+        );
+    "});
+}
+
+#[gpui::test]
+async fn test_newline_below(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4)
+    });
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
+        .unwrap(),
+    );
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(indoc! {"
+        const a: ˇA = (
+            (ˇ
+                «const_functionˇ»(ˇ),
+                so«mˇ»et«hˇ»ing_ˇelse,ˇ
+            )ˇ
+        ˇ);ˇ
+    "});
+
+    cx.update_editor(|e, cx| e.newline_below(&NewlineBelow, cx));
+    cx.assert_editor_state(indoc! {"
+        const a: A = (
+            ˇ
+            (
+                ˇ
+                const_function(),
+                ˇ
+                ˇ
+                something_else,
+                ˇ
+                ˇ
+                ˇ
+                ˇ
+            )
+            ˇ
+        );
+        ˇ
+        ˇ
+    "});
+}
+
+#[gpui::test]
+async fn test_newline_comments(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4)
+    });
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            line_comments: vec!["//".into()],
+            ..LanguageConfig::default()
+        },
+        None,
+    ));
+    {
+        let mut cx = EditorTestContext::new(cx).await;
+        cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+        cx.set_state(indoc! {"
+        // Fooˇ
+    "});
+
+        cx.update_editor(|e, cx| e.newline(&Newline, cx));
+        cx.assert_editor_state(indoc! {"
+        // Foo
+        //ˇ
+    "});
+        // Ensure that if cursor is before the comment start, we do not actually insert a comment prefix.
+        cx.set_state(indoc! {"
+        ˇ// Foo
+    "});
+        cx.update_editor(|e, cx| e.newline(&Newline, cx));
+        cx.assert_editor_state(indoc! {"
+
+        ˇ// Foo
+    "});
+    }
+    // Ensure that comment continuations can be disabled.
+    update_test_language_settings(cx, |settings| {
+        settings.defaults.extend_comment_on_newline = Some(false);
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        // Fooˇ
+    "});
+    cx.update_editor(|e, cx| e.newline(&Newline, cx));
+    cx.assert_editor_state(indoc! {"
+        // Foo
+        ˇ
+    "});
+}
+
+#[gpui::test]
+fn test_insert_with_old_selections(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("a( X ), b( Y ), c( Z )", cx);
+        let mut editor = build_editor(buffer.clone(), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([3..4, 11..12, 19..20]));
+        editor
+    });
+
+    _ = editor.update(cx, |editor, cx| {
+        // Edit the buffer directly, deleting ranges surrounding the editor's selections
+        editor.buffer.update(cx, |buffer, cx| {
+            buffer.edit([(2..5, ""), (10..13, ""), (18..21, "")], None, cx);
+            assert_eq!(buffer.read(cx).text(), "a(), b(), c()".unindent());
+        });
+        assert_eq!(editor.selections.ranges(cx), &[2..2, 7..7, 12..12],);
+
+        editor.insert("Z", cx);
+        assert_eq!(editor.text(cx), "a(Z), b(Z), c(Z)");
+
+        // The selections are moved after the inserted characters
+        assert_eq!(editor.selections.ranges(cx), &[3..3, 9..9, 15..15],);
+    });
+}
+
+#[gpui::test]
+async fn test_tab(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(3)
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        ˇabˇc
+        ˇ🏀ˇ🏀ˇefg
+        dˇ
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+           ˇab ˇc
+           ˇ🏀  ˇ🏀  ˇefg
+        d  ˇ
+    "});
+
+    cx.set_state(indoc! {"
+        a
+        «🏀ˇ»🏀«🏀ˇ»🏀«🏀ˇ»
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        a
+           «🏀ˇ»🏀«🏀ˇ»🏀«🏀ˇ»
+    "});
+}
+
+#[gpui::test]
+async fn test_tab_after_hard_tabs(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4)
+    });
+
+    // Tabs preceding the cursor are expanded when computing the next tab stop.
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("\tabˇc\n\tabcd\tˇe\nabˇc");
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state("\tab  ˇc\n\tabcd\t    ˇe\nab  ˇc");
+}
+
+#[gpui::test]
+async fn test_tab_in_leading_whitespace_auto_indents_lines(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "(" ")" @end) @indent"#)
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // cursors that are already at the suggested indent level insert
+    // a soft tab. cursors that are to the left of the suggested indent
+    // auto-indent their line.
+    cx.set_state(indoc! {"
+        ˇ
+        const a: B = (
+            c(
+                d(
+        ˇ
+                )
+        ˇ
+        ˇ    )
+        );
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+            ˇ
+        const a: B = (
+            c(
+                d(
+                    ˇ
+                )
+                ˇ
+            ˇ)
+        );
+    "});
+
+    // handle auto-indent when there are multiple cursors on the same line
+    cx.set_state(indoc! {"
+        const a: B = (
+            c(
+        ˇ    ˇ
+        ˇ    )
+        );
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        const a: B = (
+            c(
+                ˇ
+            ˇ)
+        );
+    "});
+}
+
+#[gpui::test]
+async fn test_tab_with_mixed_whitespace(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4)
+    });
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "{" "}" @end) @indent"#)
+        .unwrap(),
+    );
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(indoc! {"
+        fn a() {
+            if b {
+        \t ˇc
+            }
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            if b {
+                ˇc
+            }
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_indent_outdent(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+          «oneˇ» «twoˇ»
+        three
+         four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+            «oneˇ» «twoˇ»
+        three
+         four
+    "});
+
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        «oneˇ» «twoˇ»
+        three
+         four
+    "});
+
+    // select across line ending
+    cx.set_state(indoc! {"
+        one two
+        t«hree
+        ˇ» four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+            t«hree
+        ˇ» four
+    "});
+
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        t«hree
+        ˇ» four
+    "});
+
+    // Ensure that indenting/outdenting works when the cursor is at column 0.
+    cx.set_state(indoc! {"
+        one two
+        ˇthree
+            four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+            ˇthree
+            four
+    "});
+
+    cx.set_state(indoc! {"
+        one two
+        ˇ    three
+            four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        ˇthree
+            four
+    "});
+}
+
+#[gpui::test]
+async fn test_indent_outdent_with_hard_tabs(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.hard_tabs = Some(true);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // select two ranges on one line
+    cx.set_state(indoc! {"
+        «oneˇ» «twoˇ»
+        three
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        \t«oneˇ» «twoˇ»
+        three
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        \t\t«oneˇ» «twoˇ»
+        three
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        \t«oneˇ» «twoˇ»
+        three
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        «oneˇ» «twoˇ»
+        three
+        four
+    "});
+
+    // select across a line ending
+    cx.set_state(indoc! {"
+        one two
+        t«hree
+        ˇ»four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        \tt«hree
+        ˇ»four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        \t\tt«hree
+        ˇ»four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        \tt«hree
+        ˇ»four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        t«hree
+        ˇ»four
+    "});
+
+    // Ensure that indenting/outdenting works when the cursor is at column 0.
+    cx.set_state(indoc! {"
+        one two
+        ˇthree
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        ˇthree
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        \tˇthree
+        four
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one two
+        ˇthree
+        four
+    "});
+}
+
+#[gpui::test]
+async fn test_indent_outdent_round_trip_with_hard_tabs(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.hard_tabs = Some(true);
+        settings.defaults.tab_size = NonZeroU32::new(4);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Lines indented with spaces gain and lose a leading tab.
+    cx.set_state(indoc! {"
+        «one
+            two
+        \tthreeˇ»
+    "});
+    cx.update_editor(|e, cx| e.indent(&Indent, cx));
+    cx.update_editor(|e, cx| e.indent(&Indent, cx));
+    cx.assert_editor_state(indoc! {"
+        \t\t«one
+        \t\t    two
+        \t\t\tthreeˇ»
+    "});
+    cx.update_editor(|e, cx| e.outdent(&Outdent, cx));
+    cx.update_editor(|e, cx| e.outdent(&Outdent, cx));
+    cx.assert_editor_state(indoc! {"
+        «one
+            two
+        \tthreeˇ»
+    "});
+
+    // A tab after the indentation inserts a literal tab character.
+    cx.set_state("\tone ˇtwo");
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state("\tone \tˇtwo");
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state("one \tˇtwo");
+}
+
+#[gpui::test]
+fn test_replace_all_in_excerpts(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer_1 = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "one two\none\n"));
+    let buffer_2 = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "two one\n"));
+    let multibuffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
+        multibuffer.push_excerpts(
+            buffer_1.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(2, 0),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer.push_excerpts(
+            buffer_2.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(1, 0),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer
+    });
+
+    let view = cx.add_window(|cx| build_editor(multibuffer, cx));
+    _ = view.update(cx, |view, cx| {
+        view.replace_all_in_excerpts("one", "three", cx);
+        assert_eq!(view.text(cx), "three two\nthree\n\ntwo three\n");
+    });
+    assert_eq!(
+        buffer_1.read_with(cx, |buffer, _| buffer.text()),
+        "three two\nthree\n"
+    );
+    assert_eq!(
+        buffer_2.read_with(cx, |buffer, _| buffer.text()),
+        "two three\n"
+    );
+
+    // The replacement is undone across both buffers at once
+    _ = view.update(cx, |view, cx| view.undo(&Undo, cx));
+    assert_eq!(
+        buffer_1.read_with(cx, |buffer, _| buffer.text()),
+        "one two\none\n"
+    );
+    assert_eq!(
+        buffer_2.read_with(cx, |buffer, _| buffer.text()),
+        "two one\n"
+    );
+
+    // Matches shown by several overlapping excerpts are only replaced once
+    let buffer = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "a one b one c\n"));
+    let multibuffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
+        multibuffer.push_excerpts(
+            buffer.clone(),
+            [
+                ExcerptRange {
+                    context: Point::new(0, 0)..Point::new(0, 9),
+                    primary: None,
+                },
+                ExcerptRange {
+                    context: Point::new(0, 2)..Point::new(1, 0),
+                    primary: None,
+                },
+            ],
+            cx,
+        );
+        multibuffer
+    });
+    let view = cx.add_window(|cx| build_editor(multibuffer, cx));
+    _ = view.update(cx, |view, cx| {
+        view.replace_all_in_excerpts("one", "three", cx)
+    });
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "a three b three c\n"
+    );
+}
+
+#[gpui::test]
+async fn test_replace_all_selections(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("«aˇ» bb «ccccˇ» ddd «ˇ»e");
+    cx.update_editor(|e, cx| e.replace_all_selections("xyz", cx));
+    cx.assert_editor_state("xyzˇ bb xyzˇ ddd xyzˇe");
+
+    cx.set_state("one «twoˇ»\n«threeˇ» four");
+    cx.update_editor(|e, cx| e.replace_all_selections("", cx));
+    cx.assert_editor_state("one ˇ\nˇ four");
+}
+
+#[gpui::test]
+fn test_whole_buffer_edits_preserve_bom(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "\u{feff}one\ntwo"));
+    let view = cx.add_window(|cx| {
+        let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        assert!(view.has_bom(cx));
+
+        view.set_text("three", cx);
+        assert_eq!(view.text(cx), "\u{feff}three");
+
+        // A BOM at the start of the new text isn't duplicated
+        view.set_text("\u{feff}four\n\n", cx);
+        assert_eq!(view.text(cx), "\u{feff}four\n\n");
+    });
+
+    buffer.update(cx, |buffer, cx| buffer.ensure_final_newline(cx));
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "\u{feff}four\n"
+    );
+
+    _ = view.update(cx, |view, cx| {
+        view.set_text("", cx);
+        assert_eq!(view.text(cx), "\u{feff}");
+        assert!(view.has_bom(cx));
+    });
+
+    // Setting the buffer's text directly can still remove the BOM.
+    buffer.update(cx, |buffer, cx| buffer.set_text("five", cx));
+    assert_eq!(buffer.read_with(cx, |buffer, _| buffer.text()), "five");
+    _ = view.update(cx, |view, cx| assert!(!view.has_bom(cx)));
+}
+
+#[gpui::test]
+fn test_indent_outdent_with_excerpts(cx: &mut TestAppContext) {
+    init_test(cx, |settings| {
+        settings.languages.extend([
+            (
+                "TOML".into(),
+                LanguageSettingsContent {
+                    tab_size: NonZeroU32::new(2),
+                    ..Default::default()
+                },
+            ),
+            (
+                "Rust".into(),
+                LanguageSettingsContent {
+                    tab_size: NonZeroU32::new(4),
+                    ..Default::default()
+                },
+            ),
+        ]);
+    });
+
+    let toml_language = Arc::new(Language::new(
+        LanguageConfig {
+            name: "TOML".into(),
+            ..Default::default()
+        },
+        None,
+    ));
+    let rust_language = Arc::new(Language::new(
+        LanguageConfig {
+            name: "Rust".into(),
+            ..Default::default()
+        },
+        None,
+    ));
+
+    let toml_buffer = cx.new_model(|cx| {
+        Buffer::new(0, cx.entity_id().as_u64(), "a = 1\nb = 2\n").with_language(toml_language, cx)
+    });
+    let rust_buffer = cx.new_model(|cx| {
+        Buffer::new(0, cx.entity_id().as_u64(), "const c: usize = 3;\n")
+            .with_language(rust_language, cx)
+    });
+    let multibuffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
+        multibuffer.push_excerpts(
+            toml_buffer.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(2, 0),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer.push_excerpts(
+            rust_buffer.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(1, 0),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer
+    });
+
+    cx.add_window(|cx| {
+        let mut editor = build_editor(multibuffer, cx);
+
+        assert_eq!(
+            editor.text(cx),
+            indoc! {"
+                a = 1
+                b = 2
+
+                const c: usize = 3;
+            "}
+        );
+
+        select_ranges(
+            &mut editor,
+            indoc! {"
+                «aˇ» = 1
+                b = 2
+
+                «const c:ˇ» usize = 3;
+            "},
+            cx,
+        );
+
+        editor.tab(&Tab, cx);
+        assert_text_with_selections(
+            &mut editor,
+            indoc! {"
+                  «aˇ» = 1
+                b = 2
+
+                    «const c:ˇ» usize = 3;
+            "},
+            cx,
+        );
+        editor.tab_prev(&TabPrev, cx);
+        assert_text_with_selections(
+            &mut editor,
+            indoc! {"
+                «aˇ» = 1
+                b = 2
+
+                «const c:ˇ» usize = 3;
+            "},
+            cx,
+        );
+
+        editor
+    });
+}
+
+#[gpui::test]
+async fn test_backspace(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Basic backspace
+    cx.set_state(indoc! {"
+        onˇe two three
+        fou«rˇ» five six
+        seven «ˇeight nine
+        »ten
+    "});
+    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+    cx.assert_editor_state(indoc! {"
+        oˇe two three
+        fouˇ five six
+        seven ˇten
+    "});
+
+    // Test backspace inside and around indents
+    cx.set_state(indoc! {"
+        zero
+            ˇone
+                ˇtwo
+            ˇ ˇ ˇ  three
+        ˇ  ˇ  four
+    "});
+    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+    cx.assert_editor_state(indoc! {"
+        zero
+        ˇone
+            ˇtwo
+        ˇ  threeˇ  four
+    "});
+
+    // Test backspace with line_mode set to true
+    cx.update_editor(|e, _| e.selections.line_mode = true);
+    cx.set_state(indoc! {"
+        The ˇquick ˇbrown
+        fox jumps over
+        the lazy dog
+        ˇThe qu«ick bˇ»rown"});
+    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+    cx.assert_editor_state(indoc! {"
+        ˇfox jumps over
+        the lazy dogˇ"});
+}
+
+#[gpui::test]
+async fn test_delete(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        onˇe two three
+        fou«rˇ» five six
+        seven «ˇeight nine
+        »ten
+    "});
+    cx.update_editor(|e, cx| e.delete(&Delete, cx));
+    cx.assert_editor_state(indoc! {"
+        onˇ two three
+        fouˇ five six
+        seven ˇten
+    "});
+
+    // Test backspace with line_mode set to true
+    cx.update_editor(|e, _| e.selections.line_mode = true);
+    cx.set_state(indoc! {"
+        The ˇquick ˇbrown
+        fox «ˇjum»ps over
+        the lazy dog
+        ˇThe qu«ick bˇ»rown"});
+    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+    cx.assert_editor_state("ˇthe lazy dogˇ");
+}
+
+#[gpui::test]
+fn test_delete_line(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
+                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 1),
+                DisplayPoint::new(3, 0)..DisplayPoint::new(3, 0),
+            ])
+        });
+        view.delete_line(&DeleteLine, cx);
+        assert_eq!(view.display_text(cx), "ghi");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
+                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1)
+            ]
+        );
+    });
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(2, 0)..DisplayPoint::new(0, 1)])
+        });
+        view.delete_line(&DeleteLine, cx);
+        assert_eq!(view.display_text(cx), "ghi\n");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1)]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_delete_line_preserves_goal_column(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        one two threeˇ
+        four
+        five
+        six seven eight
+    "});
+    cx.update_editor(|e, cx| e.move_down(&MoveDown, cx));
+    cx.assert_editor_state(indoc! {"
+        one two three
+        fourˇ
+        five
+        six seven eight
+    "});
+
+    // Deleting the line clamps the cursor on a short line, but keeps its goal.
+    cx.update_editor(|e, cx| e.delete_line(&DeleteLine, cx));
+    cx.assert_editor_state(indoc! {"
+        one two three
+        fiveˇ
+        six seven eight
+    "});
+    cx.update_editor(|e, cx| e.move_down(&MoveDown, cx));
+    cx.assert_editor_state(indoc! {"
+        one two three
+        five
+        six seven eigˇht
+    "});
+
+    // The goal is established from the cursor's column when there is none yet.
+    cx.set_state(indoc! {"
+        abcdˇef
+        x
+        ghijkl
+    "});
+    cx.update_editor(|e, cx| e.delete_line(&DeleteLine, cx));
+    cx.assert_editor_state(indoc! {"
+        xˇ
+        ghijkl
+    "});
+    cx.update_editor(|e, cx| e.move_down(&MoveDown, cx));
+    cx.assert_editor_state(indoc! {"
+        x
+        ghijˇkl
+    "});
+}
+
+#[gpui::test]
+fn test_join_lines_with_single_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaa\nbbb\nccc\nddd\n\n", cx);
+        let mut editor = build_editor(buffer.clone(), cx);
+        let buffer = buffer.read(cx).as_singleton().unwrap();
+
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(0, 0)..Point::new(0, 0)]
+        );
+
+        // When on single line, replace newline at end by space
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd\n\n");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(0, 3)..Point::new(0, 3)]
+        );
+
+        // When multiple lines are selected, remove newlines that are spanned by the selection
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(0, 5)..Point::new(2, 2)])
+        });
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb ccc ddd\n\n");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(0, 11)..Point::new(0, 11)]
+        );
+
+        // Undo should be transactional
+        editor.undo(&Undo, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd\n\n");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(0, 5)..Point::new(2, 2)]
+        );
+
+        // When joining an empty line don't insert a space
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(2, 1)..Point::new(2, 2)])
+        });
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd\n");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(2, 3)..Point::new(2, 3)]
+        );
+
+        // We can remove trailing newlines
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(2, 3)..Point::new(2, 3)]
+        );
+
+        // We don't blow up on the last line
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(2, 3)..Point::new(2, 3)]
+        );
+
+        // reset to test indentation
+        editor.buffer.update(cx, |buffer, cx| {
+            buffer.edit(
+                [
+                    (Point::new(1, 0)..Point::new(1, 2), "  "),
+                    (Point::new(2, 0)..Point::new(2, 3), "  \n\td"),
+                ],
+                None,
+                cx,
+            )
+        });
+
+        // We remove any leading spaces
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\n  c\n  \n\td");
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(0, 1)..Point::new(0, 1)])
+        });
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb c\n  \n\td");
+
+        // We don't insert a space for a line containing only spaces
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb c\n\td");
+
+        // We ignore any leading tabs
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb c d");
+
+        // Joining through the final line doesn't add a trailing space
+        editor.set_text("aaa\nbbb\nccc", cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 1)..Point::new(2, 2)])
+        });
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa\nbbb ccc");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [Point::new(1, 3)..Point::new(1, 3)]
+        );
+
+        editor
+    });
+}
+
+#[gpui::test]
+fn test_join_lines_with_multi_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaa\nbbb\nccc\nddd\n\n", cx);
+        let mut editor = build_editor(buffer.clone(), cx);
+        let buffer = buffer.read(cx).as_singleton().unwrap();
+
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([
+                Point::new(0, 2)..Point::new(1, 1),
+                Point::new(1, 2)..Point::new(1, 2),
+                Point::new(3, 1)..Point::new(3, 2),
+            ])
+        });
+
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb ccc\nddd\n");
+
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [
+                Point::new(0, 7)..Point::new(0, 7),
+                Point::new(1, 3)..Point::new(1, 3)
+            ]
+        );
+        editor
+    });
+}
+
+#[gpui::test]
+async fn test_rewrap(cx: &mut TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.preferred_line_length = Some(40);
+    });
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            line_comments: vec!["// ".into()],
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // A cursor rewraps the whole comment paragraph around it.
+    cx.set_state(indoc! {"
+        fn a() {
+            // ˇLorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod
+            // tempor incididunt.
+        }
+    "});
+    cx.update_editor(|e, cx| e.rewrap(&Rewrap, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            // Lorem ipsum dolor sit amet,
+            // consectetur adipiscing elit, sed
+            // do eiusmod tempor incididunt.ˇ
+        }
+    "});
+
+    // A selection only rewraps the lines it spans.
+    cx.set_state(indoc! {"
+        // «one two
+        // threeˇ»
+        //
+        // four
+        // five
+    "});
+    cx.update_editor(|e, cx| e.rewrap(&Rewrap, cx));
+    cx.assert_editor_state(indoc! {"
+        // one two threeˇ
+        //
+        // four
+        // five
+    "});
+}
+
+#[gpui::test]
+async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Test sort_lines_case_insensitive()
+    cx.set_state(indoc! {"
+        «z
+        y
+        x
+        Z
+        Y
+        Xˇ»
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_case_insensitive(&SortLinesCaseInsensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «x
+        X
+        y
+        Y
+        z
+        Zˇ»
+    "});
+
+    // Test reverse_lines()
+    cx.set_state(indoc! {"
+        «5
+        4
+        3
+        2
+        1ˇ»
+    "});
+    cx.update_editor(|e, cx| e.reverse_lines(&ReverseLines, cx));
+    cx.assert_editor_state(indoc! {"
+        «1
+        2
+        3
+        4
+        5ˇ»
+    "});
+
+    // Reversing the last three lines of a buffer without a trailing newline
+    // keeps the selection over the same rows, and neither adds nor drops a newline.
+    cx.set_state(indoc! {"
+        first
+        on«ˇe
+        two
+        thr»ee"});
+    cx.update_editor(|e, cx| e.reverse_lines(&ReverseLines, cx));
+    cx.assert_editor_state(indoc! {"
+        first
+        «ˇthree
+        two
+        one»"});
+
+    // Skip testing shuffle_line()
+
+    // From here on out, test more complex cases of manipulate_lines() with a single driver method: sort_lines_case_sensitive()
+    // Since all methods calling manipulate_lines() are doing the exact same general thing (reordering lines)
+
+    // Don't manipulate when cursor is on single line, but expand the selection
+    cx.set_state(indoc! {"
+        ddˇdd
+        ccc
+        bb
+        a
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «ddddˇ»
+        ccc
+        bb
+        a
+    "});
+
+    // Basic manipulate case
+    // Start selection moves to column 0
+    // End of selection shrinks to fit shorter line
+    cx.set_state(indoc! {"
+        dd«d
+        ccc
+        bb
+        aaaaaˇ»
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «aaaaa
+        bb
+        ccc
+        dddˇ»
+    "});
+
+    // Manipulate case with newlines
+    cx.set_state(indoc! {"
+        dd«d
+        ccc
+
+        bb
+        aaaaa
+
+        ˇ»
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «
+
+        aaaaa
+        bb
+        ccc
+        dddˇ»
+
+    "});
+
+    // Manipulating the last lines of a buffer without a trailing newline
+    // neither adds nor drops a newline
+    cx.set_state(indoc! {"
+        first
+        c«cc
+        bb
+        aˇ»"});
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        first
+        «a
+        bb
+        cccˇ»"});
+}
+
+#[gpui::test]
+async fn test_manipulate_lines_with_multi_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Manipulate with multiple selections on a single line
+    cx.set_state(indoc! {"
+        dd«dd
+        cˇ»c«c
+        bb
+        aaaˇ»aa
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «aaaaa
+        bb
+        ccc
+        ddddˇ»
+    "});
+
+    // Manipulate with multiple disjoin selections
+    cx.set_state(indoc! {"
+        5«
+        4
+        3
+        2
+        1ˇ»
+
+        dd«dd
+        ccc
+        bb
+        aaaˇ»aa
     "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
     cx.assert_editor_state(indoc! {"
-        \t«oneˇ» «twoˇ»
-        three
-        four
+        «1
+        2
+        3
+        4
+        5ˇ»
+
+        «aaaaa
+        bb
+        ccc
+        ddddˇ»
     "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        \t\t«oneˇ» «twoˇ»
-        three
-        four
+}
+
+#[gpui::test]
+async fn test_sort_lines_following_content(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Cursors on adjacent lines move along with their lines.
+    cx.set_state(indoc! {"
+        cˇat
+        ˇbat
+        aˇnt
     "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.update_editor(|e, cx| e.sort_lines(true, true, cx));
     cx.assert_editor_state(indoc! {"
-        \t«oneˇ» «twoˇ»
-        three
-        four
+        aˇnt
+        ˇbat
+        cˇat
     "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+
+    // A selection becomes a cursor on the line its head was on.
+    cx.set_state(indoc! {"
+        «dog
+        Cat
+        biˇ»rd
+    "});
+    cx.update_editor(|e, cx| e.sort_lines(false, true, cx));
     cx.assert_editor_state(indoc! {"
-        «oneˇ» «twoˇ»
-        three
-        four
+        biˇrd
+        Cat
+        dog
     "});
 
-    // select across a line ending
+    // Duplicate lines keep their relative order.
     cx.set_state(indoc! {"
-        one two
-        t«hree
-        ˇ»four
+        «b
+        a
+        bˇ»
+        a
     "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.update_editor(|e, cx| e.sort_lines(true, true, cx));
     cx.assert_editor_state(indoc! {"
-        one two
-        \tt«hree
-        ˇ»four
+        a
+        b
+        bˇ
+        a
     "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        one two
-        \t\tt«hree
-        ˇ»four
+}
+
+#[gpui::test]
+async fn test_manipulate_text(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Test convert_to_upper_case()
+    cx.set_state(indoc! {"
+        «hello worldˇ»
     "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
     cx.assert_editor_state(indoc! {"
-        one two
-        \tt«hree
-        ˇ»four
+        «HELLO WORLDˇ»
     "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+
+    // Test convert_to_lower_case()
+    cx.set_state(indoc! {"
+        «HELLO WORLDˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_lower_case(&ConvertToLowerCase, cx));
     cx.assert_editor_state(indoc! {"
-        one two
-        t«hree
-        ˇ»four
+        «hello worldˇ»
     "});
 
-    // Ensure that indenting/outdenting works when the cursor is at column 0.
+    // Test multiple line, single selection case
+    // Test code hack that covers the fact that to_case crate doesn't support '\n' as a word boundary
     cx.set_state(indoc! {"
-        one two
-        ˇthree
-        four
+        «The quick brown
+        fox jumps over
+        the lazy dogˇ»
     "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.update_editor(|e, cx| e.convert_to_title_case(&ConvertToTitleCase, cx));
     cx.assert_editor_state(indoc! {"
-        one two
-        ˇthree
-        four
+        «The Quick Brown
+        Fox Jumps Over
+        The Lazy Dogˇ»
     "});
-    cx.update_editor(|e, cx| e.tab(&Tab, cx));
-    cx.assert_editor_state(indoc! {"
-        one two
-        \tˇthree
-        four
+
+    // Test multiple line, single selection case
+    // Test code hack that covers the fact that to_case crate doesn't support '\n' as a word boundary
+    cx.set_state(indoc! {"
+        «The quick brown
+        fox jumps over
+        the lazy dogˇ»
     "});
-    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.update_editor(|e, cx| e.convert_to_upper_camel_case(&ConvertToUpperCamelCase, cx));
     cx.assert_editor_state(indoc! {"
-        one two
-        ˇthree
-        four
+        «TheQuickBrown
+        FoxJumpsOver
+        TheLazyDogˇ»
     "});
-}
-
-#[gpui::test]
-fn test_indent_outdent_with_excerpts(cx: &mut TestAppContext) {
-    init_test(cx, |settings| {
-        settings.languages.extend([
-            (
-                "TOML".into(),
-                LanguageSettingsContent {
-                    tab_size: NonZeroU32::new(2),
-                    ..Default::default()
-                },
-            ),
-            (
-                "Rust".into(),
-                LanguageSettingsContent {
-                    tab_size: NonZeroU32::new(4),
-                    ..Default::default()
-                },
-            ),
-        ]);
-    });
-
-    let toml_language = Arc::new(Language::new(
-        LanguageConfig {
-            name: "TOML".into(),
-            ..Default::default()
-        },
-        None,
-    ));
-    let rust_language = Arc::new(Language::new(
-        LanguageConfig {
-            name: "Rust".into(),
-            ..Default::default()
-        },
-        None,
-    ));
-
-    let toml_buffer = cx.new_model(|cx| {
-        Buffer::new(0, cx.entity_id().as_u64(), "a = 1\nb = 2\n").with_language(toml_language, cx)
-    });
-    let rust_buffer = cx.new_model(|cx| {
-        Buffer::new(0, cx.entity_id().as_u64(), "const c: usize = 3;\n")
-            .with_language(rust_language, cx)
-    });
-    let multibuffer = cx.new_model(|cx| {
-        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
-        multibuffer.push_excerpts(
-            toml_buffer.clone(),
-            [ExcerptRange {
-                context: Point::new(0, 0)..Point::new(2, 0),
-                primary: None,
-            }],
-            cx,
-        );
-        multibuffer.push_excerpts(
-            rust_buffer.clone(),
-            [ExcerptRange {
-                context: Point::new(0, 0)..Point::new(1, 0),
-                primary: None,
-            }],
-            cx,
-        );
-        multibuffer
-    });
-
-    cx.add_window(|cx| {
-        let mut editor = build_editor(multibuffer, cx);
-
-        assert_eq!(
-            editor.text(cx),
-            indoc! {"
-                a = 1
-                b = 2
 
-                const c: usize = 3;
-            "}
-        );
+    // From here on out, test more complex cases of manipulate_text()
 
-        select_ranges(
-            &mut editor,
-            indoc! {"
-                «aˇ» = 1
-                b = 2
+    // Test no selection case - should affect words cursors are in
+    // Cursor at beginning, middle, and end of word
+    cx.set_state(indoc! {"
+        ˇhello big beauˇtiful worldˇ
+    "});
+    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «HELLOˇ» big «BEAUTIFULˇ» «WORLDˇ»
+    "});
 
-                «const c:ˇ» usize = 3;
-            "},
-            cx,
-        );
+    // Test multiple selections on a single line and across multiple lines
+    cx.set_state(indoc! {"
+        «Theˇ» quick «brown
+        foxˇ» jumps «overˇ»
+        the «lazyˇ» dog
+    "});
+    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «THEˇ» quick «BROWN
+        FOXˇ» jumps «OVERˇ»
+        the «LAZYˇ» dog
+    "});
 
-        editor.tab(&Tab, cx);
-        assert_text_with_selections(
-            &mut editor,
-            indoc! {"
-                  «aˇ» = 1
-                b = 2
+    // Test case where text length grows
+    cx.set_state(indoc! {"
+        «tschüßˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «TSCHÜSSˇ»
+    "});
 
-                    «const c:ˇ» usize = 3;
-            "},
-            cx,
-        );
-        editor.tab_prev(&TabPrev, cx);
-        assert_text_with_selections(
-            &mut editor,
-            indoc! {"
-                «aˇ» = 1
-                b = 2
+    // Test to make sure we don't crash when text shrinks
+    cx.set_state(indoc! {"
+        aaa_bbbˇ
+    "});
+    cx.update_editor(|e, cx| e.convert_to_lower_camel_case(&ConvertToLowerCamelCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «aaaBbbˇ»
+    "});
 
-                «const c:ˇ» usize = 3;
-            "},
-            cx,
-        );
+    // Test to make sure we all aware of the fact that each word can grow and shrink
+    // Final selections should be aware of this fact
+    cx.set_state(indoc! {"
+        aaa_bˇbb bbˇb_ccc ˇccc_ddd
+    "});
+    cx.update_editor(|e, cx| e.convert_to_lower_camel_case(&ConvertToLowerCamelCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «aaaBbbˇ» «bbbCccˇ» «cccDddˇ»
+    "});
 
-        editor
-    });
+    // Edits for every selection are undone together
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        aaa_bˇbb bbˇb_ccc ˇccc_ddd
+    "});
 }
 
 #[gpui::test]
-async fn test_backspace(cx: &mut gpui::TestAppContext) {
+async fn test_capitalize_word(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let mut cx = EditorTestContext::new(cx).await;
 
-    // Basic backspace
+    // Empty selections capitalize the word under the cursor
     cx.set_state(indoc! {"
-        onˇe two three
-        fou«rˇ» five six
-        seven «ˇeight nine
-        »ten
+        hello bigˇ beauˇtiful wORLD
     "});
-    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+    cx.update_editor(|e, cx| e.capitalize_word(&CapitalizeWord, cx));
     cx.assert_editor_state(indoc! {"
-        oˇe two three
-        fouˇ five six
-        seven ˇten
+        hello «Bigˇ» «Beautifulˇ» wORLD
     "});
 
-    // Test backspace inside and around indents
+    // Every word in a selection is capitalized, even across lines
     cx.set_state(indoc! {"
-        zero
-            ˇone
-                ˇtwo
-            ˇ ˇ ˇ  three
-        ˇ  ˇ  four
+        «the quick brown
+        fox jUMPSˇ» over
     "});
-    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+    cx.update_editor(|e, cx| e.capitalize_word(&CapitalizeWord, cx));
     cx.assert_editor_state(indoc! {"
-        zero
-        ˇone
-            ˇtwo
-        ˇ  threeˇ  four
+        «The Quick Brown
+        Fox JUMPSˇ» over
     "});
 
-    // Test backspace with line_mode set to true
-    cx.update_editor(|e, _| e.selections.line_mode = true);
+    // First letters whose uppercase form has a different length
     cx.set_state(indoc! {"
-        The ˇquick ˇbrown
-        fox jumps over
-        the lazy dog
-        ˇThe qu«ick bˇ»rown"});
-    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
+        «ßtraße ñandúˇ» ˇélan
+    "});
+    cx.update_editor(|e, cx| e.capitalize_word(&CapitalizeWord, cx));
     cx.assert_editor_state(indoc! {"
-        ˇfox jumps over
-        the lazy dogˇ"});
+        «SStraße Ñandúˇ» «Élanˇ»
+    "});
 }
 
 #[gpui::test]
-async fn test_delete(cx: &mut gpui::TestAppContext) {
+fn test_duplicate_line(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
-    let mut cx = EditorTestContext::new(cx).await;
-    cx.set_state(indoc! {"
-        onˇe two three
-        fou«rˇ» five six
-        seven «ˇeight nine
-        »ten
-    "});
-    cx.update_editor(|e, cx| e.delete(&Delete, cx));
-    cx.assert_editor_state(indoc! {"
-        onˇ two three
-        fouˇ five six
-        seven ˇten
-    "});
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 1),
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
+                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0),
+                DisplayPoint::new(3, 0)..DisplayPoint::new(3, 0),
+            ])
+        });
+        view.duplicate_line(&DuplicateLine, cx);
+        assert_eq!(view.display_text(cx), "abc\nabc\ndef\ndef\nghi\n\n");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 1),
+                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
+                DisplayPoint::new(3, 0)..DisplayPoint::new(3, 0),
+                DisplayPoint::new(6, 0)..DisplayPoint::new(6, 0),
+            ]
+        );
+    });
 
-    // Test backspace with line_mode set to true
-    cx.update_editor(|e, _| e.selections.line_mode = true);
-    cx.set_state(indoc! {"
-        The ˇquick ˇbrown
-        fox «ˇjum»ps over
-        the lazy dog
-        ˇThe qu«ick bˇ»rown"});
-    cx.update_editor(|e, cx| e.backspace(&Backspace, cx));
-    cx.assert_editor_state("ˇthe lazy dogˇ");
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 1)..DisplayPoint::new(1, 1),
+                DisplayPoint::new(1, 2)..DisplayPoint::new(2, 1),
+            ])
+        });
+        view.duplicate_line(&DuplicateLine, cx);
+        assert_eq!(view.display_text(cx), "abc\ndef\nghi\nabc\ndef\nghi\n");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(3, 1)..DisplayPoint::new(4, 1),
+                DisplayPoint::new(4, 2)..DisplayPoint::new(5, 1),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+fn test_duplicate_line_down(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\njkl\nmno", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 1),
+                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
+                DisplayPoint::new(2, 1)..DisplayPoint::new(3, 1),
+            ])
+        });
+        view.duplicate_line_down(&DuplicateLineDown, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "abc\nabc\ndef\nghi\njkl\nghi\njkl\nmno"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 1),
+                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
+                DisplayPoint::new(5, 1)..DisplayPoint::new(6, 1),
+            ]
+        );
+
+        // Duplicating the last line doesn't need a trailing newline.
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([DisplayPoint::new(7, 3)..DisplayPoint::new(7, 3)])
+        });
+        view.duplicate_line_down(&DuplicateLineDown, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "abc\nabc\ndef\nghi\njkl\nghi\njkl\nmno\nmno"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![DisplayPoint::new(8, 3)..DisplayPoint::new(8, 3)]
+        );
+    });
+}
+
+#[gpui::test]
+fn test_move_line_up_down(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(10, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.fold_ranges(
+            vec![
+                Point::new(0, 2)..Point::new(1, 2),
+                Point::new(2, 3)..Point::new(4, 1),
+                Point::new(7, 0)..Point::new(8, 4),
+            ],
+            true,
+            cx,
+        );
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
+                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
+                DisplayPoint::new(3, 2)..DisplayPoint::new(4, 3),
+                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 2),
+            ])
+        });
+        assert_eq!(
+            view.display_text(cx),
+            "aa⋯bbb\nccc⋯eeee\nfffff\nggggg\n⋯i\njjjjj"
+        );
+
+        view.move_line_up(&MoveLineUp, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "aa⋯bbb\nccc⋯eeee\nggggg\n⋯i\njjjjj\nfffff"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
+                DisplayPoint::new(2, 1)..DisplayPoint::new(2, 1),
+                DisplayPoint::new(2, 2)..DisplayPoint::new(3, 3),
+                DisplayPoint::new(4, 0)..DisplayPoint::new(4, 2)
+            ]
+        );
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.move_line_down(&MoveLineDown, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "ccc⋯eeee\naa⋯bbb\nfffff\nggggg\n⋯i\njjjjj"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(1, 1)..DisplayPoint::new(1, 1),
+                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
+                DisplayPoint::new(3, 2)..DisplayPoint::new(4, 3),
+                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 2)
+            ]
+        );
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.move_line_down(&MoveLineDown, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "ccc⋯eeee\nfffff\naa⋯bbb\nggggg\n⋯i\njjjjj"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(2, 1)..DisplayPoint::new(2, 1),
+                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
+                DisplayPoint::new(3, 2)..DisplayPoint::new(4, 3),
+                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 2)
+            ]
+        );
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.move_line_up(&MoveLineUp, cx);
+        assert_eq!(
+            view.display_text(cx),
+            "ccc⋯eeee\naa⋯bbb\nggggg\n⋯i\njjjjj\nfffff"
+        );
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(1, 1)..DisplayPoint::new(1, 1),
+                DisplayPoint::new(2, 1)..DisplayPoint::new(2, 1),
+                DisplayPoint::new(2, 2)..DisplayPoint::new(3, 3),
+                DisplayPoint::new(4, 0)..DisplayPoint::new(4, 2)
+            ]
+        );
+    });
 }
 
 #[gpui::test]
-fn test_delete_line(cx: &mut TestAppContext) {
+fn test_undo_redo_restores_folds(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
+        let buffer = MultiBuffer::build_simple(&sample_text(6, 3, 'a'), cx);
         build_editor(buffer, cx)
     });
     _ = view.update(cx, |view, cx| {
+        view.fold_ranges([Point::new(2, 1)..Point::new(3, 1)], true, cx);
         view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
-                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 1),
-                DisplayPoint::new(3, 0)..DisplayPoint::new(3, 0),
-            ])
+            s.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
         });
-        view.delete_line(&DeleteLine, cx);
-        assert_eq!(view.display_text(cx), "ghi");
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            vec![
-                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 0),
-                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1)
-            ]
-        );
-    });
+        assert_eq!(view.display_text(cx), "aaa\nbbb\nc⋯dd\neee\nfff");
 
-    let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
-        build_editor(buffer, cx)
-    });
-    _ = view.update(cx, |view, cx| {
+        // Moving the folded line refolds it at its new position.
+        view.move_line_up(&MoveLineUp, cx);
+        assert_eq!(view.display_text(cx), "aaa\nc⋯dd\nbbb\neee\nfff");
+
+        view.undo(&Undo, cx);
+        assert_eq!(view.display_text(cx), "aaa\nbbb\nc⋯dd\neee\nfff");
+
+        view.redo(&Redo, cx);
+        assert_eq!(view.display_text(cx), "aaa\nc⋯dd\nbbb\neee\nfff");
+
+        // Folds created after a transaction are left alone when undoing it.
+        view.undo(&Undo, cx);
         view.change_selections(None, cx, |s| {
-            s.select_display_ranges([DisplayPoint::new(2, 0)..DisplayPoint::new(0, 1)])
+            s.select_ranges([Point::new(0, 0)..Point::new(0, 0)])
         });
-        view.delete_line(&DeleteLine, cx);
-        assert_eq!(view.display_text(cx), "ghi\n");
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            vec![DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1)]
-        );
+        view.handle_input("x", cx);
+        view.fold_ranges([Point::new(4, 1)..Point::new(5, 1)], true, cx);
+        assert_eq!(view.display_text(cx), "xaaa\nbbb\nc⋯dd\ne⋯ff");
+        view.undo(&Undo, cx);
+        assert_eq!(view.display_text(cx), "aaa\nbbb\nc⋯dd\ne⋯ff");
     });
 }
 
 #[gpui::test]
-fn test_join_lines_with_single_selection(cx: &mut TestAppContext) {
+fn test_move_line_up_down_with_blocks(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
-    cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("aaa\nbbb\nccc\nddd\n\n", cx);
-        let mut editor = build_editor(buffer.clone(), cx);
-        let buffer = buffer.read(cx).as_singleton().unwrap();
-
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            &[Point::new(0, 0)..Point::new(0, 0)]
-        );
-
-        // When on single line, replace newline at end by space
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd\n\n");
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            &[Point::new(0, 3)..Point::new(0, 3)]
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(10, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer.read(cx).snapshot(cx);
+        editor.insert_blocks(
+            [BlockProperties {
+                style: BlockStyle::Fixed,
+                position: snapshot.anchor_after(Point::new(2, 0)),
+                disposition: BlockDisposition::Below,
+                height: 1,
+                render: Arc::new(|_| div().into_any()),
+            }],
+            Some(Autoscroll::fit()),
+            cx,
         );
-
-        // When multiple lines are selected, remove newlines that are spanned by the selection
         editor.change_selections(None, cx, |s| {
-            s.select_ranges([Point::new(0, 5)..Point::new(2, 2)])
+            s.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
         });
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb ccc ddd\n\n");
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            &[Point::new(0, 11)..Point::new(0, 11)]
-        );
+        editor.move_line_down(&MoveLineDown, cx);
+    });
+}
 
-        // Undo should be transactional
-        editor.undo(&Undo, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd\n\n");
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            &[Point::new(0, 5)..Point::new(2, 2)]
-        );
+#[gpui::test]
+fn test_transpose(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
 
-        // When joining an empty line don't insert a space
-        editor.change_selections(None, cx, |s| {
-            s.select_ranges([Point::new(2, 1)..Point::new(2, 2)])
-        });
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd\n");
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            [Point::new(2, 3)..Point::new(2, 3)]
-        );
+    _ = cx.add_window(|cx| {
+        let mut editor = build_editor(MultiBuffer::build_simple("abc", cx), cx);
+        editor.set_style(EditorStyle::default(), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([1..1]));
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bac");
+        assert_eq!(editor.selections.ranges(cx), [2..2]);
 
-        // We can remove trailing newlines
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd");
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            [Point::new(2, 3)..Point::new(2, 3)]
-        );
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bca");
+        assert_eq!(editor.selections.ranges(cx), [3..3]);
 
-        // We don't blow up on the last line
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb\nccc\nddd");
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            [Point::new(2, 3)..Point::new(2, 3)]
-        );
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bac");
+        assert_eq!(editor.selections.ranges(cx), [3..3]);
 
-        // reset to test indentation
-        editor.buffer.update(cx, |buffer, cx| {
-            buffer.edit(
-                [
-                    (Point::new(1, 0)..Point::new(1, 2), "  "),
-                    (Point::new(2, 0)..Point::new(2, 3), "  \n\td"),
-                ],
-                None,
-                cx,
-            )
-        });
+        editor
+    });
 
-        // We remove any leading spaces
-        assert_eq!(buffer.read(cx).text(), "aaa bbb\n  c\n  \n\td");
-        editor.change_selections(None, cx, |s| {
-            s.select_ranges([Point::new(0, 1)..Point::new(0, 1)])
-        });
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb c\n  \n\td");
+    _ = cx.add_window(|cx| {
+        let mut editor = build_editor(MultiBuffer::build_simple("abc\nde", cx), cx);
+        editor.set_style(EditorStyle::default(), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([3..3]));
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "acb\nde");
+        assert_eq!(editor.selections.ranges(cx), [3..3]);
 
-        // We don't insert a space for a line containing only spaces
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb c\n\td");
+        editor.change_selections(None, cx, |s| s.select_ranges([4..4]));
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "acbd\ne");
+        assert_eq!(editor.selections.ranges(cx), [5..5]);
 
-        // We ignore any leading tabs
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb c d");
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "acbde\n");
+        assert_eq!(editor.selections.ranges(cx), [6..6]);
+
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "acbd\ne");
+        assert_eq!(editor.selections.ranges(cx), [6..6]);
 
         editor
     });
-}
 
-#[gpui::test]
-fn test_join_lines_with_multi_selection(cx: &mut TestAppContext) {
-    init_test(cx, |_| {});
+    _ = cx.add_window(|cx| {
+        let mut editor = build_editor(MultiBuffer::build_simple("abc\nde", cx), cx);
+        editor.set_style(EditorStyle::default(), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([1..1, 2..2, 4..4]));
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bacd\ne");
+        assert_eq!(editor.selections.ranges(cx), [2..2, 3..3, 5..5]);
 
-    cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("aaa\nbbb\nccc\nddd\n\n", cx);
-        let mut editor = build_editor(buffer.clone(), cx);
-        let buffer = buffer.read(cx).as_singleton().unwrap();
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bcade\n");
+        assert_eq!(editor.selections.ranges(cx), [3..3, 4..4, 6..6]);
 
-        editor.change_selections(None, cx, |s| {
-            s.select_ranges([
-                Point::new(0, 2)..Point::new(1, 1),
-                Point::new(1, 2)..Point::new(1, 2),
-                Point::new(3, 1)..Point::new(3, 2),
-            ])
-        });
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bcda\ne");
+        assert_eq!(editor.selections.ranges(cx), [4..4, 6..6]);
 
-        editor.join_lines(&JoinLines, cx);
-        assert_eq!(buffer.read(cx).text(), "aaa bbb ccc\nddd\n");
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bcade\n");
+        assert_eq!(editor.selections.ranges(cx), [4..4, 6..6]);
+
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "bcaed\n");
+        assert_eq!(editor.selections.ranges(cx), [5..5, 6..6]);
 
-        assert_eq!(
-            editor.selections.ranges::<Point>(cx),
-            [
-                Point::new(0, 7)..Point::new(0, 7),
-                Point::new(1, 3)..Point::new(1, 3)
-            ]
-        );
         editor
     });
-}
-
-#[gpui::test]
-async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
-    init_test(cx, |_| {});
 
-    let mut cx = EditorTestContext::new(cx).await;
+    _ = cx.add_window(|cx| {
+        let mut editor = build_editor(MultiBuffer::build_simple("🍐🏀✋", cx), cx);
+        editor.set_style(EditorStyle::default(), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([4..4]));
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "🏀🍐✋");
+        assert_eq!(editor.selections.ranges(cx), [8..8]);
 
-    // Test sort_lines_case_insensitive()
-    cx.set_state(indoc! {"
-        «z
-        y
-        x
-        Z
-        Y
-        Xˇ»
-    "});
-    cx.update_editor(|e, cx| e.sort_lines_case_insensitive(&SortLinesCaseInsensitive, cx));
-    cx.assert_editor_state(indoc! {"
-        «x
-        X
-        y
-        Y
-        z
-        Zˇ»
-    "});
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "🏀✋🍐");
+        assert_eq!(editor.selections.ranges(cx), [11..11]);
 
-    // Test reverse_lines()
-    cx.set_state(indoc! {"
-        «5
-        4
-        3
-        2
-        1ˇ»
-    "});
-    cx.update_editor(|e, cx| e.reverse_lines(&ReverseLines, cx));
-    cx.assert_editor_state(indoc! {"
-        «1
-        2
-        3
-        4
-        5ˇ»
-    "});
+        editor.transpose(&Default::default(), cx);
+        assert_eq!(editor.text(cx), "🏀🍐✋");
+        assert_eq!(editor.selections.ranges(cx), [11..11]);
 
-    // Skip testing shuffle_line()
+        editor
+    });
+}
 
-    // From here on out, test more complex cases of manipulate_lines() with a single driver method: sort_lines_case_sensitive()
-    // Since all methods calling manipulate_lines() are doing the exact same general thing (reordering lines)
+#[gpui::test]
+async fn test_clear_selections_content(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
 
-    // Don't manipulate when cursor is on single line, but expand the selection
     cx.set_state(indoc! {"
-        ddˇdd
-        ccc
-        bb
-        a
+        one «twoˇ» three
+        «ˇfour
+        five» six ˇseven
+        eight «nineˇ»
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.clear_selections_content(&ClearSelectionsContent, cx));
     cx.assert_editor_state(indoc! {"
-        «ddddˇ»
-        ccc
-        bb
-        a
+        one ˇ three
+        ˇ six ˇseven
+        eight ˇ
     "});
 
-    // Basic manipulate case
-    // Start selection moves to column 0
-    // End of selection shrinks to fit shorter line
-    cx.set_state(indoc! {"
-        dd«d
-        ccc
-        bb
-        aaaaaˇ»
-    "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    // The deletions are undone in a single step.
+    cx.update_editor(|e, cx| e.undo(&Undo, cx));
     cx.assert_editor_state(indoc! {"
-        «aaaaa
-        bb
-        ccc
-        dddˇ»
+        one «twoˇ» three
+        «ˇfour
+        five» six ˇseven
+        eight «nineˇ»
     "});
 
-    // Manipulate case with newlines
-    cx.set_state(indoc! {"
-        dd«d
-        ccc
+    // Line mode doesn't expand the selections to whole lines.
+    cx.set_state("«oneˇ» two\nthree «ˇfour»");
+    cx.update_editor(|e, cx| {
+        e.selections.line_mode = true;
+        e.clear_selections_content(&ClearSelectionsContent, cx)
+    });
+    cx.assert_editor_state("ˇ two\nthree ˇ");
+}
 
-        bb
-        aaaaa
+#[gpui::test]
+async fn test_collapse_selection_after_copy(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
 
-        ˇ»
-    "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
-    cx.assert_editor_state(indoc! {"
-        «
+    let mut cx = EditorTestContext::new(cx).await;
 
-        aaaaa
-        bb
-        ccc
-        dddˇ»
+    // By default, copying preserves the selections.
+    cx.set_state("«oneˇ» two «ˇthree» four");
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+    cx.assert_editor_state("«oneˇ» two «ˇthree» four");
 
-    "});
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.collapse_selection_after_copy = Some(true);
+            });
+        });
+    });
+
+    // With the setting enabled, each selection collapses to its head.
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+    cx.assert_editor_state("oneˇ two ˇthree four");
+
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state("oneoneˇ two threeˇthree four");
 }
 
 #[gpui::test]
-async fn test_manipulate_lines_with_multi_selection(cx: &mut TestAppContext) {
+async fn test_word_count(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
     let mut cx = EditorTestContext::new(cx).await;
-
-    // Manipulate with multiple selections on a single line
-    cx.set_state(indoc! {"
-        dd«dd
-        cˇ»c«c
-        bb
-        aaaˇ»aa
-    "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
-    cx.assert_editor_state(indoc! {"
-        «aaaaa
-        bb
-        ccc
-        ddddˇ»
-    "});
-
-    // Manipulate with multiple disjoin selections
     cx.set_state(indoc! {"
-        5«
-        4
-        3
-        2
-        1ˇ»
-
-        dd«dd
-        ccc
-        bb
-        aaaˇ»aa
+        The «quick brown» fox
+        jumps   over\tthe lazy dog.
+        ˇ
+        «Ünïcödé ✅ wörds ˇ» héllo
+        日本語　テキスト
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
-    cx.assert_editor_state(indoc! {"
-        «1
-        2
-        3
-        4
-        5ˇ»
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.word_count(cx), 15);
+        assert_eq!(editor.selected_word_count(cx), 5);
+    });
 
-        «aaaaa
-        bb
-        ccc
-        ddddˇ»
-    "});
+    cx.set_state("ˇ  \n\t ");
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.word_count(cx), 0);
+        assert_eq!(editor.selected_word_count(cx), 0);
+    });
 }
 
 #[gpui::test]
-async fn test_manipulate_text(cx: &mut TestAppContext) {
+async fn test_read_only(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
     let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("«oneˇ» two\nthree");
+    cx.update_editor(|e, cx| {
+        e.copy(&Copy, cx);
+        e.set_read_only(true);
+        e.handle_input("x", cx);
+        e.paste(&Paste, cx);
+        e.backspace(&Backspace, cx);
+        e.cut(&Cut, cx);
+        e.delete_line(&DeleteLine, cx);
+        e.newline(&Newline, cx);
+    });
+    cx.assert_editor_state("«oneˇ» two\nthree");
 
-    // Test convert_to_upper_case()
-    cx.set_state(indoc! {"
-        «hello worldˇ»
-    "});
-    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
-    cx.assert_editor_state(indoc! {"
-        «HELLO WORLDˇ»
-    "});
+    // Movement and copying still work.
+    cx.update_editor(|e, cx| {
+        e.move_right(&MoveRight, cx);
+        e.select_to_end_of_line(
+            &SelectToEndOfLine {
+                stop_at_soft_wraps: true,
+            },
+            cx,
+        );
+        e.copy(&Copy, cx);
+    });
+    cx.assert_editor_state("one« twoˇ»\nthree");
+    assert_eq!(
+        cx.read_from_clipboard().map(|item| item.text().to_owned()),
+        Some(" two".to_owned())
+    );
 
-    // Test convert_to_lower_case()
-    cx.set_state(indoc! {"
-        «HELLO WORLDˇ»
-    "});
-    cx.update_editor(|e, cx| e.convert_to_lower_case(&ConvertToLowerCase, cx));
-    cx.assert_editor_state(indoc! {"
-        «hello worldˇ»
-    "});
+    cx.update_editor(|e, cx| {
+        e.set_read_only(false);
+        e.handle_input("x", cx);
+    });
+    cx.assert_editor_state("onexˇ\nthree");
+}
 
-    // Test multiple line, single selection case
-    // Test code hack that covers the fact that to_case crate doesn't support '\n' as a word boundary
-    cx.set_state(indoc! {"
-        «The quick brown
-        fox jumps over
-        the lazy dogˇ»
-    "});
-    cx.update_editor(|e, cx| e.convert_to_title_case(&ConvertToTitleCase, cx));
-    cx.assert_editor_state(indoc! {"
-        «The Quick Brown
-        Fox Jumps Over
-        The Lazy Dogˇ»
-    "});
+#[gpui::test]
+async fn test_clipboard(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
 
-    // Test multiple line, single selection case
-    // Test code hack that covers the fact that to_case crate doesn't support '\n' as a word boundary
-    cx.set_state(indoc! {"
-        «The quick brown
-        fox jumps over
-        the lazy dogˇ»
-    "});
-    cx.update_editor(|e, cx| e.convert_to_upper_camel_case(&ConvertToUpperCamelCase, cx));
-    cx.assert_editor_state(indoc! {"
-        «TheQuickBrown
-        FoxJumpsOver
-        TheLazyDogˇ»
-    "});
+    let mut cx = EditorTestContext::new(cx).await;
 
-    // From here on out, test more complex cases of manipulate_text()
+    cx.set_state("«one✅ ˇ»two «three ˇ»four «five ˇ»six ");
+    cx.update_editor(|e, cx| e.cut(&Cut, cx));
+    cx.assert_editor_state("ˇtwo ˇfour ˇsix ");
 
-    // Test no selection case - should affect words cursors are in
-    // Cursor at beginning, middle, and end of word
-    cx.set_state(indoc! {"
-        ˇhello big beauˇtiful worldˇ
-    "});
-    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
-    cx.assert_editor_state(indoc! {"
-        «HELLOˇ» big «BEAUTIFULˇ» «WORLDˇ»
-    "});
+    // Paste with three cursors. Each cursor pastes one slice of the clipboard text.
+    cx.set_state("two ˇfour ˇsix ˇ");
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state("two one✅ ˇfour three ˇsix five ˇ");
 
-    // Test multiple selections on a single line and across multiple lines
-    cx.set_state(indoc! {"
-        «Theˇ» quick «brown
-        foxˇ» jumps «overˇ»
-        the «lazyˇ» dog
-    "});
-    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
-    cx.assert_editor_state(indoc! {"
-        «THEˇ» quick «BROWN
-        FOXˇ» jumps «OVERˇ»
-        the «LAZYˇ» dog
-    "});
+    // Paste again but with only two cursors. Since the number of cursors doesn't
+    // match the number of slices in the clipboard, the entire clipboard text
+    // is pasted at each cursor.
+    cx.set_state("ˇtwo one✅ four three six five ˇ");
+    cx.update_editor(|e, cx| {
+        e.handle_input("( ", cx);
+        e.paste(&Paste, cx);
+        e.handle_input(") ", cx);
+    });
+    cx.assert_editor_state(
+        &([
+            "( one✅ ",
+            "three ",
+            "five ) ˇtwo one✅ four three six five ( one✅ ",
+            "three ",
+            "five ) ˇ",
+        ]
+        .join("\n")),
+    );
 
-    // Test case where text length grows
+    // Cut with three selections, one of which is full-line.
     cx.set_state(indoc! {"
-        «tschüßˇ»
-    "});
-    cx.update_editor(|e, cx| e.convert_to_upper_case(&ConvertToUpperCase, cx));
+        1«2ˇ»3
+        4ˇ567
+        «8ˇ»9"});
+    cx.update_editor(|e, cx| e.cut(&Cut, cx));
     cx.assert_editor_state(indoc! {"
-        «TSCHÜSSˇ»
-    "});
+        1ˇ3
+        ˇ9"});
 
-    // Test to make sure we don't crash when text shrinks
+    // Paste with three selections, noticing how the copied selection that was full-line
+    // gets inserted before the second cursor.
     cx.set_state(indoc! {"
-        aaa_bbbˇ
-    "});
-    cx.update_editor(|e, cx| e.convert_to_lower_camel_case(&ConvertToLowerCamelCase, cx));
+        1ˇ3
+        9ˇ
+        «oˇ»ne"});
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
     cx.assert_editor_state(indoc! {"
-        «aaaBbbˇ»
-    "});
+        12ˇ3
+        4567
+        9ˇ
+        8ˇne"});
 
-    // Test to make sure we all aware of the fact that each word can grow and shrink
-    // Final selections should be aware of this fact
+    // Copy with a single cursor only, which writes the whole line into the clipboard.
     cx.set_state(indoc! {"
-        aaa_bˇbb bbˇb_ccc ˇccc_ddd
-    "});
-    cx.update_editor(|e, cx| e.convert_to_lower_camel_case(&ConvertToLowerCamelCase, cx));
+        The quick brown
+        fox juˇmps over
+        the lazy dog"});
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
+    assert_eq!(
+        cx.read_from_clipboard().map(|item| item.text().to_owned()),
+        Some("fox jumps over\n".to_owned())
+    );
+
+    // Paste with three selections, noticing how the copied full-line selection is inserted
+    // before the empty selections but replaces the selection that is non-empty.
+    cx.set_state(indoc! {"
+        Tˇhe quick brown
+        «foˇ»x jumps over
+        tˇhe lazy dog"});
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
     cx.assert_editor_state(indoc! {"
-        «aaaBbbˇ» «bbbCccˇ» «cccDddˇ»
-    "});
+        fox jumps over
+        Tˇhe quick brown
+        fox jumps over
+        ˇx jumps over
+        fox jumps over
+        tˇhe lazy dog"});
 }
 
 #[gpui::test]
-fn test_duplicate_line(cx: &mut TestAppContext) {
+async fn test_rainbow_bracket_depths(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
-    let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
-        build_editor(buffer, cx)
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_brackets_query(indoc! {r#"
+            ("{" @open "}" @close)
+            ("(" @open ")" @close)
+            ("[" @open "]" @close)
+        "#})
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(indoc! {"
+        fn a(b: [u8; 2]) {
+            c(d(e));
+        }ˇ
+    "});
+    cx.executor().run_until_parked();
+
+    // Without the setting, the snapshot carries no bracket depths.
+    cx.update_editor(|editor, cx| {
+        assert_eq!(editor.snapshot(cx).bracket_depths(0..3), Vec::new());
     });
-    _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(0, 0)..DisplayPoint::new(0, 1),
-                DisplayPoint::new(0, 2)..DisplayPoint::new(0, 2),
-                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 0),
-                DisplayPoint::new(3, 0)..DisplayPoint::new(3, 0),
-            ])
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.rainbow_brackets = Some(true);
+            });
         });
-        view.duplicate_line(&DuplicateLine, cx);
-        assert_eq!(view.display_text(cx), "abc\nabc\ndef\ndef\nghi\n\n");
+    });
+    cx.update_editor(|editor, cx| {
+        let bracket =
+            |row, column| DisplayPoint::new(row, column)..DisplayPoint::new(row, column + 1);
         assert_eq!(
-            view.selections.display_ranges(cx),
+            editor.snapshot(cx).bracket_depths(0..3),
             vec![
-                DisplayPoint::new(1, 0)..DisplayPoint::new(1, 1),
-                DisplayPoint::new(1, 2)..DisplayPoint::new(1, 2),
-                DisplayPoint::new(3, 0)..DisplayPoint::new(3, 0),
-                DisplayPoint::new(6, 0)..DisplayPoint::new(6, 0),
+                (bracket(0, 4), 0),
+                (bracket(0, 8), 1),
+                (bracket(0, 14), 1),
+                (bracket(0, 15), 0),
+                (bracket(0, 17), 0),
+                (bracket(1, 5), 1),
+                (bracket(1, 7), 2),
+                (bracket(1, 9), 2),
+                (bracket(1, 10), 1),
+                (bracket(2, 0), 0),
             ]
         );
-    });
 
-    let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple("abc\ndef\nghi\n", cx);
-        build_editor(buffer, cx)
-    });
-    _ = view.update(cx, |view, cx| {
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(0, 1)..DisplayPoint::new(1, 1),
-                DisplayPoint::new(1, 2)..DisplayPoint::new(2, 1),
-            ])
-        });
-        view.duplicate_line(&DuplicateLine, cx);
-        assert_eq!(view.display_text(cx), "abc\ndef\nghi\nabc\ndef\nghi\n");
+        // Only brackets within the requested rows are returned, but their depth
+        // still accounts for the pairs enclosing them.
         assert_eq!(
-            view.selections.display_ranges(cx),
+            editor.snapshot(cx).bracket_depths(1..2),
             vec![
-                DisplayPoint::new(3, 1)..DisplayPoint::new(4, 1),
-                DisplayPoint::new(4, 2)..DisplayPoint::new(5, 1),
+                (bracket(1, 5), 1),
+                (bracket(1, 7), 2),
+                (bracket(1, 9), 2),
+                (bracket(1, 10), 1),
             ]
         );
     });
 }
 
 #[gpui::test]
-fn test_move_line_up_down(cx: &mut TestAppContext) {
+async fn test_content_width(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
-    let view = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple(&sample_text(10, 5, 'a'), cx);
-        build_editor(buffer, cx)
-    });
-    _ = view.update(cx, |view, cx| {
-        view.fold_ranges(
-            vec![
-                Point::new(0, 2)..Point::new(1, 2),
-                Point::new(2, 3)..Point::new(4, 1),
-                Point::new(7, 0)..Point::new(8, 4),
-            ],
-            true,
-            cx,
-        );
-        view.change_selections(None, cx, |s| {
-            s.select_display_ranges([
-                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
-                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
-                DisplayPoint::new(3, 2)..DisplayPoint::new(4, 3),
-                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 2),
-            ])
-        });
-        assert_eq!(
-            view.display_text(cx),
-            "aa⋯bbb\nccc⋯eeee\nfffff\nggggg\n⋯i\njjjjj"
-        );
-
-        view.move_line_up(&MoveLineUp, cx);
-        assert_eq!(
-            view.display_text(cx),
-            "aa⋯bbb\nccc⋯eeee\nggggg\n⋯i\njjjjj\nfffff"
-        );
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("abc\nabcdef\nˇ");
+    let initial_width = cx.update_editor(|editor, cx| {
+        let width = editor.content_width(cx);
+        let snapshot = editor.snapshot(cx);
+        let text_layout_details = editor.text_layout_details(cx);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            vec![
-                DisplayPoint::new(0, 1)..DisplayPoint::new(0, 1),
-                DisplayPoint::new(2, 1)..DisplayPoint::new(2, 1),
-                DisplayPoint::new(2, 2)..DisplayPoint::new(3, 3),
-                DisplayPoint::new(4, 0)..DisplayPoint::new(4, 2)
-            ]
+            width,
+            snapshot.x_for_display_point(DisplayPoint::new(1, 6), &text_layout_details)
         );
+        width
     });
+    assert!(initial_width > px(0.));
 
-    _ = view.update(cx, |view, cx| {
-        view.move_line_down(&MoveLineDown, cx);
-        assert_eq!(
-            view.display_text(cx),
-            "ccc⋯eeee\naa⋯bbb\nfffff\nggggg\n⋯i\njjjjj"
-        );
-        assert_eq!(
-            view.selections.display_ranges(cx),
-            vec![
-                DisplayPoint::new(1, 1)..DisplayPoint::new(1, 1),
-                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
-                DisplayPoint::new(3, 2)..DisplayPoint::new(4, 3),
-                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 2)
-            ]
-        );
-    });
+    cx.update_editor(|editor, cx| editor.insert("abcdefghijklmnop", cx));
+    let new_width = cx.update_editor(|editor, cx| editor.content_width(cx));
+    assert!(new_width > initial_width);
+}
 
-    _ = view.update(cx, |view, cx| {
-        view.move_line_down(&MoveLineDown, cx);
-        assert_eq!(
-            view.display_text(cx),
-            "ccc⋯eeee\nfffff\naa⋯bbb\nggggg\n⋯i\njjjjj"
-        );
+#[gpui::test]
+fn test_highlighted_row_set(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(10, 4, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = editor.update(cx, |editor, _| {
+        assert!(editor.highlighted_row_set().is_empty());
         assert_eq!(
-            view.selections.display_ranges(cx),
-            vec![
-                DisplayPoint::new(2, 1)..DisplayPoint::new(2, 1),
-                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
-                DisplayPoint::new(3, 2)..DisplayPoint::new(4, 3),
-                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 2)
-            ]
+            editor.highlighted_row_ranges(0..10),
+            Vec::<Range<u32>>::new()
         );
-    });
 
-    _ = view.update(cx, |view, cx| {
-        view.move_line_up(&MoveLineUp, cx);
+        editor.set_highlighted_rows([7, 1, 2, 4, 2]);
         assert_eq!(
-            view.display_text(cx),
-            "ccc⋯eeee\naa⋯bbb\nggggg\n⋯i\njjjjj\nfffff"
+            editor.highlighted_row_set().into_iter().collect::<Vec<_>>(),
+            vec![1, 2, 4, 7]
         );
+        assert_eq!(editor.highlighted_row_ranges(0..10), vec![1..3, 4..5, 7..8]);
+        assert_eq!(editor.highlighted_row_ranges(2..5), vec![2..3, 4..5]);
+        assert_eq!(editor.highlighted_rows(), Some(1..8));
+
+        // Both ways of highlighting rows share the same storage.
+        editor.highlight_rows(Some(3..5));
+        assert_eq!(editor.highlighted_row_ranges(0..10), vec![3..5]);
         assert_eq!(
-            view.selections.display_ranges(cx),
-            vec![
-                DisplayPoint::new(1, 1)..DisplayPoint::new(1, 1),
-                DisplayPoint::new(2, 1)..DisplayPoint::new(2, 1),
-                DisplayPoint::new(2, 2)..DisplayPoint::new(3, 3),
-                DisplayPoint::new(4, 0)..DisplayPoint::new(4, 2)
-            ]
+            editor.highlighted_row_set().into_iter().collect::<Vec<_>>(),
+            vec![3, 4]
         );
-    });
-}
 
-#[gpui::test]
-fn test_move_line_up_down_with_blocks(cx: &mut TestAppContext) {
-    init_test(cx, |_| {});
+        editor.set_highlighted_rows([5]);
+        assert_eq!(editor.highlighted_row_ranges(0..10), vec![5..6]);
+        assert_eq!(editor.highlighted_rows(), Some(5..6));
 
-    let editor = cx.add_window(|cx| {
-        let buffer = MultiBuffer::build_simple(&sample_text(10, 5, 'a'), cx);
-        build_editor(buffer, cx)
-    });
-    _ = editor.update(cx, |editor, cx| {
-        let snapshot = editor.buffer.read(cx).snapshot(cx);
-        editor.insert_blocks(
-            [BlockProperties {
-                style: BlockStyle::Fixed,
-                position: snapshot.anchor_after(Point::new(2, 0)),
-                disposition: BlockDisposition::Below,
-                height: 1,
-                render: Arc::new(|_| div().into_any()),
-            }],
-            Some(Autoscroll::fit()),
-            cx,
+        editor.clear_highlighted_rows();
+        assert!(editor.highlighted_row_set().is_empty());
+        assert_eq!(editor.highlighted_rows(), None);
+        assert_eq!(
+            editor.highlighted_row_ranges(0..10),
+            Vec::<Range<u32>>::new()
         );
-        editor.change_selections(None, cx, |s| {
-            s.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
-        });
-        editor.move_line_down(&MoveLineDown, cx);
     });
 }
 
 #[gpui::test]
-fn test_transpose(cx: &mut TestAppContext) {
+fn test_auto_height_line_count(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
-    _ = cx.add_window(|cx| {
-        let mut editor = build_editor(MultiBuffer::build_simple("abc", cx), cx);
-        editor.set_style(EditorStyle::default(), cx);
-        editor.change_selections(None, cx, |s| s.select_ranges([1..1]));
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bac");
-        assert_eq!(editor.selections.ranges(cx), [2..2]);
-
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bca");
-        assert_eq!(editor.selections.ranges(cx), [3..3]);
-
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bac");
-        assert_eq!(editor.selections.ranges(cx), [3..3]);
-
-        editor
-    });
-
-    _ = cx.add_window(|cx| {
-        let mut editor = build_editor(MultiBuffer::build_simple("abc\nde", cx), cx);
-        editor.set_style(EditorStyle::default(), cx);
-        editor.change_selections(None, cx, |s| s.select_ranges([3..3]));
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "acb\nde");
-        assert_eq!(editor.selections.ranges(cx), [3..3]);
-
-        editor.change_selections(None, cx, |s| s.select_ranges([4..4]));
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "acbd\ne");
-        assert_eq!(editor.selections.ranges(cx), [5..5]);
-
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "acbde\n");
-        assert_eq!(editor.selections.ranges(cx), [6..6]);
+    let editor = cx.add_window(|cx| Editor::auto_height(3, cx));
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(editor.line_count_for_auto_height(cx), 1);
 
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "acbd\ne");
-        assert_eq!(editor.selections.ranges(cx), [6..6]);
+        editor.set_text("a\nb", cx);
+        assert_eq!(editor.line_count_for_auto_height(cx), 2);
 
-        editor
+        editor.set_text(sample_text(10, 1, 'a'), cx);
+        assert_eq!(editor.line_count_for_auto_height(cx), 3);
+        editor.set_scroll_position(point(0., 5.), cx);
     });
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(editor.scroll_position(cx), point(0., 5.));
 
-    _ = cx.add_window(|cx| {
-        let mut editor = build_editor(MultiBuffer::build_simple("abc\nde", cx), cx);
-        editor.set_style(EditorStyle::default(), cx);
-        editor.change_selections(None, cx, |s| s.select_ranges([1..1, 2..2, 4..4]));
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bacd\ne");
-        assert_eq!(editor.selections.ranges(cx), [2..2, 3..3, 5..5]);
-
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bcade\n");
-        assert_eq!(editor.selections.ranges(cx), [3..3, 4..4, 6..6]);
+        editor.set_text("a\nb", cx);
+    });
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(editor.line_count_for_auto_height(cx), 2);
+        assert_eq!(editor.scroll_position(cx), point(0., 0.));
+    });
+}
 
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bcda\ne");
-        assert_eq!(editor.selections.ranges(cx), [4..4, 6..6]);
+#[gpui::test]
+fn test_single_line_editor_rejects_newlines(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
 
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bcade\n");
-        assert_eq!(editor.selections.ranges(cx), [4..4, 6..6]);
+    let editor = cx.add_window(|cx| Editor::single_line(cx));
+    _ = editor.update(cx, |editor, cx| {
+        cx.write_to_clipboard(ClipboardItem::new("a\nb".into()));
+        editor.paste(&Paste, cx);
+        assert_eq!(editor.text(cx), "ab");
+
+        editor.insert("\r\nc\n", cx);
+        assert_eq!(editor.text(cx), "abc");
+        assert_eq!(editor.buffer.read(cx).snapshot(cx).max_point().row, 0);
+
+        // Clipboard metadata from a multi-line copy is ignored as well.
+        editor.select_all(&SelectAll, cx);
+        cx.write_to_clipboard(ClipboardItem::new("d\ne".into()).with_metadata(vec![
+            ClipboardSelection {
+                len: 3,
+                is_entire_line: true,
+                first_line_indent: 0,
+            },
+        ]));
+        editor.paste(&Paste, cx);
+        assert_eq!(editor.text(cx), "de");
+    });
+}
 
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "bcaed\n");
-        assert_eq!(editor.selections.ranges(cx), [5..5, 6..6]);
+#[gpui::test]
+async fn test_paste_above_and_below(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
 
-        editor
+    // Single-line editors don't paste line-wise.
+    let editor = cx.add_window(|cx| Editor::single_line(cx));
+    _ = editor.update(cx, |editor, cx| {
+        cx.write_to_clipboard(ClipboardItem::new("alpha\nbeta".into()));
+        editor.set_text("one", cx);
+        editor.paste_below(&PasteBelow, cx);
+        editor.paste_above(&PasteAbove, cx);
+        assert_eq!(editor.text(cx), "one");
     });
 
-    _ = cx.add_window(|cx| {
-        let mut editor = build_editor(MultiBuffer::build_simple("🍐🏀✋", cx), cx);
-        editor.set_style(EditorStyle::default(), cx);
-        editor.change_selections(None, cx, |s| s.select_ranges([4..4]));
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "🏀🍐✋");
-        assert_eq!(editor.selections.ranges(cx), [8..8]);
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update(|cx| cx.write_to_clipboard(ClipboardItem::new("alpha\nbeta".into())));
+
+    // The clipboard text is inserted as whole lines, even though it wasn't
+    // copied as entire lines.
+    cx.set_state("one\ntwˇo\nthree");
+    cx.update_editor(|e, cx| e.paste_below(&PasteBelow, cx));
+    cx.assert_editor_state("one\ntwo\nˇalpha\nbeta\nthree");
+
+    cx.update_editor(|e, cx| e.paste_above(&PasteAbove, cx));
+    cx.assert_editor_state("one\ntwo\nˇalpha\nbeta\nalpha\nbeta\nthree");
+
+    // Pasting below the last line, with a trailing newline in the clipboard.
+    cx.update(|cx| cx.write_to_clipboard(ClipboardItem::new("four\n".into())));
+    cx.set_state("oˇne\ntwo ˇthree");
+    cx.update_editor(|e, cx| e.paste_below(&PasteBelow, cx));
+    cx.assert_editor_state("one\nˇfour\ntwo three\nˇfour");
+}
 
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "🏀✋🍐");
-        assert_eq!(editor.selections.ranges(cx), [11..11]);
+#[gpui::test]
+async fn test_paste_lines_onto_cursors(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
 
-        editor.transpose(&Default::default(), cx);
-        assert_eq!(editor.text(cx), "🏀🍐✋");
-        assert_eq!(editor.selections.ranges(cx), [11..11]);
+    cx.set_state("«one\ntwo\nthreeˇ»");
+    cx.update_editor(|e, cx| e.copy(&Copy, cx));
 
-        editor
-    });
+    // With one cursor per copied line, each cursor receives one line.
+    cx.set_state("a ˇ\nb ˇ\nc ˇ");
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state("a oneˇ\nb twoˇ\nc threeˇ");
+
+    // Otherwise, the whole text is pasted at each cursor.
+    cx.set_state("a ˇ\nb ˇ");
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state("a one\ntwo\nthreeˇ\nb one\ntwo\nthreeˇ");
 }
 
 #[gpui::test]
-async fn test_clipboard(cx: &mut gpui::TestAppContext) {
+async fn test_paste_normalizes_base_indent(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
 
     let mut cx = EditorTestContext::new(cx).await;
+    cx.update(|cx| {
+        cx.write_to_clipboard(ClipboardItem::new(
+            "            if a {\n                b();\n\n            }".into(),
+        ))
+    });
 
-    cx.set_state("«one✅ ˇ»two «three ˇ»four «five ˇ»six ");
-    cx.update_editor(|e, cx| e.cut(&Cut, cx));
-    cx.assert_editor_state("ˇtwo ˇfour ˇsix ");
-
-    // Paste with three cursors. Each cursor pastes one slice of the clipboard text.
-    cx.set_state("two ˇfour ˇsix ˇ");
+    // The clipboard's base indentation is stripped when pasting at column 0.
+    cx.set_state(indoc! {"
+        ˇ
+        other
+    "});
     cx.update_editor(|e, cx| e.paste(&Paste, cx));
-    cx.assert_editor_state("two one✅ ˇfour three ˇsix five ˇ");
+    cx.assert_editor_state(indoc! {"
+        if a {
+            b();
 
-    // Paste again but with only two cursors. Since the number of cursors doesn't
-    // match the number of slices in the clipboard, the entire clipboard text
-    // is pasted at each cursor.
-    cx.set_state("ˇtwo one✅ four three six five ˇ");
-    cx.update_editor(|e, cx| {
-        e.handle_input("( ", cx);
-        e.paste(&Paste, cx);
-        e.handle_input(") ", cx);
-    });
-    cx.assert_editor_state(
-        &([
-            "( one✅ ",
-            "three ",
-            "five ) ˇtwo one✅ four three six five ( one✅ ",
-            "three ",
-            "five ) ˇ",
-        ]
-        .join("\n")),
-    );
+        }ˇ
+        other
+    "});
 
-    // Cut with three selections, one of which is full-line.
+    // The destination line's indentation is applied to every pasted line.
     cx.set_state(indoc! {"
-        1«2ˇ»3
-        4ˇ567
-        «8ˇ»9"});
-    cx.update_editor(|e, cx| e.cut(&Cut, cx));
+        fn f() {
+            ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
     cx.assert_editor_state(indoc! {"
-        1ˇ3
-        ˇ9"});
+        fn f() {
+            if a {
+                b();
 
-    // Paste with three selections, noticing how the copied selection that was full-line
-    // gets inserted before the second cursor.
+            }ˇ
+        }
+    "});
+
+    // The first line is often copied without its indentation, so it doesn't count
+    // towards the base indentation.
+    cx.update(|cx| {
+        cx.write_to_clipboard(ClipboardItem::new(
+            "if a {\n                b();\n            }".into(),
+        ))
+    });
     cx.set_state(indoc! {"
-        1ˇ3
-        9ˇ
-        «oˇ»ne"});
+        fn f() {
+            ˇ
+        }
+    "});
     cx.update_editor(|e, cx| e.paste(&Paste, cx));
     cx.assert_editor_state(indoc! {"
-        12ˇ3
-        4567
-        9ˇ
-        8ˇne"});
+        fn f() {
+            if a {
+                b();
+            }ˇ
+        }
+    "});
 
-    // Copy with a single cursor only, which writes the whole line into the clipboard.
+    // Text copied within the editor is re-based in the same way.
     cx.set_state(indoc! {"
-        The quick brown
-        fox juˇmps over
-        the lazy dog"});
+        fn f() {
+            if a {
+                «if b {
+                    c();
+                }ˇ»
+            }
+        }
+    "});
     cx.update_editor(|e, cx| e.copy(&Copy, cx));
-    assert_eq!(
-        cx.read_from_clipboard().map(|item| item.text().to_owned()),
-        Some("fox jumps over\n".to_owned())
-    );
-
-    // Paste with three selections, noticing how the copied full-line selection is inserted
-    // before the empty selections but replaces the selection that is non-empty.
     cx.set_state(indoc! {"
-        Tˇhe quick brown
-        «foˇ»x jumps over
-        tˇhe lazy dog"});
+        ˇ
+        other
+    "});
     cx.update_editor(|e, cx| e.paste(&Paste, cx));
     cx.assert_editor_state(indoc! {"
-        fox jumps over
-        Tˇhe quick brown
-        fox jumps over
-        ˇx jumps over
-        fox jumps over
-        tˇhe lazy dog"});
+        if b {
+            c();
+        }ˇ
+        other
+    "});
+
+    // Single-line text is inserted unchanged.
+    cx.update(|cx| cx.write_to_clipboard(ClipboardItem::new("    x".into())));
+    cx.set_state("aˇb");
+    cx.update_editor(|e, cx| e.paste(&Paste, cx));
+    cx.assert_editor_state("a    xˇb");
 }
 
 #[gpui::test]
@@ -3419,6 +6113,184 @@ fn test_select_all(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_add_selections(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("«oneˇ» two three four");
+    cx.update_editor(|editor, cx| {
+        editor.add_selections([8..13, 4..7], None, cx);
+    });
+    cx.assert_editor_state("«oneˇ» «twoˇ» «threeˇ» four");
+
+    // Overlapping selections are merged with the existing ones
+    cx.update_editor(|editor, cx| {
+        editor.add_selections([10..18], None, cx);
+    });
+    cx.assert_editor_state("«oneˇ» «twoˇ» «three fourˇ»");
+}
+
+#[gpui::test]
+async fn test_selected_text(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("«oneˇ» two ˇthree «fouˇ»r\nfive");
+    cx.update_editor(|editor, cx| assert_eq!(editor.selected_text(cx), "one\n\nfou"));
+
+    cx.set_state("on«e two\nfiˇ»ve");
+    cx.update_editor(|editor, cx| assert_eq!(editor.selected_text(cx), "e two\nfi"));
+}
+
+#[gpui::test]
+async fn test_select_paragraph(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let text = indoc! {"
+        one
+        two
+
+        three
+        fˇour
+        five
+
+
+        six"};
+
+    cx.set_state(text);
+    cx.update_editor(|editor, cx| editor.select_paragraph(false, cx));
+    cx.assert_editor_state(indoc! {"
+        one
+        two
+
+        «three
+        four
+        five
+        ˇ»
+
+        six"});
+
+    // Around a paragraph, the following blank lines are included.
+    cx.set_state(text);
+    cx.update_editor(|editor, cx| editor.select_paragraph(true, cx));
+    cx.assert_editor_state(indoc! {"
+        one
+        two
+
+        «three
+        four
+        five
+
+
+        ˇ»six"});
+
+    // At the end of the buffer, the preceding blank lines are included instead.
+    cx.set_state("one\n\n\ntwo\nthrˇee");
+    cx.update_editor(|editor, cx| editor.select_paragraph(true, cx));
+    cx.assert_editor_state("one\n«\n\ntwo\nthreeˇ»");
+
+    // On a blank line, the run of blank lines is selected.
+    cx.set_state("one\n\nˇ\ntwo");
+    cx.update_editor(|editor, cx| editor.select_paragraph(false, cx));
+    cx.assert_editor_state("one\n«\n\nˇ»two");
+}
+
+#[gpui::test]
+async fn test_select_sentence(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        First sentence. Dr. Smith went to
+        the store, e.g. for mˇilk! Did he?
+
+        Another paragraph."});
+    cx.update_editor(|editor, cx| editor.select_sentence(cx));
+    cx.assert_editor_state(indoc! {"
+        First sentence. «Dr. Smith went to
+        the store, e.g. for milk!ˇ» Did he?
+
+        Another paragraph."});
+
+    // Sentences don't extend past the end of their paragraph.
+    cx.set_state(indoc! {"
+        One. Two
+        thrˇee
+
+        Four."});
+    cx.update_editor(|editor, cx| editor.select_sentence(cx));
+    cx.assert_editor_state(indoc! {"
+        One. «Two
+        threeˇ»
+
+        Four."});
+
+    // Each cursor selects its own sentence.
+    cx.set_state("ˇOne? Two. Thˇree.");
+    cx.update_editor(|editor, cx| editor.select_sentence(cx));
+    cx.assert_editor_state("«One?ˇ» Two. «Three.ˇ»");
+}
+
+#[gpui::test]
+fn test_delete_paragraph(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    // Folded lines within the paragraph are deleted along with it.
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one\ntwo\nthree\nfour\n\nfive", cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.fold_ranges([Point::new(1, 0)..Point::new(3, 0)], true, cx);
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(0, 1)..Point::new(0, 1)])
+        });
+        view.delete_paragraph(&DeleteParagraph, cx);
+        assert_eq!(view.display_text(cx), "five");
+        assert_eq!(
+            view.selections.ranges(cx),
+            vec![Point::new(0, 0)..Point::new(0, 0)]
+        );
+    });
+
+    // Paragraphs end at excerpt boundaries.
+    let buffer_1 = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "a1\na2\nextra"));
+    let buffer_2 = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "b1\nb2"));
+    let multibuffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
+        multibuffer.push_excerpts(
+            buffer_1.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(1, 2),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer.push_excerpts(
+            buffer_2.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(1, 2),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer
+    });
+    let view = cx.add_window(|cx| build_editor(multibuffer, cx));
+    _ = view.update(cx, |view, cx| {
+        assert_eq!(view.text(cx), "a1\na2\nb1\nb2");
+        view.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 1)..Point::new(1, 1)])
+        });
+        view.delete_paragraph(&DeleteParagraph, cx);
+        assert_eq!(view.text(cx), "\nb1\nb2");
+    });
+    assert_eq!(buffer_1.read_with(cx, |buffer, _| buffer.text()), "\nextra");
+    assert_eq!(buffer_2.read_with(cx, |buffer, _| buffer.text()), "b1\nb2");
+}
+
 #[gpui::test]
 fn test_select_line(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3466,6 +6338,108 @@ fn test_select_line(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_select_word(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("one twˇo three\nfˇour");
+    cx.update_editor(|e, cx| e.select_word(&SelectWord, cx));
+    cx.assert_editor_state("one «twoˇ» three\n«fourˇ»");
+
+    // Non-empty selections are extended to include the word at their head.
+    cx.set_state("«one tˇ»wo three\nfo«ˇur five»");
+    cx.update_editor(|e, cx| e.select_word(&SelectWord, cx));
+    cx.assert_editor_state("«one twoˇ» three\n«ˇfour five»");
+}
+
+#[gpui::test]
+fn test_expand_to_line_boundaries(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(6, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(0, 2)..DisplayPoint::new(1, 3),
+                DisplayPoint::new(3, 1)..DisplayPoint::new(3, 1),
+                DisplayPoint::new(5, 4)..DisplayPoint::new(5, 2),
+            ])
+        });
+        view.expand_to_line_boundaries(&ExpandToLineBoundaries, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(0, 0)..DisplayPoint::new(2, 0),
+                DisplayPoint::new(3, 0)..DisplayPoint::new(4, 0),
+                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 5),
+            ]
+        );
+    });
+
+    // Repeating the action doesn't extend the selections any further
+    _ = view.update(cx, |view, cx| {
+        view.expand_to_line_boundaries(&ExpandToLineBoundaries, cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            vec![
+                DisplayPoint::new(0, 0)..DisplayPoint::new(2, 0),
+                DisplayPoint::new(3, 0)..DisplayPoint::new(4, 0),
+                DisplayPoint::new(5, 0)..DisplayPoint::new(5, 5),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_select_indent_block(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        def foo():
+            a = 1
+            if a:
+                ˇb = 2
+
+            c = 3
+        d = 4
+    "});
+    cx.update_editor(|editor, cx| editor.select_indent_block(&SelectIndentBlock, cx));
+    cx.assert_editor_state(indoc! {"
+        def foo():
+            a = 1
+            if a:
+        «        b = 2ˇ»
+
+            c = 3
+        d = 4
+    "});
+
+    cx.set_state(indoc! {"
+        def foo():
+            a ˇ= 1
+            if a:
+                b = 2
+
+            c = 3
+        d = 4
+    "});
+    cx.update_editor(|editor, cx| editor.select_indent_block(&SelectIndentBlock, cx));
+    cx.assert_editor_state(indoc! {"
+        def foo():
+        «    a = 1
+            if a:
+                b = 2
+
+            c = 3ˇ»
+        d = 4
+    "});
+}
+
 #[gpui::test]
 fn test_split_selection_into_lines(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3820,6 +6794,136 @@ async fn test_select_next(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
 }
 
+#[gpui::test]
+async fn test_select_next_case_insensitive(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("ˇfoo Foo bar FOO");
+
+    let action = SelectNext {
+        replace_newest: false,
+        case_insensitive: true,
+    };
+    cx.update_editor(|e, cx| e.select_next(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» Foo bar FOO");
+
+    cx.update_editor(|e, cx| e.select_next(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» «Fooˇ» bar FOO");
+
+    cx.update_editor(|e, cx| e.select_next(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» «Fooˇ» bar «FOOˇ»");
+
+    // The default action stays case-sensitive.
+    cx.set_state("ˇfoo Foo bar FOO");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» Foo bar FOO");
+
+    // Changing the flag mid-way rebuilds the matcher.
+    cx.set_state("ˇfoo Foo foo FOO");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» Foo «fooˇ» FOO");
+    cx.update_editor(|e, cx| e.select_next(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» Foo «fooˇ» «FOOˇ»");
+
+    // Selecting previous occurrences honors the flag too.
+    cx.set_state("foo Foo bar ˇFOO");
+    let action = SelectPrevious {
+        replace_newest: false,
+        case_insensitive: true,
+    };
+    cx.update_editor(|e, cx| e.select_previous(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("foo Foo bar «FOOˇ»");
+    cx.update_editor(|e, cx| e.select_previous(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("foo «Fooˇ» bar «FOOˇ»");
+    cx.update_editor(|e, cx| e.select_previous(&action, cx))
+        .unwrap();
+    cx.assert_editor_state("«fooˇ» «Fooˇ» bar «FOOˇ»");
+}
+
+#[gpui::test]
+fn test_select_next_across_excerpt_boundaries(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer_1 = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "c\nd\nc"));
+    let buffer_2 = cx.new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), "d\nc\nd"));
+    let multibuffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
+        multibuffer.push_excerpts(
+            buffer_1.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(2, 1),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer.push_excerpts(
+            buffer_2.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(2, 1),
+                primary: None,
+            }],
+            cx,
+        );
+        multibuffer
+    });
+
+    cx.add_window(|cx| {
+        let mut editor = build_editor(multibuffer.clone(), cx);
+        select_ranges(&mut editor, "«c\ndˇ»\nc\nd\nc\nd", cx);
+
+        // The match spanning the join between the two excerpts is skipped.
+        editor.select_next(&SelectNext::default(), cx).unwrap();
+        assert_text_with_selections(&mut editor, "«c\ndˇ»\nc\nd\n«c\ndˇ»", cx);
+
+        editor.select_next(&SelectNext::default(), cx).unwrap();
+        assert_text_with_selections(&mut editor, "«c\ndˇ»\nc\nd\n«c\ndˇ»", cx);
+
+        editor
+    });
+
+    cx.add_window(|cx| {
+        let mut editor = build_editor(multibuffer.clone(), cx);
+        select_ranges(&mut editor, "«c\ndˇ»\nc\nd\nc\nd", cx);
+
+        editor
+            .select_previous(&SelectPrevious::default(), cx)
+            .unwrap();
+        assert_text_with_selections(&mut editor, "«c\ndˇ»\nc\nd\n«c\ndˇ»", cx);
+
+        editor
+            .select_previous(&SelectPrevious::default(), cx)
+            .unwrap();
+        assert_text_with_selections(&mut editor, "«c\ndˇ»\nc\nd\n«c\ndˇ»", cx);
+
+        editor
+    });
+
+    cx.add_window(|cx| {
+        let mut editor = build_editor(multibuffer, cx);
+        select_ranges(&mut editor, "«c\ndˇ»\nc\nd\nc\nd", cx);
+
+        editor
+            .select_all_matches(&SelectAllMatches::default(), cx)
+            .unwrap();
+        assert_text_with_selections(&mut editor, "«c\ndˇ»\nc\nd\n«c\ndˇ»", cx);
+
+        editor
+    });
+}
+
 #[gpui::test]
 async fn test_select_all_matches(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -3832,6 +6936,19 @@ async fn test_select_all_matches(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
 }
 
+#[gpui::test]
+async fn test_select_all_matches_in_large_buffer(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let text = "foo bar foobar\n".repeat(500);
+    cx.set_state(&format!("ˇ{text}"));
+
+    cx.update_editor(|e, cx| e.select_all_matches(&SelectAllMatches::default(), cx))
+        .unwrap();
+    cx.assert_editor_state(&"«fooˇ» bar foobar\n".repeat(500));
+}
+
 #[gpui::test]
 async fn test_select_next_with_multiple_carets(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -3901,6 +7018,36 @@ async fn test_select_previous_with_single_caret(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndef«abcˇ»\n«abcˇ»");
 }
 
+#[gpui::test]
+async fn test_select_previous_wraps_around_wordwise(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("abc\nˇabc abc\ndefabc\nabc");
+
+    cx.update_editor(|e, cx| e.select_previous(&SelectPrevious::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("abc\n«abcˇ» abc\ndefabc\nabc");
+
+    cx.update_editor(|e, cx| e.select_previous(&SelectPrevious::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abcˇ»\n«abcˇ» abc\ndefabc\nabc");
+
+    // Once the start of the buffer is reached, the search wraps around to the end.
+    cx.update_editor(|e, cx| e.select_previous(&SelectPrevious::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abcˇ»\n«abcˇ» abc\ndefabc\n«abcˇ»");
+
+    // Matches inside a larger word are skipped when searching for a whole word.
+    cx.update_editor(|e, cx| e.select_previous(&SelectPrevious::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
+
+    cx.update_editor(|e, cx| e.select_previous(&SelectPrevious::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
+}
+
 #[gpui::test]
 async fn test_select_previous_with_multiple_carets(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -4051,62 +7198,135 @@ async fn test_select_larger_smaller_syntax_node(cx: &mut gpui::TestAppContext) {
     );
 
     _ = view.update(cx, |view, cx| {
-        view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
+        view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
+    });
+    assert_eq!(
+        view.update(cx, |view, cx| view.selections.display_ranges(cx)),
+        &[
+            DisplayPoint::new(0, 23)..DisplayPoint::new(0, 27),
+            DisplayPoint::new(2, 35)..DisplayPoint::new(2, 7),
+            DisplayPoint::new(3, 15)..DisplayPoint::new(3, 21),
+        ]
+    );
+
+    _ = view.update(cx, |view, cx| {
+        view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
+    });
+    assert_eq!(
+        view.update(cx, |view, cx| view.selections.display_ranges(cx)),
+        &[
+            DisplayPoint::new(0, 25)..DisplayPoint::new(0, 25),
+            DisplayPoint::new(2, 24)..DisplayPoint::new(2, 12),
+            DisplayPoint::new(3, 18)..DisplayPoint::new(3, 18),
+        ]
+    );
+
+    // Trying to shrink the selected syntax node one more time has no effect.
+    _ = view.update(cx, |view, cx| {
+        view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
+    });
+    assert_eq!(
+        view.update(cx, |view, cx| view.selections.display_ranges(cx)),
+        &[
+            DisplayPoint::new(0, 25)..DisplayPoint::new(0, 25),
+            DisplayPoint::new(2, 24)..DisplayPoint::new(2, 12),
+            DisplayPoint::new(3, 18)..DisplayPoint::new(3, 18),
+        ]
+    );
+
+    // Ensure that we keep expanding the selection if the larger selection starts or ends within
+    // a fold.
+    _ = view.update(cx, |view, cx| {
+        view.fold_ranges(
+            vec![
+                Point::new(0, 21)..Point::new(0, 24),
+                Point::new(3, 20)..Point::new(3, 22),
+            ],
+            true,
+            cx,
+        );
+        view.select_larger_syntax_node(&SelectLargerSyntaxNode, cx);
+    });
+    assert_eq!(
+        view.update(cx, |view, cx| view.selections.display_ranges(cx)),
+        &[
+            DisplayPoint::new(0, 16)..DisplayPoint::new(0, 28),
+            DisplayPoint::new(2, 35)..DisplayPoint::new(2, 7),
+            DisplayPoint::new(3, 4)..DisplayPoint::new(3, 23),
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_select_smaller_syntax_node_restores_merged_carets(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let language = Arc::new(Language::new(
+        LanguageConfig::default(),
+        Some(tree_sitter_rust::language()),
+    ));
+
+    let text = r#"
+        fn f(a: usize) {
+            let value = a + 1;
+        }
+    "#
+    .unindent();
+
+    let buffer = cx
+        .new_model(|cx| Buffer::new(0, cx.entity_id().as_u64(), text).with_language(language, cx));
+    let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+    let (view, cx) = cx.add_window_view(|cx| build_editor(buffer, cx));
+
+    view.condition::<crate::EditorEvent>(&cx, |view, cx| !view.buffer.read(cx).is_parsing(cx))
+        .await;
+
+    let original_carets = [
+        DisplayPoint::new(0, 5)..DisplayPoint::new(0, 5),
+        DisplayPoint::new(1, 9)..DisplayPoint::new(1, 9),
+        DisplayPoint::new(1, 11)..DisplayPoint::new(1, 11),
+    ];
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges(original_carets.clone())
+        });
+        view.select_larger_syntax_node(&SelectLargerSyntaxNode, cx);
     });
+    // Both carets within `value` expand to the same node and are merged.
     assert_eq!(
         view.update(cx, |view, cx| view.selections.display_ranges(cx)),
         &[
-            DisplayPoint::new(0, 23)..DisplayPoint::new(0, 27),
-            DisplayPoint::new(2, 35)..DisplayPoint::new(2, 7),
-            DisplayPoint::new(3, 15)..DisplayPoint::new(3, 21),
+            DisplayPoint::new(0, 5)..DisplayPoint::new(0, 6),
+            DisplayPoint::new(1, 8)..DisplayPoint::new(1, 13),
         ]
     );
 
     _ = view.update(cx, |view, cx| {
+        view.select_larger_syntax_node(&SelectLargerSyntaxNode, cx);
         view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
     });
     assert_eq!(
         view.update(cx, |view, cx| view.selections.display_ranges(cx)),
         &[
-            DisplayPoint::new(0, 25)..DisplayPoint::new(0, 25),
-            DisplayPoint::new(2, 24)..DisplayPoint::new(2, 12),
-            DisplayPoint::new(3, 18)..DisplayPoint::new(3, 18),
+            DisplayPoint::new(0, 5)..DisplayPoint::new(0, 6),
+            DisplayPoint::new(1, 8)..DisplayPoint::new(1, 13),
         ]
     );
 
-    // Trying to shrink the selected syntax node one more time has no effect.
+    // Shrinking all the way back restores the exact original carets.
     _ = view.update(cx, |view, cx| {
         view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
     });
     assert_eq!(
         view.update(cx, |view, cx| view.selections.display_ranges(cx)),
-        &[
-            DisplayPoint::new(0, 25)..DisplayPoint::new(0, 25),
-            DisplayPoint::new(2, 24)..DisplayPoint::new(2, 12),
-            DisplayPoint::new(3, 18)..DisplayPoint::new(3, 18),
-        ]
+        &original_carets
     );
-
-    // Ensure that we keep expanding the selection if the larger selection starts or ends within
-    // a fold.
     _ = view.update(cx, |view, cx| {
-        view.fold_ranges(
-            vec![
-                Point::new(0, 21)..Point::new(0, 24),
-                Point::new(3, 20)..Point::new(3, 22),
-            ],
-            true,
-            cx,
-        );
-        view.select_larger_syntax_node(&SelectLargerSyntaxNode, cx);
+        view.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx);
     });
     assert_eq!(
         view.update(cx, |view, cx| view.selections.display_ranges(cx)),
-        &[
-            DisplayPoint::new(0, 16)..DisplayPoint::new(0, 28),
-            DisplayPoint::new(2, 35)..DisplayPoint::new(2, 7),
-            DisplayPoint::new(3, 4)..DisplayPoint::new(3, 23),
-        ]
+        &original_carets
     );
 }
 
@@ -4172,6 +7392,57 @@ async fn test_autoindent_selections(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_tab_over_autoclosed_bracket(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            brackets: BracketPairConfig {
+                pairs: vec![BracketPair {
+                    start: "(".to_string(),
+                    end: ")".to_string(),
+                    close: true,
+                    newline: true,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // By default, tab inserts indentation before the closing bracket.
+    cx.set_state("fooˇ");
+    cx.update_editor(|editor, cx| {
+        editor.handle_input("(", cx);
+        editor.tab(&Tab, cx);
+    });
+    cx.assert_editor_state("foo(    ˇ)");
+
+    _ = cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.tab_over_closing_brackets = Some(true);
+            });
+        })
+    });
+
+    cx.set_state("fooˇ");
+    cx.update_editor(|editor, cx| editor.handle_input("(", cx));
+    cx.assert_editor_state("foo(ˇ)");
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("foo()ˇ");
+
+    // Brackets that weren't autoclosed are indented as usual.
+    cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("foo(    ˇ)");
+}
+
 #[gpui::test]
 async fn test_autoclose_pairs(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -4343,6 +7614,48 @@ async fn test_autoclose_pairs(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("a\"\"ˇ");
 }
 
+#[gpui::test]
+async fn test_backspace_deletes_autoclosed_pair(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            brackets: BracketPairConfig {
+                pairs: vec![BracketPair {
+                    start: "(".to_string(),
+                    end: ")".to_string(),
+                    close: true,
+                    newline: false,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    cx.set_state("a ˇ b");
+    cx.update_editor(|editor, cx| {
+        editor.handle_input("(", cx);
+        editor.handle_input("(", cx);
+    });
+    cx.assert_editor_state("a ((ˇ)) b");
+
+    cx.update_editor(|editor, cx| {
+        editor.backspace(&Backspace, cx);
+        assert_eq!(editor.autoclose_regions.len(), 1);
+    });
+    cx.assert_editor_state("a (ˇ) b");
+
+    cx.update_editor(|editor, cx| {
+        editor.backspace(&Backspace, cx);
+        assert!(editor.autoclose_regions.is_empty());
+    });
+    cx.assert_editor_state("a ˇ b");
+}
+
 #[gpui::test]
 async fn test_autoclose_with_embedded_language(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -4834,6 +8147,82 @@ async fn test_surround_with_pair(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_surround_selection_with_parens(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            brackets: BracketPairConfig {
+                pairs: vec![BracketPair {
+                    start: "(".to_string(),
+                    end: ")".to_string(),
+                    close: true,
+                    newline: false,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state("let a = «fooˇ» + «barˇ»;");
+
+    cx.update_editor(|editor, cx| editor.handle_input("(", cx));
+    cx.assert_editor_state("let a = («fooˇ») + («barˇ»);");
+
+    // The surrounding brackets are inserted in a single transaction.
+    cx.update_editor(|editor, cx| editor.undo(&Undo, cx));
+    cx.assert_editor_state("let a = «fooˇ» + «barˇ»;");
+}
+
+#[gpui::test]
+async fn test_surround_and_autoclose_with_mixed_selections(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            brackets: BracketPairConfig {
+                pairs: vec![BracketPair {
+                    start: "(".to_string(),
+                    end: ")".to_string(),
+                    close: true,
+                    newline: true,
+                }],
+                ..Default::default()
+            },
+            autoclose_before: ")".to_string(),
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    cx.set_state(indoc! {"
+        «fooˇ» bar
+        baz ˇ
+    "});
+
+    // Non-empty selections are surrounded, while empty ones are autoclosed.
+    cx.update_editor(|view, cx| view.handle_input("(", cx));
+    cx.assert_editor_state(indoc! {"
+        («fooˇ») bar
+        baz (ˇ)
+    "});
+
+    // Both edits belong to the same transaction.
+    cx.update_editor(|view, cx| view.undo(&Undo, cx));
+    cx.assert_editor_state(indoc! {"
+        «fooˇ» bar
+        baz ˇ
+    "});
+}
+
 #[gpui::test]
 async fn test_delete_autoclose_pair(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -4941,6 +8330,60 @@ async fn test_delete_autoclose_pair(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_tab_expands_templates(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_editor(|editor, _| {
+        editor.register_template("log", "console.log(${1:value});$0");
+    });
+
+    cx.set_state("ˇ");
+    cx.update_editor(|editor, cx| editor.handle_input("log", cx));
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("console.log(«valueˇ»);");
+
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("console.log(value);ˇ");
+
+    // Words that aren't triggers are indented as usual.
+    cx.set_state("blogˇ");
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("blog    ˇ");
+}
+
+#[gpui::test]
+async fn test_insert_snippet_text(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state("ˇ");
+    cx.update_editor(|editor, cx| {
+        editor
+            .insert_snippet_text("fn ${1:name}(${2:args}) {\n    $0\n}", cx)
+            .unwrap();
+    });
+    cx.assert_editor_state("fn «nameˇ»(args) {\n    \n}");
+
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("fn name(«argsˇ») {\n    \n}");
+
+    cx.update_editor(|editor, cx| editor.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state("fn «nameˇ»(args) {\n    \n}");
+
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.update_editor(|editor, cx| editor.tab(&Tab, cx));
+    cx.assert_editor_state("fn name(args) {\n    ˇ\n}");
+
+    // Read-only editors are left untouched.
+    cx.set_state("ˇ");
+    cx.update_editor(|editor, cx| {
+        editor.set_read_only(true);
+        editor.insert_snippet_text("fn ${1:name}() {}", cx).unwrap();
+    });
+    cx.assert_editor_state("ˇ");
+}
+
 #[gpui::test]
 async fn test_snippets(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -5812,22 +9255,119 @@ async fn test_toggle_comment(cx: &mut gpui::TestAppContext) {
         }
     "});
 
-    // If a selection span multiple lines, empty lines are not toggled.
+    // If a selection span multiple lines, empty lines are not toggled.
+    cx.set_state(indoc! {"
+        fn a() {
+            «a();
+
+            c();ˇ»
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            // «a();
+
+            // c();ˇ»
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_toggle_comment_with_blank_lines(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.comment_blank_lines = Some(true);
+            });
+        });
+    });
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            line_comments: vec!["// ".into()],
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // Blank lines within the block receive a prefix aligned with the other lines.
+    cx.set_state(indoc! {"
+        fn a() {
+            «a();
+
+            c();ˇ»
+        }
+    "});
+
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            // «a();
+            //•
+            // c();ˇ»
+        }
+    "});
+
+    // The commented blank lines don't prevent the block from being uncommented.
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            «a();
+        ••••
+            c();ˇ»
+        }
+    "});
+}
+
+#[gpui::test]
+async fn test_toggle_comments_blank_lines_payload(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            line_comments: vec!["// ".into()],
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // Without the payload, blank lines within the block are skipped.
     cx.set_state(indoc! {"
         fn a() {
             «a();
 
-            c();ˇ»
+                b();ˇ»
         }
     "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            // «a();
 
+            //     b();ˇ»
+        }
+    "});
     cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
 
+    // With it, they receive a prefix at the block's minimum indentation.
+    let toggle_comments = &ToggleComments {
+        comment_blank_lines: true,
+        ..Default::default()
+    };
+    cx.update_editor(|e, cx| e.toggle_comments(toggle_comments, cx));
     cx.assert_editor_state(indoc! {"
         fn a() {
             // «a();
-
-            // c();ˇ»
+            //•
+            //     b();ˇ»
         }
     "});
 }
@@ -5855,6 +9395,7 @@ async fn test_advance_downward_on_toggle_comment(cx: &mut gpui::TestAppContext)
 
     let toggle_comments = &ToggleComments {
         advance_downwards: true,
+        ..Default::default()
     };
 
     // Single cursor on one line -> advance
@@ -6091,6 +9632,66 @@ async fn test_toggle_block_comment(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_toggle_inline_block_comment(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            line_comments: vec!["// ".into()],
+            block_comment: Some(("/* ".into(), " */".into())),
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // Selections within a single line are wrapped in a block comment.
+    cx.set_state(indoc! {"
+        fn a() {
+            let x = «b()ˇ» + c();
+        }
+    "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            let x = /* «b()ˇ» */ + c();
+        }
+    "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            let x = «b()ˇ» + c();
+        }
+    "});
+
+    // Selections that include the delimiters are unwrapped, ignoring surrounding whitespace.
+    cx.set_state(indoc! {"
+        fn a() {
+            let x = b()« /* + c() */ˇ»;
+        }
+    "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            let x = b()« + c()ˇ»;
+        }
+    "});
+
+    // Selections spanning whole lines still use line comments.
+    cx.set_state(indoc! {"
+        fn a() {
+            «let x = b();ˇ»
+        }
+    "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            // «let x = b();ˇ»
+        }
+    "});
+}
+
 #[gpui::test]
 fn test_editing_disjoint_excerpts(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -6962,6 +10563,270 @@ async fn go_to_prev_overlapping_diagnostic(
     "});
 }
 
+#[gpui::test]
+async fn go_to_diagnostic_cycles_in_both_directions(
+    executor: BackgroundExecutor,
+    cx: &mut gpui::TestAppContext,
+) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let project = cx.update_editor(|editor, _| editor.project.clone().unwrap());
+
+    cx.set_state(indoc! {"
+        ˇlet one = 1;
+        let two = 2;
+        let three = 3;
+    "});
+
+    _ = cx.update(|cx| {
+        _ = project.update(cx, |project, cx| {
+            project
+                .update_diagnostics(
+                    LanguageServerId(0),
+                    lsp::PublishDiagnosticsParams {
+                        uri: lsp::Url::from_file_path("/root/file").unwrap(),
+                        version: None,
+                        diagnostics: [(0, 7), (1, 7), (2, 9)]
+                            .into_iter()
+                            .map(|(row, end_column)| lsp::Diagnostic {
+                                range: lsp::Range::new(
+                                    lsp::Position::new(row, 4),
+                                    lsp::Position::new(row, end_column),
+                                ),
+                                severity: Some(lsp::DiagnosticSeverity::ERROR),
+                                ..Default::default()
+                            })
+                            .collect(),
+                    },
+                    &[],
+                    cx,
+                )
+                .unwrap()
+        });
+    });
+
+    executor.run_until_parked();
+
+    let one = indoc! {"
+        let ˇone = 1;
+        let two = 2;
+        let three = 3;
+    "};
+    let two = indoc! {"
+        let one = 1;
+        let ˇtwo = 2;
+        let three = 3;
+    "};
+    let three = indoc! {"
+        let one = 1;
+        let two = 2;
+        let ˇthree = 3;
+    "};
+
+    // Forward, wrapping around to the start of the buffer.
+    for expected_state in [one, two, three, one] {
+        cx.update_editor(|editor, cx| editor.go_to_diagnostic(&GoToDiagnostic, cx));
+        cx.assert_editor_state(expected_state);
+    }
+
+    // Backward, wrapping around to the end of the buffer.
+    for expected_state in [three, two, one, three] {
+        cx.update_editor(|editor, cx| editor.go_to_prev_diagnostic(&GoToPrevDiagnostic, cx));
+        cx.assert_editor_state(expected_state);
+    }
+}
+
+#[gpui::test]
+async fn go_to_diagnostic_with_severity(
+    executor: BackgroundExecutor,
+    cx: &mut gpui::TestAppContext,
+) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let project = cx.update_editor(|editor, _| editor.project.clone().unwrap());
+
+    cx.set_state(indoc! {"
+        ˇlet a = 1;
+        let b = 2;
+        let c = 3;
+        let d = 4;
+    "});
+
+    _ = cx.update(|cx| {
+        _ = project.update(cx, |project, cx| {
+            project
+                .update_diagnostics(
+                    LanguageServerId(0),
+                    lsp::PublishDiagnosticsParams {
+                        uri: lsp::Url::from_file_path("/root/file").unwrap(),
+                        version: None,
+                        diagnostics: [
+                            lsp::DiagnosticSeverity::ERROR,
+                            lsp::DiagnosticSeverity::WARNING,
+                            lsp::DiagnosticSeverity::HINT,
+                            lsp::DiagnosticSeverity::ERROR,
+                        ]
+                        .into_iter()
+                        .enumerate()
+                        .map(|(row, severity)| lsp::Diagnostic {
+                            range: lsp::Range::new(
+                                lsp::Position::new(row as u32, 4),
+                                lsp::Position::new(row as u32, 5),
+                            ),
+                            severity: Some(severity),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    },
+                    &[],
+                    cx,
+                )
+                .unwrap()
+        });
+    });
+
+    executor.run_until_parked();
+
+    let cursor_row = |cx: &mut EditorTestContext| {
+        cx.update_editor(|editor, cx| editor.selections.newest::<Point>(cx).head().row)
+    };
+
+    // Plain navigation stops at errors and warnings.
+    let mut rows = Vec::new();
+    for _ in 0..4 {
+        cx.update_editor(|editor, cx| editor.go_to_diagnostic(&GoToDiagnostic, cx));
+        rows.push(cursor_row(&mut cx));
+    }
+    assert_eq!(rows, [0, 1, 3, 0]);
+
+    // Error-only navigation skips warnings and hints, in both directions.
+    let errors_only = MinDiagnosticSeverity::Error;
+    let mut rows = Vec::new();
+    for _ in 0..3 {
+        cx.update_editor(|editor, cx| {
+            editor.go_to_diagnostic_with_severity(
+                &GoToDiagnosticWithSeverity {
+                    min_severity: errors_only,
+                },
+                cx,
+            )
+        });
+        rows.push(cursor_row(&mut cx));
+    }
+    assert_eq!(rows, [3, 0, 3]);
+
+    let mut rows = Vec::new();
+    for _ in 0..3 {
+        cx.update_editor(|editor, cx| {
+            editor.go_to_prev_diagnostic_with_severity(
+                &GoToPrevDiagnosticWithSeverity {
+                    min_severity: errors_only,
+                },
+                cx,
+            )
+        });
+        rows.push(cursor_row(&mut cx));
+    }
+    assert_eq!(rows, [0, 3, 0]);
+
+    // Including hints stops at every diagnostic.
+    let mut rows = Vec::new();
+    for _ in 0..4 {
+        cx.update_editor(|editor, cx| {
+            editor.go_to_diagnostic_with_severity(
+                &GoToDiagnosticWithSeverity {
+                    min_severity: MinDiagnosticSeverity::Hint,
+                },
+                cx,
+            )
+        });
+        rows.push(cursor_row(&mut cx));
+    }
+    assert_eq!(rows, [1, 2, 3, 0]);
+}
+
+#[gpui::test]
+async fn test_activate_diagnostics_distinguishes_primary_block(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state(indoc! {"
+        ˇfn a() {
+            let x = 1;
+            let y = x;
+        }
+    "});
+
+    let related_range = cx.text_anchor_range(indoc! {"
+        fn a() {
+            let «x» = 1;
+            let y = x;
+        }
+    "});
+    let primary_range = cx.text_anchor_range(indoc! {"
+        fn a() {
+            let x = 1;
+            let y = «x»;
+        }
+    "});
+    cx.update_buffer(|buffer, cx| {
+        let snapshot = buffer.text_snapshot();
+        let set = language::DiagnosticSet::from_sorted_entries(
+            vec![
+                language::DiagnosticEntry {
+                    range: related_range,
+                    diagnostic: Diagnostic {
+                        message: "`x` defined here".to_string(),
+                        severity: DiagnosticSeverity::INFORMATION,
+                        group_id: 0,
+                        is_primary: false,
+                        ..Default::default()
+                    },
+                },
+                language::DiagnosticEntry {
+                    range: primary_range,
+                    diagnostic: Diagnostic {
+                        message: "unused variable `y`".to_string(),
+                        severity: DiagnosticSeverity::WARNING,
+                        group_id: 0,
+                        is_primary: true,
+                        ..Default::default()
+                    },
+                },
+            ],
+            &snapshot,
+        );
+        buffer.update_diagnostics(LanguageServerId(0), set, cx);
+    });
+
+    cx.update_editor(|editor, cx| {
+        assert!(editor.activate_diagnostics(0, cx));
+        let snapshot = editor.snapshot(cx);
+        let active_diagnostics = editor.active_diagnostics.as_ref().unwrap();
+        assert_eq!(active_diagnostics.blocks.len(), 2);
+
+        let buffer = &snapshot.buffer_snapshot;
+        let mut blocks = snapshot
+            .display_snapshot
+            .blocks_in_range(0..snapshot.max_point().row() + 1)
+            .filter_map(|(_, block)| match block {
+                TransformBlock::Custom(block) => {
+                    let diagnostic = &active_diagnostics.blocks[&block.id()];
+                    Some((block.position().to_point(buffer), diagnostic.is_primary))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|(position, _)| *position);
+        assert_eq!(
+            blocks,
+            [(Point::new(1, 8), false), (Point::new(2, 12), true)]
+        );
+    });
+}
+
 #[gpui::test]
 async fn go_to_hunk(executor: BackgroundExecutor, cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -7173,6 +11038,147 @@ async fn test_move_to_enclosing_bracket(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_delete_to_enclosing_bracket(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_typescript(Default::default(), cx).await;
+
+    // Deletes towards the nearer bracket, preferring the closing one.
+    cx.set_state("const x = {abˇcd};");
+    cx.update_editor(|editor, cx| {
+        editor.delete_to_enclosing_bracket(&DeleteToEnclosingBracket, cx)
+    });
+    cx.assert_editor_state("const x = {abˇ};");
+
+    // Only the innermost pair is considered, and each cursor is handled separately.
+    cx.set_state("foo(aˇbcd, [1ˇ, 2, 3])");
+    cx.update_editor(|editor, cx| {
+        editor.delete_to_enclosing_bracket(&DeleteToEnclosingBracket, cx)
+    });
+    cx.assert_editor_state("foo(ˇbcd, [ˇ, 2, 3])");
+
+    // Cursors outside of any brackets are left alone.
+    cx.set_state("ˇfoo(a)");
+    cx.update_editor(|editor, cx| {
+        editor.delete_to_enclosing_bracket(&DeleteToEnclosingBracket, cx)
+    });
+    cx.assert_editor_state("ˇfoo(a)");
+}
+
+#[gpui::test]
+async fn test_select_enclosing_brackets(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_typescript(Default::default(), cx).await;
+    cx.set_state("console.log(foo(1, ˇ2), [3]);");
+
+    cx.update_editor(|editor, cx| editor.select_enclosing_brackets(&SelectEnclosingBrackets, cx));
+    cx.assert_editor_state("console.log(foo(«1, 2ˇ»), [3]);");
+
+    // Repeated invocations expand to the next outer pair
+    cx.update_editor(|editor, cx| editor.select_enclosing_brackets(&SelectEnclosingBrackets, cx));
+    cx.assert_editor_state("console.log(«foo(1, 2), [3]ˇ»);");
+
+    // Without an enclosing pair, the selection is left unchanged
+    cx.update_editor(|editor, cx| editor.select_enclosing_brackets(&SelectEnclosingBrackets, cx));
+    cx.assert_editor_state("console.log(«foo(1, 2), [3]ˇ»);");
+
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state("console.log(foo(«1, 2ˇ»), [3]);");
+}
+
+#[gpui::test]
+async fn test_select_enclosing_bracket_pair(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_typescript(Default::default(), cx).await;
+    cx.set_state("console.log(foo(1, ˇ2), [3]);");
+
+    cx.update_editor(|editor, cx| {
+        editor.select_enclosing_bracket_pair(&SelectEnclosingBracketPair, cx)
+    });
+    cx.assert_editor_state("console.log(foo«(1, 2)ˇ», [3]);");
+
+    // Repeated invocations expand to the next outer pair
+    cx.update_editor(|editor, cx| {
+        editor.select_enclosing_bracket_pair(&SelectEnclosingBracketPair, cx)
+    });
+    cx.assert_editor_state("console.log«(foo(1, 2), [3])ˇ»;");
+
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state("console.log(foo«(1, 2)ˇ», [3]);");
+
+    // Changing the selections by other means discards the expansion history
+    cx.update_editor(|editor, cx| editor.move_left(&MoveLeft, cx));
+    cx.assert_editor_state("console.log(fooˇ(1, 2), [3]);");
+    cx.update_editor(|editor, cx| editor.select_smaller_syntax_node(&SelectSmallerSyntaxNode, cx));
+    cx.assert_editor_state("console.log(fooˇ(1, 2), [3]);");
+}
+
+#[gpui::test]
+async fn test_select_text_object_toggles_inner_and_around(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_typescript(Default::default(), cx).await;
+    cx.set_state("console.log(foo(1, ˇ2), [3]);");
+
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::Brackets, cx));
+    cx.assert_editor_state("console.log(foo(«1, 2ˇ»), [3]);");
+
+    // Selecting the same object again toggles between inner and around
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::Brackets, cx));
+    cx.assert_editor_state("console.log(foo«(1, 2)ˇ», [3]);");
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::Brackets, cx));
+    cx.assert_editor_state("console.log(foo(«1, 2ˇ»), [3]);");
+
+    // Selecting a different object starts over with its inner range
+    cx.set_state("let a = \"one twˇo\";");
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::Word, cx));
+    cx.assert_editor_state("let a = \"one «twoˇ»\";");
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::Word, cx));
+    cx.assert_editor_state("let a = \"one« twoˇ»\";");
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::String, cx));
+    cx.assert_editor_state("let a = \"«one twoˇ»\";");
+    cx.update_editor(|editor, cx| editor.select_text_object(TextObject::String, cx));
+    cx.assert_editor_state("let a = «\"one two\"ˇ»;");
+}
+
+#[gpui::test]
+async fn test_select_next_pair(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.set_state("ˇa(b, c(d)); e(f);");
+
+    cx.update_editor(|editor, cx| editor.select_next_pair('(', ')', cx));
+    cx.assert_editor_state("a(«b, c(d)ˇ»); e(f);");
+
+    // Repeated invocations step through the following pairs
+    cx.update_editor(|editor, cx| editor.select_next_pair('(', ')', cx));
+    cx.assert_editor_state("a(b, c(d)); e(«fˇ»);");
+
+    // Without a following pair, the selection is left unchanged
+    cx.update_editor(|editor, cx| editor.select_next_pair('(', ')', cx));
+    cx.assert_editor_state("a(b, c(d)); e(«fˇ»);");
+
+    // Unbalanced pairs are not selected
+    cx.set_state("ˇ[a] (b");
+    cx.update_editor(|editor, cx| editor.select_next_pair('(', ')', cx));
+    cx.assert_editor_state("ˇ[a] (b");
+    cx.update_editor(|editor, cx| editor.select_next_pair('[', ']', cx));
+    cx.assert_editor_state("[«aˇ»] (b");
+
+    // Symmetric delimiters pair up alternate occurrences
+    cx.set_state("ˇa = \"b\" + \"c\";");
+    cx.update_editor(|editor, cx| editor.select_next_pair('"', '"', cx));
+    cx.assert_editor_state("a = \"«bˇ»\" + \"c\";");
+    cx.update_editor(|editor, cx| editor.select_next_pair('"', '"', cx));
+    cx.assert_editor_state("a = \"b\" + \"«cˇ»\";");
+    cx.update_editor(|editor, cx| editor.select_next_pair('"', '"', cx));
+    cx.assert_editor_state("a = \"b\" + \"«cˇ»\";");
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_copilot(executor: BackgroundExecutor, cx: &mut gpui::TestAppContext) {
     // flaky
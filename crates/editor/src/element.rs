@@ -17,8 +17,9 @@ use crate::{
     mouse_context_menu,
     scroll::scroll_amount::ScrollAmount,
     CursorShape, DisplayPoint, Editor, EditorMode, EditorSettings, EditorSnapshot, EditorStyle,
-    HalfPageDown, HalfPageUp, HoveredCursor, LineDown, LineUp, OpenExcerpts, PageDown, PageUp,
-    Point, SelectPhase, Selection, SoftWrap, ToPoint, CURSORS_VISIBLE_FOR, MAX_LINE_LEN,
+    HalfPageDown, HalfPageUp, HoveredCursor, LineDown, LineNumbersMode, LineUp, OpenExcerpts,
+    PageDown, PageUp, Point, SelectPhase, Selection, SoftWrap, ToPoint, CURSORS_VISIBLE_FOR,
+    MAX_LINE_LEN,
 };
 use anyhow::Result;
 use collections::{BTreeMap, HashMap};
@@ -147,17 +148,22 @@ impl EditorElement {
         register_action(view, cx, Editor::move_down);
         register_action(view, cx, Editor::move_up);
         register_action(view, cx, Editor::cancel);
+        register_action(view, cx, Editor::collapse_to_newest);
+        register_action(view, cx, Editor::swap_selection_ends);
         register_action(view, cx, Editor::newline);
         register_action(view, cx, Editor::newline_above);
         register_action(view, cx, Editor::newline_below);
         register_action(view, cx, Editor::backspace);
         register_action(view, cx, Editor::delete);
+        register_action(view, cx, Editor::clear_selections_content);
         register_action(view, cx, Editor::tab);
         register_action(view, cx, Editor::tab_prev);
         register_action(view, cx, Editor::indent);
         register_action(view, cx, Editor::outdent);
         register_action(view, cx, Editor::delete_line);
+        register_action(view, cx, Editor::delete_paragraph);
         register_action(view, cx, Editor::join_lines);
+        register_action(view, cx, Editor::rewrap);
         register_action(view, cx, Editor::sort_lines_case_sensitive);
         register_action(view, cx, Editor::sort_lines_case_insensitive);
         register_action(view, cx, Editor::reverse_lines);
@@ -169,20 +175,25 @@ impl EditorElement {
         register_action(view, cx, Editor::convert_to_kebab_case);
         register_action(view, cx, Editor::convert_to_upper_camel_case);
         register_action(view, cx, Editor::convert_to_lower_camel_case);
+        register_action(view, cx, Editor::capitalize_word);
         register_action(view, cx, Editor::delete_to_previous_word_start);
         register_action(view, cx, Editor::delete_to_previous_subword_start);
         register_action(view, cx, Editor::delete_to_next_word_end);
         register_action(view, cx, Editor::delete_to_next_subword_end);
         register_action(view, cx, Editor::delete_to_beginning_of_line);
         register_action(view, cx, Editor::delete_to_end_of_line);
+        register_action(view, cx, Editor::delete_to_end_of_buffer_line);
         register_action(view, cx, Editor::cut_to_end_of_line);
         register_action(view, cx, Editor::duplicate_line);
+        register_action(view, cx, Editor::duplicate_line_down);
         register_action(view, cx, Editor::move_line_up);
         register_action(view, cx, Editor::move_line_down);
         register_action(view, cx, Editor::transpose);
         register_action(view, cx, Editor::cut);
         register_action(view, cx, Editor::copy);
         register_action(view, cx, Editor::paste);
+        register_action(view, cx, Editor::paste_above);
+        register_action(view, cx, Editor::paste_below);
         register_action(view, cx, Editor::undo);
         register_action(view, cx, Editor::redo);
         register_action(view, cx, Editor::move_page_up);
@@ -190,6 +201,7 @@ impl EditorElement {
         register_action(view, cx, Editor::next_screen);
         register_action(view, cx, Editor::scroll_cursor_top);
         register_action(view, cx, Editor::scroll_cursor_center);
+        register_action(view, cx, Editor::center_on_cursors);
         register_action(view, cx, Editor::scroll_cursor_bottom);
         register_action(view, cx, |editor, _: &LineDown, cx| {
             editor.scroll_screen(&ScrollAmount::Line(1.), cx)
@@ -215,6 +227,7 @@ impl EditorElement {
         register_action(view, cx, Editor::move_to_next_subword_end);
         register_action(view, cx, Editor::move_to_beginning_of_line);
         register_action(view, cx, Editor::move_to_end_of_line);
+        register_action(view, cx, Editor::move_to_end_of_buffer_line);
         register_action(view, cx, Editor::move_to_start_of_paragraph);
         register_action(view, cx, Editor::move_to_end_of_paragraph);
         register_action(view, cx, Editor::move_to_beginning);
@@ -223,12 +236,15 @@ impl EditorElement {
         register_action(view, cx, Editor::select_down);
         register_action(view, cx, Editor::select_left);
         register_action(view, cx, Editor::select_right);
+        register_action(view, cx, Editor::select_page_up);
+        register_action(view, cx, Editor::select_page_down);
         register_action(view, cx, Editor::select_to_previous_word_start);
         register_action(view, cx, Editor::select_to_previous_subword_start);
         register_action(view, cx, Editor::select_to_next_word_end);
         register_action(view, cx, Editor::select_to_next_subword_end);
         register_action(view, cx, Editor::select_to_beginning_of_line);
         register_action(view, cx, Editor::select_to_end_of_line);
+        register_action(view, cx, Editor::select_to_end_of_buffer_line);
         register_action(view, cx, Editor::select_to_start_of_paragraph);
         register_action(view, cx, Editor::select_to_end_of_paragraph);
         register_action(view, cx, Editor::select_to_beginning);
@@ -238,6 +254,9 @@ impl EditorElement {
             editor.select_all_matches(action, cx).log_err();
         });
         register_action(view, cx, Editor::select_line);
+        register_action(view, cx, Editor::select_word);
+        register_action(view, cx, Editor::expand_to_line_boundaries);
+        register_action(view, cx, Editor::select_indent_block);
         register_action(view, cx, Editor::split_selection_into_lines);
         register_action(view, cx, Editor::add_selection_above);
         register_action(view, cx, Editor::add_selection_below);
@@ -250,11 +269,16 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_comments);
         register_action(view, cx, Editor::select_larger_syntax_node);
         register_action(view, cx, Editor::select_smaller_syntax_node);
+        register_action(view, cx, Editor::select_enclosing_brackets);
+        register_action(view, cx, Editor::select_enclosing_bracket_pair);
         register_action(view, cx, Editor::move_to_enclosing_bracket);
+        register_action(view, cx, Editor::delete_to_enclosing_bracket);
         register_action(view, cx, Editor::undo_selection);
         register_action(view, cx, Editor::redo_selection);
         register_action(view, cx, Editor::go_to_diagnostic);
         register_action(view, cx, Editor::go_to_prev_diagnostic);
+        register_action(view, cx, Editor::go_to_diagnostic_with_severity);
+        register_action(view, cx, Editor::go_to_prev_diagnostic_with_severity);
         register_action(view, cx, Editor::go_to_hunk);
         register_action(view, cx, Editor::go_to_prev_hunk);
         register_action(view, cx, Editor::go_to_definition);
@@ -262,14 +286,21 @@ impl EditorElement {
         register_action(view, cx, Editor::go_to_type_definition);
         register_action(view, cx, Editor::go_to_type_definition_split);
         register_action(view, cx, Editor::fold);
+        register_action(view, cx, Editor::fold_recursive);
+        register_action(view, cx, Editor::fold_all);
         register_action(view, cx, Editor::fold_at);
+        register_action(view, cx, Editor::fold_at_level);
         register_action(view, cx, Editor::unfold_lines);
+        register_action(view, cx, Editor::unfold_all);
+        register_action(view, cx, Editor::unfold_all_automatic);
         register_action(view, cx, Editor::unfold_at);
         register_action(view, cx, Editor::fold_selected_ranges);
         register_action(view, cx, Editor::show_completions);
         register_action(view, cx, Editor::toggle_code_actions);
         register_action(view, cx, Editor::open_excerpts);
         register_action(view, cx, Editor::toggle_soft_wrap);
+        register_action(view, cx, Editor::set_wrap_column);
+        register_action(view, cx, Editor::cycle_line_numbers);
         register_action(view, cx, Editor::toggle_inlay_hints);
         register_action(view, cx, hover_popover::hover);
         register_action(view, cx, Editor::reveal_in_finder);
@@ -674,7 +705,8 @@ impl EditorElement {
                 }
             }
 
-            if let Some(highlighted_rows) = &layout.highlighted_rows {
+            let highlighted_line_bg = cx.theme().colors().editor_highlighted_line_background;
+            for highlighted_rows in &layout.highlighted_rows {
                 let origin = point(
                     bounds.origin.x,
                     bounds.origin.y
@@ -685,7 +717,6 @@ impl EditorElement {
                     bounds.size.width,
                     layout.position_map.line_height * highlighted_rows.len() as f32,
                 );
-                let highlighted_line_bg = cx.theme().colors().editor_highlighted_line_background;
                 cx.paint_quad(fill(Bounds { origin, size }, highlighted_line_bg));
             }
 
@@ -1151,6 +1182,22 @@ impl EditorElement {
                     )
                 }
 
+                if let Some((row, annotation)) = layout.active_line_annotation.as_ref() {
+                    let line = &layout.position_map.line_layouts[(row - start_row) as usize].line;
+                    // Leave some room between the end of the line and the annotation.
+                    let x = line.width + 2. * layout.position_map.em_width
+                        - layout.position_map.scroll_position.x;
+                    let y = *row as f32 * layout.position_map.line_height
+                        - layout.position_map.scroll_position.y;
+                    annotation
+                        .paint(
+                            content_origin + point(x, y),
+                            layout.position_map.line_height,
+                            cx,
+                        )
+                        .log_err();
+                }
+
                 cx.with_z_index(0, |cx| {
                     for cursor in cursors {
                         cursor.paint(content_origin, cx);
@@ -1707,10 +1754,11 @@ impl EditorElement {
     ) {
         let font_size = self.style.text.font_size.to_pixels(cx.rem_size());
         let include_line_numbers = snapshot.mode == EditorMode::Full;
+        let show_line_numbers = snapshot.line_numbers_mode != LineNumbersMode::Hidden;
         let mut shaped_line_numbers = Vec::with_capacity(rows.len());
         let mut fold_statuses = Vec::with_capacity(rows.len());
         let mut line_number = String::new();
-        let is_relative = EditorSettings::get_global(cx).relative_line_numbers;
+        let is_relative = snapshot.line_numbers_mode == LineNumbersMode::Relative;
         let relative_to = if is_relative {
             Some(newest_selection_head.row())
         } else {
@@ -1732,24 +1780,28 @@ impl EditorElement {
             };
             if let Some(buffer_row) = row {
                 if include_line_numbers {
-                    line_number.clear();
-                    let default_number = buffer_row + 1;
-                    let number = relative_rows
-                        .get(&(ix as u32 + rows.start))
-                        .unwrap_or(&default_number);
-                    write!(&mut line_number, "{}", number).unwrap();
-                    let run = TextRun {
-                        len: line_number.len(),
-                        font: self.style.text.font(),
-                        color,
-                        background_color: None,
-                        underline: None,
-                    };
-                    let shaped_line = cx
-                        .text_system()
-                        .shape_line(line_number.clone().into(), font_size, &[run])
-                        .unwrap();
-                    shaped_line_numbers.push(Some(shaped_line));
+                    if show_line_numbers {
+                        line_number.clear();
+                        let default_number = buffer_row + 1;
+                        let number = relative_rows
+                            .get(&(ix as u32 + rows.start))
+                            .unwrap_or(&default_number);
+                        write!(&mut line_number, "{}", number).unwrap();
+                        let run = TextRun {
+                            len: line_number.len(),
+                            font: self.style.text.font(),
+                            color,
+                            background_color: None,
+                            underline: None,
+                        };
+                        let shaped_line = cx
+                            .text_system()
+                            .shape_line(line_number.clone().into(), font_size, &[run])
+                            .unwrap();
+                        shaped_line_numbers.push(Some(shaped_line));
+                    } else {
+                        shaped_line_numbers.push(None);
+                    }
                     fold_statuses.push(
                         is_singleton
                             .then(|| {
@@ -1813,6 +1865,22 @@ impl EditorElement {
                 .collect()
         } else {
             let chunks = snapshot.highlighted_chunks(rows.clone(), true, &self.style);
+            let accents = &cx.theme().styles.accents;
+            let brackets = if accents.is_empty() {
+                Vec::new()
+            } else {
+                snapshot
+                    .bracket_depths(rows.clone())
+                    .into_iter()
+                    .map(|(range, depth)| (range, accents[depth % accents.len()]))
+                    .collect::<Vec<_>>()
+            };
+            let chunks: Box<dyn Iterator<Item = HighlightedChunk<'_>> + '_> = if brackets.is_empty()
+            {
+                Box::new(chunks)
+            } else {
+                Box::new(color_bracket_chunks(chunks, rows.start, &brackets).into_iter())
+            };
             LineWithInvisibles::from_chunks(
                 chunks,
                 &self.style.text,
@@ -1910,7 +1978,7 @@ impl EditorElement {
             let mut active_rows = BTreeMap::new();
             let is_singleton = editor.is_singleton(cx);
 
-            let highlighted_rows = editor.highlighted_rows();
+            let highlighted_rows = editor.highlighted_row_ranges(start_row..end_row);
             let highlighted_ranges = editor.background_highlights_in_range(
                 start_anchor..end_anchor,
                 &snapshot.display_snapshot,
@@ -1933,7 +2001,7 @@ impl EditorElement {
                     let layout = SelectionLayout::new(
                         selection,
                         editor.selections.line_mode,
-                        editor.cursor_shape,
+                        snapshot.cursor_shape,
                         &snapshot.display_snapshot,
                         is_newest,
                         true,
@@ -2045,7 +2113,7 @@ impl EditorElement {
                 SelectionLayout::new(
                     newest,
                     editor.selections.line_mode,
-                    editor.cursor_shape,
+                    snapshot.cursor_shape,
                     &snapshot.display_snapshot,
                     true,
                     true,
@@ -2195,6 +2263,34 @@ impl EditorElement {
             })
             });
 
+            let active_line_annotation = editor
+                .active_line_annotation
+                .as_ref()
+                .and_then(|(line_start, text)| {
+                    let buffer_row = line_start.to_point(&snapshot.buffer_snapshot).row;
+                    let line_end = Point::new(buffer_row, snapshot.buffer_snapshot.line_len(buffer_row));
+                    let display_row = line_end.to_display_point(&snapshot).row();
+                    if !(start_row..end_row).contains(&display_row) {
+                        return None;
+                    }
+
+                    let annotation = cx
+                        .text_system()
+                        .shape_line(
+                            text.clone(),
+                            font_size,
+                            &[TextRun {
+                                len: text.len(),
+                                font: self.style.text.font(),
+                                color: cx.theme().colors().text_muted,
+                                background_color: None,
+                                underline: None,
+                            }],
+                        )
+                        .log_err()?;
+                    Some((display_row, annotation))
+                });
+
             let invisible_symbol_font_size = font_size / 2.;
             let tab_invisible = cx
                 .text_system()
@@ -2263,6 +2359,7 @@ impl EditorElement {
                 fold_indicators,
                 tab_invisible,
                 space_invisible,
+                active_line_annotation,
                 hover_popovers: hover,
             }
         })
@@ -2898,7 +2995,7 @@ impl Element for EditorElement {
                         style.size.height = self.style.text.line_height_in_pixels(rem_size).into();
                         cx.with_element_context(|cx| cx.request_layout(&style, None))
                     }
-                    EditorMode::AutoHeight { max_lines } => {
+                    EditorMode::AutoHeight { .. } => {
                         let editor_handle = cx.view().clone();
                         let max_line_number_width =
                             self.max_line_number_width(&editor.snapshot(cx), cx);
@@ -2910,7 +3007,6 @@ impl Element for EditorElement {
                                         .update(cx, |editor, cx| {
                                             compute_auto_height_layout(
                                                 editor,
-                                                max_lines,
                                                 max_line_number_width,
                                                 known_dimensions,
                                                 cx,
@@ -3033,7 +3129,7 @@ pub struct LayoutState {
     visible_anchor_range: Range<Anchor>,
     visible_display_row_range: Range<u32>,
     active_rows: BTreeMap<u32, bool>,
-    highlighted_rows: Option<Range<u32>>,
+    highlighted_rows: Vec<Range<u32>>,
     line_numbers: Vec<Option<ShapedLine>>,
     display_hunks: Vec<DisplayDiffHunk>,
     blocks: Vec<BlockLayout>,
@@ -3049,6 +3145,7 @@ pub struct LayoutState {
     fold_indicators: Vec<Option<IconButton>>,
     tab_invisible: ShapedLine,
     space_invisible: ShapedLine,
+    active_line_annotation: Option<(u32, ShapedLine)>,
 }
 
 struct CodeActionsIndicator {
@@ -3511,6 +3608,41 @@ mod tests {
         assert_eq!(relative_rows[&2], 3);
     }
 
+    #[test]
+    fn test_color_bracket_chunks() {
+        let red = Hsla::red();
+        let blue = Hsla::blue();
+        let chunks = ["fn a(", "b) {\n", "}"].map(|chunk| HighlightedChunk {
+            chunk,
+            style: None,
+            is_tab: false,
+        });
+        let brackets = [
+            (DisplayPoint::new(2, 4)..DisplayPoint::new(2, 5), red),
+            (DisplayPoint::new(2, 6)..DisplayPoint::new(2, 7), red),
+            (DisplayPoint::new(2, 8)..DisplayPoint::new(2, 9), blue),
+            (DisplayPoint::new(3, 0)..DisplayPoint::new(3, 1), blue),
+        ];
+
+        let colored_chunks = color_bracket_chunks(chunks.into_iter(), 2, &brackets);
+        assert_eq!(
+            colored_chunks
+                .iter()
+                .map(|chunk| (chunk.chunk, chunk.style.and_then(|style| style.color)))
+                .collect::<Vec<_>>(),
+            [
+                ("fn a", None),
+                ("(", Some(red)),
+                ("b", None),
+                (")", Some(red)),
+                (" ", None),
+                ("{", Some(blue)),
+                ("\n", None),
+                ("}", Some(blue)),
+            ]
+        );
+    }
+
     #[gpui::test]
     async fn test_vim_visual_selections(cx: &mut TestAppContext) {
         init_test(cx, |_| {});
@@ -3732,6 +3864,65 @@ mod tests {
         .unwrap()
     }
 
+    #[gpui::test]
+    fn test_layout_of_large_buffer_is_bounded_by_visible_rows(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let row_count = 100_000;
+        let text = (0..row_count)
+            .map(|row| format!("line {row}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let window = cx.add_window(|cx| {
+            let buffer = MultiBuffer::build_simple(&text, cx);
+            Editor::new(EditorMode::Full, buffer, None, cx)
+        });
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+        let mut element = EditorElement::new(&editor, style);
+        let bounds = Bounds {
+            origin: point(px(500.), px(500.)),
+            size: size(px(500.), px(500.)),
+        };
+
+        let state = cx
+            .update_window(window.into(), |view, cx| {
+                cx.with_element_context(|cx| {
+                    cx.with_view_id(view.entity_id(), |cx| element.compute_layout(bounds, cx))
+                })
+            })
+            .unwrap();
+        // Only the rows in the viewport, plus the one bleeding off its bottom edge, are laid out.
+        let visible_rows = (bounds.size.height / state.position_map.line_height).ceil() as usize;
+        let laid_out_rows = state.position_map.line_layouts.len();
+        assert!(laid_out_rows <= visible_rows + 1);
+        assert_eq!(state.line_numbers.len(), laid_out_rows);
+        assert_eq!(state.max_row, row_count - 1);
+        assert_eq!(state.position_map.line_layouts[0].line.text.as_ref(), "line 0");
+
+        // Scrolling far into the buffer lays out the same number of rows, starting at the new top.
+        window
+            .update(cx, |editor, cx| {
+                editor.set_scroll_position(point(0., 90_000.), cx);
+            })
+            .unwrap();
+        let state = cx
+            .update_window(window.into(), |view, cx| {
+                cx.with_element_context(|cx| {
+                    cx.with_view_id(view.entity_id(), |cx| element.compute_layout(bounds, cx))
+                })
+            })
+            .unwrap();
+        assert_eq!(state.position_map.line_layouts.len(), laid_out_rows);
+        assert_eq!(state.position_map.line_layouts[0].line.text.as_ref(), "line 90000");
+        assert_eq!(
+            state.line_numbers[0]
+                .as_ref()
+                .map(|line_number| line_number.text.as_ref()),
+            Some("90001")
+        );
+    }
+
     #[gpui::test]
     fn test_all_invisibles_drawing(cx: &mut TestAppContext) {
         const TAB_SIZE: u32 = 4;
@@ -3943,9 +4134,77 @@ pub fn register_action<T: Action>(
     })
 }
 
+/// Splits the given chunks, which start at the beginning of `start_row`, so that each of
+/// the given brackets gets its own chunk rendered in the bracket's color.
+fn color_bracket_chunks<'a>(
+    chunks: impl Iterator<Item = HighlightedChunk<'a>>,
+    start_row: u32,
+    brackets: &[(Range<DisplayPoint>, Hsla)],
+) -> Vec<HighlightedChunk<'a>> {
+    let mut colored_chunks = Vec::new();
+    let mut position = DisplayPoint::new(start_row, 0);
+    let mut brackets = brackets.iter().peekable();
+    for chunk in chunks {
+        let mut text = chunk.chunk;
+        while !text.is_empty() {
+            while brackets
+                .peek()
+                .map_or(false, |(range, _)| range.end <= position)
+            {
+                brackets.next();
+            }
+
+            let line_len = text.find('\n').unwrap_or(text.len());
+            let (mut len, color) = match brackets.peek() {
+                Some((range, color))
+                    if range.start.row() == position.row()
+                        && ((range.start.column().saturating_sub(position.column())) as usize)
+                            < line_len =>
+                {
+                    let offset = range.start.column().saturating_sub(position.column()) as usize;
+                    if offset > 0 {
+                        (offset, None)
+                    } else {
+                        let len = (range.end.column() - position.column()) as usize;
+                        (len.min(line_len), Some(*color))
+                    }
+                }
+                _ => (line_len, None),
+            };
+
+            let ends_line = color.is_none() && len == line_len && line_len < text.len();
+            if ends_line {
+                len += 1;
+            }
+
+            let style = match color {
+                Some(color) => {
+                    let mut style = chunk.style.unwrap_or_default();
+                    style.color = Some(color);
+                    Some(style)
+                }
+                None => chunk.style,
+            };
+            let (head, tail) = text.split_at(len);
+            colored_chunks.push(HighlightedChunk {
+                chunk: head,
+                style,
+                is_tab: chunk.is_tab,
+            });
+            text = tail;
+
+            if ends_line {
+                position = DisplayPoint::new(position.row() + 1, 0);
+            } else {
+                *position.column_mut() += len as u32;
+            }
+        }
+    }
+    colored_chunks
+}
+
 fn compute_auto_height_layout(
     editor: &mut Editor,
-    max_lines: usize,
     max_line_number_width: Pixels,
     known_dimensions: Size<Option<Pixels>>,
     cx: &mut ViewContext<Editor>,
@@ -3966,7 +4225,7 @@ fn compute_auto_height_layout(
         .size
         .width;
 
-    let mut snapshot = editor.snapshot(cx);
+    let snapshot = editor.snapshot(cx);
     let gutter_dimensions =
         snapshot.gutter_dimensions(font_id, font_size, em_width, max_line_number_width, cx);
 
@@ -3975,14 +4234,9 @@ fn compute_auto_height_layout(
     let overscroll = size(em_width, px(0.));
 
     let editor_width = text_width - gutter_dimensions.margin - overscroll.width - em_width;
-    if editor.set_wrap_width(Some(editor_width), cx) {
-        snapshot = editor.snapshot(cx);
-    }
+    editor.set_wrap_width(Some(editor_width), cx);
 
-    let scroll_height = Pixels::from(snapshot.max_point().row() + 1) * line_height;
-    let height = scroll_height
-        .max(line_height)
-        .min(line_height * max_lines as f32);
+    let height = line_height * editor.line_count_for_auto_height(cx) as f32;
 
     Some(size(width, height))
 }
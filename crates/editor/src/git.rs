@@ -8,6 +8,15 @@ use crate::{
     AnchorRangeExt,
 };
 
+/// The kind of change a buffer row has relative to the buffer's diff base.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffMarker {
+    Added,
+    Modified,
+    /// Lines were deleted right before this row.
+    Deleted,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DisplayDiffHunk {
     Folded {
@@ -88,15 +97,71 @@ pub fn diff_hunk_to_display(hunk: DiffHunk<u32>, snapshot: &DisplaySnapshot) ->
     }
 }
 
+pub fn diff_hunk_to_markers(
+    hunk: DiffHunk<u32>,
+    rows: &Range<u32>,
+) -> impl Iterator<Item = (u32, DiffMarker)> {
+    let (marker_rows, marker) = match hunk.status() {
+        DiffHunkStatus::Added => (hunk.buffer_range, DiffMarker::Added),
+        DiffHunkStatus::Modified => (hunk.buffer_range, DiffMarker::Modified),
+        DiffHunkStatus::Removed => {
+            let row = hunk.buffer_range.start;
+            (row..row + 1, DiffMarker::Deleted)
+        }
+    };
+    let start = marker_rows.start.max(rows.start);
+    let end = marker_rows.end.min(rows.end);
+    (start..end).map(move |row| (row, marker))
+}
+
 #[cfg(test)]
 mod tests {
+    use super::DiffMarker;
     use crate::editor_tests::init_test;
+    use crate::test::build_editor;
     use crate::Point;
     use gpui::{Context, TestAppContext};
     use language::Capability::ReadWrite;
     use multi_buffer::{ExcerptRange, MultiBuffer};
     use project::{FakeFs, Project};
     use unindent::Unindent;
+
+    #[gpui::test]
+    async fn test_diff_markers(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        let project = Project::test(fs, [], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.create_buffer("a\nB\nc\ne\nf\n", None, cx)
+            })
+            .unwrap();
+        let multibuffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+        let editor = cx.add_window(|cx| build_editor(multibuffer, cx));
+
+        // Without a diff base, there are no markers.
+        _ = editor.update(cx, |editor, cx| {
+            assert_eq!(editor.diff_markers(0..5, cx), Vec::new());
+        });
+
+        buffer.update(cx, |buffer, cx| {
+            buffer.set_diff_base(Some("a\nb\nc\nd\ne\n".to_string()), cx);
+        });
+        cx.background_executor.run_until_parked();
+
+        _ = editor.update(cx, |editor, cx| {
+            assert_eq!(
+                editor.diff_markers(0..5, cx),
+                &[
+                    (1, DiffMarker::Modified),
+                    (3, DiffMarker::Deleted),
+                    (4, DiffMarker::Added),
+                ]
+            );
+            assert_eq!(editor.diff_markers(2..4, cx), &[(3, DiffMarker::Deleted)]);
+        });
+    }
     #[gpui::test]
     async fn test_diff_hunks_in_range(cx: &mut TestAppContext) {
         use git::diff::DiffHunkStatus;
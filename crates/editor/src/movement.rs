@@ -201,10 +201,12 @@ pub fn line_beginning(
 /// If `stop_at_soft_boundaries` is true, the returned [`DisplayPoint`] is that of a
 /// displayed line (e.g. if there's soft wrap it's gonna be returned),
 /// otherwise it's always going to be a start of a logical line.
+/// If `stop_at_indent` is true, the line's indentation is returned before its start.
 pub fn indented_line_beginning(
     map: &DisplaySnapshot,
     display_point: DisplayPoint,
     stop_at_soft_boundaries: bool,
+    stop_at_indent: bool,
 ) -> DisplayPoint {
     let point = display_point.to_point(map);
     let soft_line_start = map.clip_point(DisplayPoint::new(display_point.row(), 0), Bias::Right);
@@ -218,7 +220,7 @@ pub fn indented_line_beginning(
     if stop_at_soft_boundaries && soft_line_start > indent_start && display_point != soft_line_start
     {
         soft_line_start
-    } else if stop_at_soft_boundaries && display_point != indent_start {
+    } else if stop_at_indent && display_point != indent_start {
         indent_start
     } else {
         line_start
@@ -246,6 +248,13 @@ pub fn line_end(
     }
 }
 
+/// Returns the position of the end of the buffer line containing `display_point`,
+/// ignoring any soft wrap boundaries in between.
+pub fn buffer_line_end(map: &DisplaySnapshot, display_point: DisplayPoint) -> DisplayPoint {
+    let row = display_point.to_point(map).row;
+    Point::new(row, map.buffer_snapshot.line_len(row)).to_display_point(map)
+}
+
 /// Returns a position of the previous word boundary, where a word character is defined as either
 /// uppercase letter, lowercase letter, '_' character or language-specific word character (like '-' in CSS).
 pub fn previous_word_start(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint {
@@ -260,7 +269,7 @@ pub fn previous_word_start(map: &DisplaySnapshot, point: DisplayPoint) -> Displa
 
 /// Returns a position of the previous subword boundary, where a subword is defined as a run of
 /// word characters of the same "subkind" - where subcharacter kinds are '_' character,
-/// lowerspace characters and uppercase characters.
+/// lowerspace characters, uppercase characters and digits.
 pub fn previous_subword_start(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint {
     let raw_point = point.to_point(map);
     let scope = map.buffer_snapshot.language_scope_at(raw_point);
@@ -268,8 +277,9 @@ pub fn previous_subword_start(map: &DisplaySnapshot, point: DisplayPoint) -> Dis
     find_preceding_boundary(map, point, FindRange::MultiLine, |left, right| {
         let is_word_start =
             char_kind(&scope, left) != char_kind(&scope, right) && !right.is_whitespace();
-        let is_subword_start =
-            left == '_' && right != '_' || left.is_lowercase() && right.is_uppercase();
+        let is_subword_start = left == '_' && right != '_'
+            || left.is_lowercase() && right.is_uppercase()
+            || is_digit_boundary(left, right);
         is_word_start || is_subword_start || left == '\n'
     })
 }
@@ -288,7 +298,7 @@ pub fn next_word_end(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint
 
 /// Returns a position of the next subword boundary, where a subword is defined as a run of
 /// word characters of the same "subkind" - where subcharacter kinds are '_' character,
-/// lowerspace characters and uppercase characters.
+/// lowerspace characters, uppercase characters and digits.
 pub fn next_subword_end(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPoint {
     let raw_point = point.to_point(map);
     let scope = map.buffer_snapshot.language_scope_at(raw_point);
@@ -296,12 +306,18 @@ pub fn next_subword_end(map: &DisplaySnapshot, point: DisplayPoint) -> DisplayPo
     find_boundary(map, point, FindRange::MultiLine, |left, right| {
         let is_word_end =
             (char_kind(&scope, left) != char_kind(&scope, right)) && !left.is_whitespace();
-        let is_subword_end =
-            left != '_' && right == '_' || left.is_lowercase() && right.is_uppercase();
+        let is_subword_end = left != '_' && right == '_'
+            || left.is_lowercase() && right.is_uppercase()
+            || is_digit_boundary(left, right);
         is_word_end || is_subword_end || right == '\n'
     })
 }
 
+/// Whether a subword boundary lies between a letter and a digit.
+fn is_digit_boundary(left: char, right: char) -> bool {
+    left.is_alphabetic() && right.is_ascii_digit() || left.is_ascii_digit() && right.is_alphabetic()
+}
+
 /// Returns a position of the start of the current paragraph, where a paragraph
 /// is defined as a run of non-blank lines.
 pub fn start_of_paragraph(
@@ -571,6 +587,8 @@ mod tests {
         assert("lorem_ˇipsum_ˇdolor", cx);
         assert("loremˇIpˇsum", cx);
         assert("loremˇIpsumˇ", cx);
+        assert("lorem42ˇipˇsum", cx);
+        assert("loremˇ42ˇipsum", cx);
 
         // Word boundaries are still respected
         assert("\nˇ   ˇlorem", cx);
@@ -730,6 +748,8 @@ mod tests {
         assert("loremˇ_ipsumˇ_dolor", cx);
         assert("loˇremˇIpsum", cx);
         assert("loremˇIpsumˇDolor", cx);
+        assert("loˇremˇ42ipsum", cx);
+        assert("loremˇ42ˇipsum", cx);
 
         // Word boundaries are still respected
         assert("\nˇ   loremˇ", cx);
@@ -745,6 +765,25 @@ mod tests {
         assert(" abˇ——ˇcd", cx);
     }
 
+    #[gpui::test]
+    fn test_subword_boundary_sequence(cx: &mut gpui::AppContext) {
+        init_test(cx);
+
+        let (snapshot, display_points) = marked_display_snapshot("ˇfooˇBarˇ_bazˇ", cx);
+        let mut point = display_points[0];
+        for expected in &display_points[1..] {
+            point = next_subword_end(&snapshot, point);
+            assert_eq!(point, *expected);
+        }
+
+        let (snapshot, display_points) = marked_display_snapshot("ˇfooˇBar_ˇbazˇ", cx);
+        let mut point = display_points[3];
+        for expected in display_points[..3].iter().rev() {
+            point = previous_subword_start(&snapshot, point);
+            assert_eq!(point, *expected);
+        }
+    }
+
     #[gpui::test]
     fn test_find_boundary(cx: &mut gpui::AppContext) {
         init_test(cx);
@@ -6,15 +6,17 @@ use crate::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     hover_popover::hide_hover,
     persistence::DB,
-    Anchor, DisplayPoint, Editor, EditorEvent, EditorMode, InlayHintRefreshReason,
+    Anchor, DisplayPoint, Editor, EditorEvent, EditorMode, EditorSettings, InlayHintRefreshReason,
     MultiBufferSnapshot, ToPoint,
 };
 pub use autoscroll::{Autoscroll, AutoscrollStrategy};
 use gpui::{point, px, AppContext, Entity, Pixels, Task, ViewContext};
 use language::{Bias, Point};
 pub use scroll_amount::ScrollAmount;
+use settings::Settings;
 use std::{
     cmp::Ordering,
+    ops::Range,
     time::{Duration, Instant},
 };
 use util::ResultExt;
@@ -23,6 +25,7 @@ use workspace::{ItemId, WorkspaceId};
 pub const SCROLL_EVENT_SEPARATION: Duration = Duration::from_millis(28);
 pub const VERTICAL_SCROLL_MARGIN: f32 = 3.;
 const SCROLLBAR_SHOW_INTERVAL: Duration = Duration::from_secs(1);
+const SCROLL_ANIMATION_FRAME_INTERVAL: Duration = Duration::from_millis(16);
 
 #[derive(Default)]
 pub struct ScrollbarAutoHide(pub bool);
@@ -127,12 +130,14 @@ impl OngoingScroll {
 
 pub struct ScrollManager {
     vertical_scroll_margin: f32,
+    vertical_scroll_margin_overridden: bool,
     anchor: ScrollAnchor,
     ongoing: OngoingScroll,
     autoscroll_request: Option<(Autoscroll, bool)>,
     last_autoscroll: Option<(gpui::Point<f32>, f32, f32, AutoscrollStrategy)>,
     show_scrollbars: bool,
     hide_scrollbar_task: Option<Task<()>>,
+    animation_task: Option<Task<()>>,
     dragging_scrollbar: bool,
     visible_line_count: Option<f32>,
 }
@@ -141,17 +146,25 @@ impl ScrollManager {
     pub fn new() -> Self {
         ScrollManager {
             vertical_scroll_margin: VERTICAL_SCROLL_MARGIN,
+            vertical_scroll_margin_overridden: false,
             anchor: ScrollAnchor::new(),
             ongoing: OngoingScroll::new(),
             autoscroll_request: None,
             show_scrollbars: true,
             hide_scrollbar_task: None,
+            animation_task: None,
             dragging_scrollbar: false,
             last_autoscroll: None,
             visible_line_count: None,
         }
     }
 
+    pub(crate) fn refresh_vertical_scroll_margin(&mut self, cx: &AppContext) {
+        if !self.vertical_scroll_margin_overridden {
+            self.vertical_scroll_margin = EditorSettings::get_global(cx).scroll_margin_rows as f32;
+        }
+    }
+
     pub fn clone_state(&mut self, other: &Self) {
         self.anchor = other.anchor;
         self.ongoing = other.ongoing;
@@ -269,6 +282,10 @@ impl ScrollManager {
         }
     }
 
+    pub fn is_animating(&self) -> bool {
+        self.animation_task.is_some()
+    }
+
     pub fn scrollbars_visible(&self) -> bool {
         self.show_scrollbars
     }
@@ -299,12 +316,20 @@ impl ScrollManager {
 }
 
 impl Editor {
+    /// Returns the vertical scroll margin, clamped to half of the visible lines
+    /// once the editor has been laid out.
     pub fn vertical_scroll_margin(&mut self) -> usize {
-        self.scroll_manager.vertical_scroll_margin as usize
+        let margin = self.scroll_manager.vertical_scroll_margin;
+        match self.scroll_manager.visible_line_count {
+            Some(visible_lines) => margin.min((visible_lines / 2.).floor()) as usize,
+            None => margin as usize,
+        }
     }
 
+    /// Overrides the `scroll_margin_rows` setting for this editor.
     pub fn set_vertical_scroll_margin(&mut self, margin_rows: usize, cx: &mut ViewContext<Self>) {
         self.scroll_manager.vertical_scroll_margin = margin_rows as f32;
+        self.scroll_manager.vertical_scroll_margin_overridden = true;
         cx.notify();
     }
 
@@ -312,6 +337,30 @@ impl Editor {
         self.scroll_manager.visible_line_count
     }
 
+    /// Returns the range of buffer rows that are currently visible, based on the scroll
+    /// position and the number of lines that fit in the editor when it was last laid out.
+    /// The range is empty until the editor has been laid out.
+    pub fn visible_row_range(&self, cx: &mut ViewContext<Self>) -> Range<u32> {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let scroll_top = self.scroll_manager.anchor.scroll_position(&display_map).y;
+        let max_row = display_map.max_point().row();
+        let start_row = (scroll_top.floor() as u32).min(max_row);
+        let end_row = match self.scroll_manager.visible_line_count {
+            Some(visible_line_count) => {
+                ((scroll_top + visible_line_count).ceil() as u32).min(max_row + 1)
+            }
+            None => start_row,
+        };
+
+        let start = DisplayPoint::new(start_row, 0).to_point(&display_map).row;
+        let end = if end_row > max_row {
+            display_map.buffer_snapshot.max_point().row + 1
+        } else {
+            DisplayPoint::new(end_row, 0).to_point(&display_map).row
+        };
+        start..end.max(start)
+    }
+
     pub(crate) fn set_visible_line_count(&mut self, lines: f32, cx: &mut ViewContext<Self>) {
         let opened_first_time = self.scroll_manager.visible_line_count.is_none();
         self.scroll_manager.visible_line_count = Some(lines);
@@ -332,6 +381,7 @@ impl Editor {
         scroll_delta: gpui::Point<f32>,
         cx: &mut ViewContext<Self>,
     ) {
+        self.scroll_manager.animation_task.take();
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let position = self.scroll_manager.anchor.scroll_position(&display_map) + scroll_delta;
         self.set_scroll_position_taking_display_map(position, true, false, display_map, cx);
@@ -345,6 +395,22 @@ impl Editor {
         self.set_scroll_position_internal(scroll_position, true, false, cx);
     }
 
+    /// Scrolls vertically so that the display row containing `anchor` is at the top of
+    /// the editor, keeping the horizontal scroll position.
+    pub fn scroll_to_anchor(&mut self, anchor: Anchor, cx: &mut ViewContext<Self>) {
+        self.scroll_manager.animation_task.take();
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let row = anchor.to_display_point(&display_map).row();
+        let scroll_x = self.scroll_manager.anchor.scroll_position(&display_map).x;
+        self.set_scroll_position_taking_display_map(
+            point(scroll_x, row as f32),
+            true,
+            false,
+            display_map,
+            cx,
+        );
+    }
+
     pub(crate) fn set_scroll_position_internal(
         &mut self,
         scroll_position: gpui::Point<f32>,
@@ -352,10 +418,66 @@ impl Editor {
         autoscroll: bool,
         cx: &mut ViewContext<Self>,
     ) {
+        self.scroll_manager.animation_task.take();
         let map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         self.set_scroll_position_taking_display_map(scroll_position, local, autoscroll, map, cx);
     }
 
+    /// Smoothly scrolls towards `target` over the given `duration`, easing out as
+    /// the target is approached. Any other scroll request cancels the animation.
+    pub fn animate_scroll_to(
+        &mut self,
+        target: gpui::Point<f32>,
+        duration: Duration,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let start = self.scroll_position(cx);
+        let frame_count =
+            (duration.as_millis() / SCROLL_ANIMATION_FRAME_INTERVAL.as_millis()).max(1) as u32;
+        self.scroll_manager.animation_task = Some(cx.spawn(|editor, mut cx| async move {
+            for frame in 1..=frame_count {
+                cx.background_executor()
+                    .timer(SCROLL_ANIMATION_FRAME_INTERVAL)
+                    .await;
+
+                // Ease out cubic: fast at first, slowing down near the target.
+                let progress = 1. - (1. - frame as f32 / frame_count as f32).powi(3);
+                let position = point(
+                    start.x + (target.x - start.x) * progress,
+                    start.y + (target.y - start.y) * progress,
+                );
+                let finished = frame == frame_count;
+                let updated = editor.update(&mut cx, |editor, cx| {
+                    // Only persist the position the animation settles on.
+                    let workspace_id = editor
+                        .workspace
+                        .as_ref()
+                        .filter(|_| finished)
+                        .map(|workspace| workspace.1);
+                    hide_hover(editor, cx);
+                    let map = editor.display_map.update(cx, |map, cx| map.snapshot(cx));
+                    editor.scroll_manager.set_scroll_position(
+                        position,
+                        &map,
+                        true,
+                        false,
+                        workspace_id,
+                        cx,
+                    );
+                    editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                });
+                if updated.is_err() {
+                    return;
+                }
+            }
+            editor
+                .update(&mut cx, |editor, _| {
+                    editor.scroll_manager.animation_task = None;
+                })
+                .ok();
+        }));
+    }
+
     fn set_scroll_position_taking_display_map(
         &mut self,
         scroll_position: gpui::Point<f32>,
@@ -384,6 +506,7 @@ impl Editor {
     }
 
     pub fn set_scroll_anchor(&mut self, scroll_anchor: ScrollAnchor, cx: &mut ViewContext<Self>) {
+        self.scroll_manager.animation_task.take();
         hide_hover(self, cx);
         let workspace_id = self.workspace.as_ref().map(|workspace| workspace.1);
         let top_row = scroll_anchor
@@ -399,6 +522,7 @@ impl Editor {
         scroll_anchor: ScrollAnchor,
         cx: &mut ViewContext<Self>,
     ) {
+        self.scroll_manager.animation_task.take();
         hide_hover(self, cx);
         let workspace_id = self.workspace.as_ref().map(|workspace| workspace.1);
         let snapshot = &self.buffer().read(cx).snapshot(cx);
@@ -1,7 +1,7 @@
 use super::Axis;
 use crate::{
-    Autoscroll, Bias, Editor, EditorMode, NextScreen, ScrollAnchor, ScrollCursorBottom,
-    ScrollCursorCenter, ScrollCursorTop,
+    Autoscroll, Bias, CenterOnCursors, Editor, EditorMode, NextScreen, ScrollAnchor,
+    ScrollCursorBottom, ScrollCursorCenter, ScrollCursorTop,
 };
 use gpui::{Point, ViewContext};
 
@@ -74,6 +74,11 @@ impl Editor {
         )
     }
 
+    /// Centers the viewport on all cursors if they fit in it, or on the newest one otherwise.
+    pub fn center_on_cursors(&mut self, _: &CenterOnCursors, cx: &mut ViewContext<Editor>) {
+        self.request_autoscroll(Autoscroll::center_all(), cx);
+    }
+
     pub fn scroll_cursor_bottom(&mut self, _: &ScrollCursorBottom, cx: &mut ViewContext<Editor>) {
         let snapshot = self.snapshot(cx).display_snapshot;
         let scroll_margin_rows = self.vertical_scroll_margin() as u32;
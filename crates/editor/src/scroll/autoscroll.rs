@@ -5,6 +5,9 @@ use language::Point;
 
 use crate::{display_map::ToDisplayPoint, Editor, EditorMode, LineWithInvisibles};
 
+/// The number of rows kept visible above the target of an explicit `Autoscroll::top()`.
+const TOP_AUTOSCROLL_MARGIN: f32 = 3.;
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum Autoscroll {
     Next,
@@ -23,6 +26,18 @@ impl Autoscroll {
     pub fn center() -> Self {
         Self::Strategy(AutoscrollStrategy::Center)
     }
+
+    /// Centers the viewport on the rows spanned by all cursors if they fit in it,
+    /// or on the newest cursor otherwise.
+    pub fn center_all() -> Self {
+        Self::Strategy(AutoscrollStrategy::CenterAll)
+    }
+
+    /// Places the target row near the top of the viewport, keeping a few rows
+    /// visible above it.
+    pub fn top() -> Self {
+        Self::Strategy(AutoscrollStrategy::TopWithMargin)
+    }
 }
 
 #[derive(PartialEq, Eq, Default, Clone, Copy)]
@@ -31,7 +46,9 @@ pub enum AutoscrollStrategy {
     Newest,
     #[default]
     Center,
+    CenterAll,
     Top,
+    TopWithMargin,
     Bottom,
 }
 
@@ -71,28 +88,40 @@ impl Editor {
 
         let mut target_top;
         let mut target_bottom;
-        if let Some(highlighted_rows) = &self.highlighted_rows {
+        if let Some(highlighted_rows) = self.highlighted_rows.first() {
             target_top = highlighted_rows.start as f32;
             target_bottom = target_top + 1.;
         } else {
             let selections = self.selections.all::<Point>(cx);
-            target_top = selections
-                .first()
-                .unwrap()
-                .head()
-                .to_display_point(&display_map)
-                .row() as f32;
-            target_bottom = selections
-                .last()
-                .unwrap()
-                .head()
-                .to_display_point(&display_map)
-                .row() as f32
-                + 1.0;
+            if autoscroll == Autoscroll::center_all() {
+                let head_rows = selections
+                    .iter()
+                    .map(|selection| selection.head().to_display_point(&display_map).row());
+                let (min_row, max_row) = head_rows.fold((u32::MAX, 0), |(min, max), row| {
+                    (min.min(row), max.max(row))
+                });
+                target_top = min_row as f32;
+                target_bottom = max_row as f32 + 1.0;
+            } else {
+                target_top = selections
+                    .first()
+                    .unwrap()
+                    .head()
+                    .to_display_point(&display_map)
+                    .row() as f32;
+                target_bottom = selections
+                    .last()
+                    .unwrap()
+                    .head()
+                    .to_display_point(&display_map)
+                    .row() as f32
+                    + 1.0;
+            }
 
             // If the selections can't all fit on screen, scroll to the newest.
             if autoscroll == Autoscroll::newest()
-                || autoscroll == Autoscroll::fit() && target_bottom - target_top > visible_lines
+                || (autoscroll == Autoscroll::fit() || autoscroll == Autoscroll::center_all())
+                    && target_bottom - target_top > visible_lines
             {
                 let newest_selection_top = selections
                     .iter()
@@ -133,7 +162,7 @@ impl Editor {
 
         match strategy {
             AutoscrollStrategy::Fit | AutoscrollStrategy::Newest => {
-                let margin = margin.min(self.scroll_manager.vertical_scroll_margin);
+                let margin = margin.min(self.vertical_scroll_margin() as f32);
                 let target_top = (target_top - margin).max(0.0);
                 let target_bottom = target_bottom + margin;
                 let start_row = scroll_position.y;
@@ -151,7 +180,7 @@ impl Editor {
                     self.set_scroll_position_internal(scroll_position, local, true, cx);
                 }
             }
-            AutoscrollStrategy::Center => {
+            AutoscrollStrategy::Center | AutoscrollStrategy::CenterAll => {
                 scroll_position.y = (target_top - margin).max(0.0);
                 self.set_scroll_position_internal(scroll_position, local, true, cx);
             }
@@ -159,6 +188,11 @@ impl Editor {
                 scroll_position.y = (target_top).max(0.0);
                 self.set_scroll_position_internal(scroll_position, local, true, cx);
             }
+            AutoscrollStrategy::TopWithMargin => {
+                let margin = margin.min(TOP_AUTOSCROLL_MARGIN);
+                scroll_position.y = (target_top - margin).max(0.0);
+                self.set_scroll_position_internal(scroll_position, local, true, cx);
+            }
             AutoscrollStrategy::Bottom => {
                 scroll_position.y = (target_bottom - visible_lines).max(0.0);
                 self.set_scroll_position_internal(scroll_position, local, true, cx);
@@ -190,7 +224,7 @@ impl Editor {
         let mut target_left;
         let mut target_right;
 
-        if self.highlighted_rows.is_some() {
+        if !self.highlighted_rows.is_empty() {
             target_left = px(0.);
             target_right = px(0.);
         } else {
@@ -199,19 +233,19 @@ impl Editor {
             for selection in selections {
                 let head = selection.head().to_display_point(&display_map);
                 if head.row() >= start_row && head.row() < start_row + layouts.len() as u32 {
-                    let start_column = head.column().saturating_sub(3);
-                    let end_column = cmp::min(display_map.line_len(head.row()), head.column() + 3);
-                    target_left = target_left.min(
-                        layouts[(head.row() - start_row) as usize]
-                            .line
-                            .x_for_index(start_column as usize),
-                    );
-                    target_right = target_right.max(
-                        layouts[(head.row() - start_row) as usize]
-                            .line
-                            .x_for_index(end_column as usize)
-                            + max_glyph_width,
+                    let line = &layouts[(head.row() - start_row) as usize].line;
+                    // Lines longer than `MAX_LINE_LEN` are only partially laid out, so a
+                    // cursor past that point reveals the end of the laid-out portion.
+                    let laid_out_len = line.len() as u32;
+                    let head_column = cmp::min(head.column(), laid_out_len);
+                    let start_column = head_column.saturating_sub(3);
+                    let end_column = cmp::min(
+                        cmp::min(display_map.line_len(head.row()), laid_out_len),
+                        head_column + 3,
                     );
+                    target_left = target_left.min(line.x_for_index(start_column as usize));
+                    target_right =
+                        target_right.max(line.x_for_index(end_column as usize) + max_glyph_width);
                 }
             }
         }
@@ -29,6 +29,8 @@ pub struct SelectionsCollection {
     buffer: Model<MultiBuffer>,
     pub next_selection_id: usize,
     pub line_mode: bool,
+    /// Whether selections that touch without overlapping are merged into one.
+    merge_adjacent: bool,
     disjoint: Arc<[Selection<Anchor>]>,
     pending: Option<PendingSelection>,
 }
@@ -40,6 +42,7 @@ impl SelectionsCollection {
             buffer,
             next_selection_id: 1,
             line_mode: false,
+            merge_adjacent: true,
             disjoint: Arc::from([]),
             pending: Some(PendingSelection {
                 selection: Selection {
@@ -58,6 +61,10 @@ impl SelectionsCollection {
         self.display_map.update(cx, |map, cx| map.snapshot(cx))
     }
 
+    pub(crate) fn set_merge_adjacent(&mut self, merge_adjacent: bool) {
+        self.merge_adjacent = merge_adjacent;
+    }
+
     fn buffer<'a>(&self, cx: &'a AppContext) -> Ref<'a, MultiBufferSnapshot> {
         self.buffer.read(cx).read(cx)
     }
@@ -65,6 +72,7 @@ impl SelectionsCollection {
     pub fn clone_state(&mut self, other: &SelectionsCollection) {
         self.next_selection_id = other.next_selection_id;
         self.line_mode = other.line_mode;
+        self.merge_adjacent = other.merge_adjacent;
         self.disjoint = other.disjoint.clone();
         self.pending = other.pending.clone();
     }
@@ -112,11 +120,16 @@ impl SelectionsCollection {
             resolve_multiple::<D, _>(disjoint_anchors.iter(), &self.buffer(cx)).peekable();
 
         let mut pending_opt = self.pending::<D>(cx);
+        let merge_adjacent = self.merge_adjacent;
 
         iter::from_fn(move || {
             if let Some(pending) = pending_opt.as_mut() {
                 while let Some(next_selection) = disjoint.peek() {
-                    if pending.start <= next_selection.end && pending.end >= next_selection.start {
+                    if should_merge(
+                        &pending.start..&pending.end,
+                        &next_selection.start..&next_selection.end,
+                        merge_adjacent,
+                    ) {
                         let next_selection = disjoint.next().unwrap();
                         if next_selection.start < pending.start {
                             pending.start = next_selection.start;
@@ -124,7 +137,7 @@ impl SelectionsCollection {
                         if next_selection.end > pending.end {
                             pending.end = next_selection.end;
                         }
-                    } else if next_selection.end < pending.start {
+                    } else if next_selection.end <= pending.start {
                         return disjoint.next();
                     } else {
                         break;
@@ -513,11 +526,15 @@ impl<'a> MutableSelectionsCollection<'a> {
         T: ToOffset + ToPoint + Ord + std::marker::Copy + std::fmt::Debug,
     {
         let buffer = self.buffer.read(self.cx).snapshot(self.cx);
-        selections.sort_unstable_by_key(|s| s.start);
-        // Merge overlapping selections.
+        selections.sort_by_key(|s| (s.start, s.end));
+        // Merge overlapping selections, keeping the id of the newest one.
         let mut i = 1;
         while i < selections.len() {
-            if selections[i - 1].end >= selections[i].start {
+            if should_merge(
+                &selections[i - 1].start..&selections[i - 1].end,
+                &selections[i].start..&selections[i].end,
+                self.collection.merge_adjacent,
+            ) {
                 let removed = selections.remove(i);
                 if removed.start < selections[i - 1].start {
                     selections[i - 1].start = removed.start;
@@ -525,6 +542,9 @@ impl<'a> MutableSelectionsCollection<'a> {
                 if removed.end > selections[i - 1].end {
                     selections[i - 1].end = removed.end;
                 }
+                if removed.id > selections[i - 1].id {
+                    selections[i - 1].id = removed.id;
+                }
             } else {
                 i += 1;
             }
@@ -884,6 +904,16 @@ where
     })
 }
 
+/// Whether two selections overlap enough to be merged. Selections that merely touch
+/// are only merged when `merge_adjacent` is set, but identical selections always are.
+fn should_merge<T: Ord>(a: Range<&T>, b: Range<&T>, merge_adjacent: bool) -> bool {
+    if merge_adjacent {
+        a.start <= b.end && b.start <= a.end
+    } else {
+        (a.start < b.end && b.start < a.end) || a == b
+    }
+}
+
 fn resolve<D: TextDimension + Ord + Sub<D, Output = D>>(
     selection: &Selection<Anchor>,
     buffer: &MultiBufferSnapshot,
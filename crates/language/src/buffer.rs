@@ -22,6 +22,8 @@ use gpui::{AppContext, EventEmitter, HighlightStyle, ModelContext, Task, TaskLab
 use lazy_static::lazy_static;
 use lsp::LanguageServerId;
 use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use similar::{ChangeTag, TextDiff};
 use smallvec::SmallVec;
 use smol::future::yield_now;
@@ -59,6 +61,9 @@ pub use {tree_sitter_rust, tree_sitter_typescript};
 
 pub use lsp::DiagnosticSeverity;
 
+/// The byte order mark that a buffer's text may begin with.
+pub const BYTE_ORDER_MARK: char = '\u{feff}';
+
 lazy_static! {
     /// A label for the background task spawned by the buffer to compute
     /// a diff against the contents of its file.
@@ -150,7 +155,8 @@ pub enum IndentKind {
 }
 
 /// The shape of a selection cursor.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum CursorShape {
     /// A vertical bar
     #[default]
@@ -1447,6 +1453,11 @@ impl Buffer {
         })
     }
 
+    /// Returns whether the buffer's text begins with a byte order mark.
+    pub fn has_bom(&self) -> bool {
+        self.as_rope().chars().next() == Some(BYTE_ORDER_MARK)
+    }
+
     /// Ensures that the buffer ends with a single newline character, and
     /// no other whitespace.
     pub fn ensure_final_newline(&mut self, cx: &mut ModelContext<Self>) {
@@ -1,9 +1,5 @@
 use crate::Vim;
-use editor::{
-    display_map::ToDisplayPoint,
-    scroll::{ScrollAmount, VERTICAL_SCROLL_MARGIN},
-    DisplayPoint, Editor,
-};
+use editor::{display_map::ToDisplayPoint, scroll::ScrollAmount, DisplayPoint, Editor};
 use gpui::{actions, ViewContext};
 use language::Bias;
 use workspace::Workspace;
@@ -77,6 +73,7 @@ fn scroll_editor(
         };
 
         let top_anchor = editor.scroll_manager.anchor().anchor;
+        let vertical_scroll_margin = editor.vertical_scroll_margin() as u32;
 
         editor.change_selections(None, cx, |s| {
             s.move_with(|map, selection| {
@@ -88,8 +85,8 @@ fn scroll_editor(
                     let new_row = top.row() + selection.head().row() - old_top.row();
                     head = map.clip_point(DisplayPoint::new(new_row, head.column()), Bias::Left)
                 }
-                let min_row = top.row() + VERTICAL_SCROLL_MARGIN as u32;
-                let max_row = top.row() + visible_rows - VERTICAL_SCROLL_MARGIN as u32 - 1;
+                let min_row = top.row() + vertical_scroll_margin;
+                let max_row = (top.row() + visible_rows).saturating_sub(vertical_scroll_margin + 1);
 
                 let new_head = if head.row() < min_row {
                     map.clip_point(DisplayPoint::new(min_row, head.column()), Bias::Left)
@@ -207,6 +204,65 @@ mod test {
             )
         });
     }
+
+    #[gpui::test]
+    async fn test_scroll_with_scroll_margin_larger_than_viewport(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        let (line_height, visible_line_count) = cx.editor(|editor, cx| {
+            (
+                editor
+                    .style()
+                    .unwrap()
+                    .text
+                    .line_height_in_pixels(cx.rem_size()),
+                editor.visible_line_count().unwrap(),
+            )
+        });
+
+        let window = cx.window;
+        let margin = cx
+            .update_window(window, |_, cx| {
+                cx.viewport_size().height - line_height * visible_line_count
+            })
+            .unwrap();
+        cx.simulate_window_resize(
+            cx.window,
+            size(px(1000.), margin + 8. * line_height - px(1.0)),
+        );
+        cx.update_editor(|editor, cx| editor.set_vertical_scroll_margin(8, cx));
+
+        cx.set_state(
+            indoc!(
+                "ˇone
+                two
+                three
+                four
+                five
+                six
+                seven
+                eight
+                nine
+                ten
+                eleven
+                twelve
+            "
+            ),
+            Mode::Normal,
+        );
+
+        // The margin is clamped to half of the visible lines.
+        cx.update_editor(|editor, _| assert_eq!(editor.vertical_scroll_margin(), 3));
+        cx.simulate_keystrokes(["ctrl-e"]);
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 1.));
+            assert_eq!(
+                editor.selections.newest(cx).range(),
+                Point::new(4, 0)..Point::new(4, 0)
+            )
+        });
+    }
+
     #[gpui::test]
     async fn test_ctrl_d_u(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
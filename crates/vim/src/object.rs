@@ -463,14 +463,14 @@ fn surrounding_markers(
     let mut opening = None;
 
     if let Some((ch, range)) = movement::chars_after(map, point).next() {
-        if ch == open_marker {
+        if ch == open_marker && !is_escaped(map, range.start) {
             if open_marker == close_marker {
                 let mut total = 0;
-                for (ch, _) in movement::chars_before(map, point) {
+                for (ch, range) in movement::chars_before(map, point) {
                     if ch == '\n' {
                         break;
                     }
-                    if ch == open_marker {
+                    if ch == open_marker && !is_escaped(map, range.start) {
                         total += 1;
                     }
                 }
@@ -489,6 +489,10 @@ fn surrounding_markers(
                 break;
             }
 
+            if (ch == open_marker || ch == close_marker) && is_escaped(map, range.start) {
+                continue;
+            }
+
             if ch == open_marker {
                 if matched_closes == 0 {
                     opening = Some(range);
@@ -503,6 +507,10 @@ fn surrounding_markers(
 
     if opening.is_none() {
         for (ch, range) in movement::chars_after(map, point) {
+            if (ch == open_marker || ch == close_marker) && is_escaped(map, range.start) {
+                continue;
+            }
+
             if ch == open_marker {
                 opening = Some(range);
                 break;
@@ -524,6 +532,10 @@ fn surrounding_markers(
             break;
         }
 
+        if (ch == open_marker || ch == close_marker) && is_escaped(map, range.start) {
+            continue;
+        }
+
         if ch == close_marker {
             if matched_opens == 0 {
                 closing = Some(range);
@@ -591,6 +603,15 @@ fn surrounding_markers(
     )
 }
 
+/// Returns whether the character at `offset` is preceded by an odd number of backslashes.
+fn is_escaped(map: &DisplaySnapshot, offset: usize) -> bool {
+    movement::chars_before(map, offset)
+        .take_while(|(ch, _)| *ch == '\\')
+        .count()
+        % 2
+        == 1
+}
+
 #[cfg(test)]
 mod test {
     use indoc::indoc;
@@ -966,6 +987,23 @@ mod test {
             .await;
     }
 
+    #[gpui::test]
+    async fn test_escaped_surrounding_character_objects(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(r#"let s = "aˇ\"b";"#, Mode::Normal);
+        cx.simulate_keystrokes(["c", "i", "\""]);
+        cx.assert_state(r#"let s = "ˇ";"#, Mode::Insert);
+
+        cx.set_state(r#"let s = "a\"ˇb";"#, Mode::Normal);
+        cx.simulate_keystrokes(["v", "i", "\""]);
+        cx.assert_state(r#"let s = "«a\"bˇ»";"#, Mode::Visual);
+
+        cx.set_state(r#"fn f(a: &str) { g("\(", ˇa) }"#, Mode::Normal);
+        cx.simulate_keystrokes(["v", "i", "("]);
+        cx.assert_state(r#"fn f(a: &str) { g(«"\(", aˇ») }"#, Mode::Visual);
+    }
+
     #[gpui::test]
     async fn test_vertical_bars(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -151,6 +151,22 @@ async fn test_end_of_document_710(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("aˇa\nbb\ncc");
 }
 
+#[gpui::test]
+async fn test_find_with_multiple_cursors(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    // each cursor searches its own line, and stays put when there's no match
+    cx.set_state(indoc! {"ˇa-b-c\nˇabc-d\nˇ-xyz"}, Mode::Normal);
+    cx.simulate_keystrokes(["f", "-"]);
+    cx.assert_editor_state("aˇ-b-c\nabcˇ-d\nˇ-xyz");
+    cx.simulate_keystrokes([";"]);
+    cx.assert_editor_state("a-bˇ-c\nabcˇ-d\nˇ-xyz");
+
+    cx.set_state(indoc! {"a-b-ˇc\nabc-ˇd\n-xyˇz"}, Mode::Normal);
+    cx.simulate_keystrokes(["shift-f", "-"]);
+    cx.assert_editor_state("a-bˇ-c\nabcˇ-d\nˇ-xyz");
+}
+
 #[gpui::test]
 async fn test_indent_outdent(cx: &mut gpui::TestAppContext) {
     let mut cx = VimTestContext::new(cx, true).await;
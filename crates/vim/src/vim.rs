@@ -22,7 +22,7 @@ use gpui::{
     actions, impl_actions, Action, AppContext, EntityId, KeyContext, Subscription, View,
     ViewContext, WeakView, WindowContext,
 };
-use language::{CursorShape, Point, Selection, SelectionGoal};
+use language::{Point, Selection, SelectionGoal};
 pub use mode_indicator::ModeIndicator;
 use motion::Motion;
 use normal::normal_replace;
@@ -585,7 +585,7 @@ impl Vim {
     }
 
     fn unhook_vim_settings(&self, editor: &mut Editor, cx: &mut ViewContext<Editor>) {
-        editor.set_cursor_shape(CursorShape::Bar, cx);
+        editor.reset_cursor_shape(cx);
         editor.set_clip_at_line_ends(false, cx);
         editor.set_input_enabled(true);
         editor.set_autoindent(true);
@@ -87,6 +87,7 @@ pub fn app_menus() -> Vec<Menu<'static>> {
                     "Select Next Occurrence",
                     editor::actions::SelectNext {
                         replace_newest: false,
+                        case_insensitive: false,
                     },
                 ),
                 MenuItem::separator(),